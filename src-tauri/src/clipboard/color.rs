@@ -0,0 +1,101 @@
+//! EveryPaste - Color code parsing
+//!
+//! Recognizes clipboard text that is a single CSS color (`#RRGGBB`, `#RGB`,
+//! `rgb(...)`, `hsl(...)`)
+
+/// Parse a color code into RGB components
+pub fn parse_color(text: &str) -> Option<[u8; 3]> {
+    let text = text.trim();
+    if let Some(hex) = text.strip_prefix('#') {
+        return parse_hex(hex);
+    }
+
+    let lower = text.to_ascii_lowercase();
+    if let Some(args) = function_args(&lower, &["rgb", "rgba"]) {
+        let [r, g, b] = args;
+        return Some([parse_channel(r)?, parse_channel(g)?, parse_channel(b)?]);
+    }
+    if let Some(args) = function_args(&lower, &["hsl", "hsla"]) {
+        let [h, s, l] = args;
+        let hue = h.trim_end_matches("deg").parse::<f64>().ok()?;
+        return Some(hsl_to_rgb(hue, parse_percent(s)?, parse_percent(l)?));
+    }
+    None
+}
+
+/// Format RGB components as "#RRGGBB"
+pub fn to_hex(rgb: [u8; 3]) -> String {
+    format!("#{:02X}{:02X}{:02X}", rgb[0], rgb[1], rgb[2])
+}
+
+/// Parse "RRGGBB" or "RGB" hex digits
+fn parse_hex(hex: &str) -> Option<[u8; 3]> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    match hex.len() {
+        6 => Some([
+            u8::from_str_radix(&hex[0..2], 16).ok()?,
+            u8::from_str_radix(&hex[2..4], 16).ok()?,
+            u8::from_str_radix(&hex[4..6], 16).ok()?,
+        ]),
+        3 => {
+            let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).ok().map(|d| d * 17);
+            Some([digit(0)?, digit(1)?, digit(2)?])
+        }
+        _ => None,
+    }
+}
+
+/// Get the first three arguments of `name(a, b, c)` or `name(a b c / alpha)`
+///
+/// An alpha argument is accepted and ignored
+fn function_args<'a>(text: &'a str, names: &[&str]) -> Option<[&'a str; 3]> {
+    let (name, rest) = text.split_once('(')?;
+    if !names.contains(&name.trim()) {
+        return None;
+    }
+    let inner = rest.strip_suffix(')')?;
+    let inner = inner.split_once('/').map_or(inner, |(color, _alpha)| color);
+    let args: Vec<&str> = inner
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|arg| !arg.is_empty())
+        .collect();
+    match args.len() {
+        3 | 4 => Some([args[0], args[1], args[2]]),
+        _ => None,
+    }
+}
+
+/// Parse an RGB channel (0-255 or a percentage)
+fn parse_channel(value: &str) -> Option<u8> {
+    let channel = match value.strip_suffix('%') {
+        Some(percent) => percent.parse::<f64>().ok()? * 2.55,
+        None => value.parse::<f64>().ok()?,
+    };
+    (0.0..=255.0).contains(&channel).then(|| channel.round() as u8)
+}
+
+/// Parse a percentage into 0.0-1.0
+fn parse_percent(value: &str) -> Option<f64> {
+    let percent = value.strip_suffix('%')?.parse::<f64>().ok()?;
+    (0.0..=100.0).contains(&percent).then_some(percent / 100.0)
+}
+
+/// Convert HSL (hue in degrees, saturation and lightness 0.0-1.0) to RGB
+fn hsl_to_rgb(hue: f64, saturation: f64, lightness: f64) -> [u8; 3] {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let sector = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = lightness - chroma / 2.0;
+    let channel = |v: f64| ((v + m) * 255.0).round().clamp(0.0, 255.0) as u8;
+    [channel(r), channel(g), channel(b)]
+}
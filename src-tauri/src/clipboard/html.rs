@@ -0,0 +1,260 @@
+//! EveryPaste - HTML markup detection
+//!
+//! Recognizes HTML that an application put into the plain-text clipboard slot
+//! and converts to and from the CF_HTML clipboard format
+
+/// Elements without a closing tag
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track", "wbr",
+];
+
+/// Elements whose closing tag starts a new line in the extracted text
+const BLOCK_ELEMENTS: &[&str] = &[
+    "p", "div", "li", "tr", "h1", "h2", "h3", "h4", "h5", "h6", "pre", "blockquote", "table", "ul", "ol",
+];
+
+/// Elements whose content is dropped from the extracted text
+const HIDDEN_ELEMENTS: &[&str] = &["script", "style", "head", "title"];
+
+/// A parsed tag
+struct Tag<'a> {
+    name: String,
+    closing: bool,
+    self_closing: bool,
+    /// Text following the tag up to the next '<'
+    text_after: &'a str,
+}
+
+/// Split markup into tags, returns None if a tag is malformed
+///
+/// Comments and declarations (`<!...>`) are skipped
+fn parse_tags(html: &str) -> Option<(&str, Vec<Tag<'_>>)> {
+    let leading_end = html.find('<').unwrap_or(html.len());
+    let leading = &html[..leading_end];
+    let mut rest = &html[leading_end..];
+    let mut tags = Vec::new();
+
+    while let Some(body) = rest.strip_prefix('<') {
+        let end = if body.starts_with("!--") {
+            body.find("-->").map(|i| i + 3)?
+        } else {
+            body.find('>').map(|i| i + 1)?
+        };
+        let inner = &body[..end - 1];
+        rest = &body[end..];
+        let text_end = rest.find('<').unwrap_or(rest.len());
+        let text_after = &rest[..text_end];
+        rest = &rest[text_end..];
+
+        if inner.starts_with('!') || inner.starts_with('?') {
+            // Comment, doctype or processing instruction: keep only the text
+            tags.push(Tag { name: String::new(), closing: false, self_closing: true, text_after });
+            continue;
+        }
+
+        let closing = inner.starts_with('/');
+        let self_closing = inner.ends_with('/');
+        let name: String = inner
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        if name.is_empty() || !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            return None;
+        }
+
+        tags.push(Tag { name, closing, self_closing, text_after });
+    }
+
+    Some((leading, tags))
+}
+
+/// Whether text is clearly HTML markup rather than text that mentions tags
+///
+/// The text must start with a tag, end with a tag, contain at least one
+/// element with a closing tag and have all elements properly nested
+pub fn looks_like_html(text: &str) -> bool {
+    let text = text.trim();
+    if !text.starts_with('<') || !text.ends_with('>') {
+        return false;
+    }
+
+    let Some((_, tags)) = parse_tags(text) else {
+        return false;
+    };
+
+    let mut stack: Vec<&str> = Vec::new();
+    let mut closed_elements = 0;
+    for tag in &tags {
+        if tag.name.is_empty() || tag.self_closing || VOID_ELEMENTS.contains(&tag.name.as_str()) {
+            continue;
+        }
+        if tag.closing {
+            if stack.pop() != Some(tag.name.as_str()) {
+                return false;
+            }
+            closed_elements += 1;
+        } else {
+            stack.push(&tag.name);
+        }
+    }
+
+    closed_elements > 0 && stack.is_empty()
+}
+
+/// Decode the common character entities
+fn decode_entities(text: &str) -> String {
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Extract the visible text of HTML markup
+pub fn strip_tags(html: &str) -> String {
+    let Some((leading, tags)) = parse_tags(html) else {
+        return html.to_string();
+    };
+
+    let mut text = decode_entities(leading);
+    let mut hidden_depth = 0usize;
+    for tag in &tags {
+        let name = tag.name.as_str();
+        if HIDDEN_ELEMENTS.contains(&name) && !tag.self_closing {
+            if tag.closing {
+                hidden_depth = hidden_depth.saturating_sub(1);
+            } else {
+                hidden_depth += 1;
+            }
+        }
+        if name == "br" || (tag.closing && BLOCK_ELEMENTS.contains(&name)) {
+            text.push('\n');
+        }
+        if hidden_depth == 0 {
+            text.push_str(&decode_entities(tag.text_after));
+        }
+    }
+
+    // Trim each line and drop runs of blank lines left by the markup
+    let mut lines: Vec<&str> = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() && lines.last().is_none_or(|last| last.is_empty()) {
+            continue;
+        }
+        lines.push(line);
+    }
+    lines.join("\n").trim().to_string()
+}
+
+/// Wrap an HTML fragment in the CF_HTML header ("HTML Format" clipboard data)
+///
+/// The offsets are byte positions in the UTF-8 data, padded to a fixed width
+/// so the header length doesn't depend on them
+pub fn to_cf_html(fragment: &str) -> String {
+    const PREFIX: &str = "<html><body>\r\n<!--StartFragment-->";
+    const SUFFIX: &str = "<!--EndFragment-->\r\n</body></html>";
+    let header = |start_html: usize, end_html: usize, start_fragment: usize, end_fragment: usize| {
+        format!(
+            "Version:0.9\r\nStartHTML:{:010}\r\nEndHTML:{:010}\r\nStartFragment:{:010}\r\nEndFragment:{:010}\r\n",
+            start_html, end_html, start_fragment, end_fragment
+        )
+    };
+
+    let start_html = header(0, 0, 0, 0).len();
+    let start_fragment = start_html + PREFIX.len();
+    let end_fragment = start_fragment + fragment.len();
+    let end_html = end_fragment + SUFFIX.len();
+    format!("{}{}{}{}", header(start_html, end_html, start_fragment, end_fragment), PREFIX, fragment, SUFFIX)
+}
+
+/// Extract the HTML fragment from CF_HTML clipboard data
+pub fn from_cf_html(data: &[u8]) -> Option<String> {
+    let header_end = data.iter().position(|&b| b == b'<').unwrap_or(data.len());
+    let header = std::str::from_utf8(&data[..header_end]).ok()?;
+    let offset = |key: &str| -> Option<usize> {
+        header.lines()
+            .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+            .and_then(|value| value.trim().parse().ok())
+    };
+
+    let start = offset("StartFragment")?;
+    let end = offset("EndFragment")?;
+    let fragment = data.get(start..end)?;
+    Some(String::from_utf8_lossy(fragment).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_html_markup() {
+        assert!(looks_like_html("<p>Hello <b>world</b></p>"));
+        assert!(looks_like_html("  <div><p>One</p><br><p>Two</p></div>\n"));
+        assert!(looks_like_html("<!DOCTYPE html><html><body><p>x</p></body></html>"));
+        assert!(looks_like_html("<ul><li>a</li><li>b</li></ul>"));
+    }
+
+    #[test]
+    fn text_mentioning_tags_is_not_html() {
+        assert!(!looks_like_html("Use the <div> element for layout"));
+        assert!(!looks_like_html("<b> is bold and </i> closes italics"));
+        assert!(!looks_like_html("if a <b and c> d"));
+        assert!(!looks_like_html("<br>"));
+        assert!(!looks_like_html("<p>unclosed"));
+        assert!(!looks_like_html("<p>misnested <b>tags</p></b>"));
+        assert!(!looks_like_html("<3 you <3>"));
+    }
+
+    #[test]
+    fn strips_tags_to_visible_text() {
+        assert_eq!(strip_tags("<p>Hello <b>world</b></p>"), "Hello world");
+        assert_eq!(strip_tags("<p>One</p><p>Two</p>"), "One\nTwo");
+        assert_eq!(strip_tags("a<br>b"), "a\nb");
+        assert_eq!(strip_tags("<p>x &amp; y &lt;z&gt;</p>"), "x & y <z>");
+        assert_eq!(
+            strip_tags("<html><head><style>p { color: red }</style></head><body><p>Text</p></body></html>"),
+            "Text"
+        );
+    }
+
+    #[test]
+    fn cf_html_round_trips() {
+        for fragment in ["<b>bold</b> text", "<p>剪贴板 &amp; ümlaut</p>", ""] {
+            let data = to_cf_html(fragment);
+            assert_eq!(from_cf_html(data.as_bytes()).as_deref(), Some(fragment));
+        }
+    }
+
+    #[test]
+    fn cf_html_header_offsets_point_into_the_data() {
+        let data = to_cf_html("<i>x</i>");
+        let field = |key: &str| -> usize {
+            let start = data.find(key).unwrap() + key.len() + 1;
+            data[start..start + 10].parse().unwrap()
+        };
+        assert!(data.starts_with("Version:0.9\r\n"));
+        assert!(data[field("StartHTML")..].starts_with("<html>"));
+        assert_eq!(field("EndHTML"), data.len());
+        assert_eq!(&data[field("StartFragment")..field("EndFragment")], "<i>x</i>");
+    }
+
+    #[test]
+    fn cf_html_from_other_apps_is_parsed() {
+        // Trailing NUL and a SourceURL line as written by browsers
+        let html = "<html><body><!--StartFragment--><a href=\"x\">link</a><!--EndFragment--></body></html>";
+        let header_len = "Version:1.0\r\nStartHTML:0000000000\r\nEndHTML:0000000000\r\nStartFragment:0000000000\r\nEndFragment:0000000000\r\nSourceURL:https://example.com/\r\n".len();
+        let start = header_len + html.find("<a").unwrap();
+        let end = header_len + html.find("<!--EndFragment").unwrap();
+        let data = format!(
+            "Version:1.0\r\nStartHTML:{:010}\r\nEndHTML:{:010}\r\nStartFragment:{:010}\r\nEndFragment:{:010}\r\nSourceURL:https://example.com/\r\n{}\0",
+            header_len, header_len + html.len(), start, end, html
+        );
+        assert_eq!(from_cf_html(data.as_bytes()).as_deref(), Some("<a href=\"x\">link</a>"));
+        assert_eq!(from_cf_html(b"no header"), None);
+        assert_eq!(from_cf_html(b"StartFragment:5\r\nEndFragment:999\r\n<b>"), None);
+    }
+}
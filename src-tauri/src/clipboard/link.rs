@@ -0,0 +1,16 @@
+//! EveryPaste - Link detection
+//!
+//! Recognizes text that is a single web address
+
+/// Whether the text (ignoring surrounding whitespace) is a single http(s) URL
+pub fn is_single_url(text: &str) -> bool {
+    let text = text.trim();
+    if text.is_empty() || text.chars().any(char::is_whitespace) {
+        return false;
+    }
+
+    match url::Url::parse(text) {
+        Ok(url) => matches!(url.scheme(), "http" | "https") && url.host_str().is_some_and(|host| !host.is_empty()),
+        Err(_) => false,
+    }
+}
@@ -2,8 +2,13 @@
 //! 
 //! Provides clipboard monitoring and content management functionality
 
+pub mod color;
+pub mod html;
+pub mod link;
 pub mod models;
 pub mod monitor;
+pub mod ocr;
+pub mod units;
 
-pub use models::{ClipboardItem, ClipboardItemView, ContentType};
-pub use monitor::{ClipboardMonitor, ClipboardSnapshot};
+pub use models::{ClipboardItem, ClipboardItemView, ContentType, DEFAULT_PREVIEW_LENGTH};
+pub use monitor::{lock_clipboard, short_hash, ClipboardMonitor, ClipboardSnapshot};
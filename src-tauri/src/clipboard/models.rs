@@ -5,6 +5,9 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 
+/// Preview length (characters) used when no length is configured
+pub const DEFAULT_PREVIEW_LENGTH: usize = 100;
+
 /// Clipboard content type enumeration
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
@@ -15,6 +18,14 @@ pub enum ContentType {
     RichText,
     /// Image
     Image,
+    /// Single web address (stored like text)
+    Link,
+    /// Single color code (stored like text, preview is the normalized "#RRGGBB")
+    Color,
+    /// Copied files (plain_text is a JSON array of the full paths)
+    File,
+    /// Unsupported format placeholder (format names only, no content)
+    Unsupported,
 }
 
 impl ContentType {
@@ -24,6 +35,10 @@ impl ContentType {
             "text" => Some(ContentType::Text),
             "rich_text" => Some(ContentType::RichText),
             "image" => Some(ContentType::Image),
+            "link" => Some(ContentType::Link),
+            "color" => Some(ContentType::Color),
+            "file" => Some(ContentType::File),
+            "unsupported" => Some(ContentType::Unsupported),
             _ => None,
         }
     }
@@ -34,6 +49,10 @@ impl ContentType {
             ContentType::Text => "text",
             ContentType::RichText => "rich_text",
             ContentType::Image => "image",
+            ContentType::Link => "link",
+            ContentType::Color => "color",
+            ContentType::File => "file",
+            ContentType::Unsupported => "unsupported",
         }
     }
 }
@@ -49,80 +68,278 @@ pub struct ClipboardItem {
     pub plain_text: Option<String>,
     /// Rich text HTML content
     pub rich_text: Option<String>,
+    /// RTF content provided alongside the text (pasted together with it)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rtf: Option<String>,
     /// Image path (relative to data directory)
     pub image_path: Option<String>,
     /// Image Base64 thumbnail (for frontend preview)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub image_thumbnail: Option<String>,
+    /// Image width in pixels
+    pub width: Option<i32>,
+    /// Image height in pixels
+    pub height: Option<i32>,
+    /// Size of the image file on disk in bytes
+    pub byte_size: Option<i64>,
     /// Preview text (for list display)
     pub preview: String,
     /// Content hash (for deduplication)
     pub hash: String,
     /// Creation time
     pub created_at: DateTime<Utc>,
+    /// Last modification time (equal to created_at until the record is changed)
+    pub updated_at: DateTime<Utc>,
     /// Whether pinned
     pub is_pinned: bool,
+    /// End of a timed pin (the record is unpinned once this passes)
+    pub pin_until: Option<DateTime<Utc>>,
+    /// Name of the process the content was copied from
+    pub source_app: Option<String>,
+    /// Cached icon of the source application (path relative to the data directory)
+    pub source_icon: Option<String>,
+    /// Detected language (ISO 639-3 code)
+    pub lang: Option<String>,
+    /// Parsed numeric value when the text is a single measurement (e.g. "10 km")
+    pub measure_value: Option<f64>,
+    /// Canonical unit of the parsed measurement
+    pub measure_unit: Option<String>,
+    /// Canonical image key (dimensions and decoded pixel hash, used for image deduplication)
+    pub image_key: Option<String>,
+    /// Whether the content is encrypted at rest (text and image are only available via decrypt_item)
+    pub encrypted: bool,
 }
 
 impl ClipboardItem {
     /// Create new text type record
     pub fn new_text(id: i64, text: String, hash: String) -> Self {
-        let preview = Self::generate_preview(&text, 100);
+        Self::new_text_with_len(id, text, hash, DEFAULT_PREVIEW_LENGTH)
+    }
+
+    /// Create new text type record with a preview of the given length
+    pub fn new_text_with_len(id: i64, text: String, hash: String, preview_len: usize) -> Self {
+        let now = Utc::now();
+        let preview = Self::generate_preview(&text, preview_len);
         Self {
             id,
             content_type: ContentType::Text,
             plain_text: Some(text),
             rich_text: None,
+            rtf: None,
             image_path: None,
             image_thumbnail: None,
+            width: None,
+            height: None,
+            byte_size: None,
             preview,
             hash,
-            created_at: Utc::now(),
+            created_at: now,
+            updated_at: now,
             is_pinned: false,
+            pin_until: None,
+            source_app: None,
+            source_icon: None,
+            lang: None,
+            measure_value: None,
+            measure_unit: None,
+            image_key: None,
+            encrypted: false,
         }
     }
 
     /// Create new rich text type record
     pub fn new_rich_text(id: i64, plain: String, html: String, hash: String) -> Self {
-        let preview = Self::generate_preview(&plain, 100);
+        Self::new_rich_text_with_len(id, plain, html, hash, DEFAULT_PREVIEW_LENGTH)
+    }
+
+    /// Create new rich text type record with a preview of the given length
+    pub fn new_rich_text_with_len(id: i64, plain: String, html: String, hash: String, preview_len: usize) -> Self {
+        let now = Utc::now();
+        let preview = Self::generate_preview(&plain, preview_len);
         Self {
             id,
             content_type: ContentType::RichText,
             plain_text: Some(plain),
             rich_text: Some(html),
+            rtf: None,
             image_path: None,
             image_thumbnail: None,
+            width: None,
+            height: None,
+            byte_size: None,
             preview,
             hash,
-            created_at: Utc::now(),
+            created_at: now,
+            updated_at: now,
             is_pinned: false,
+            pin_until: None,
+            source_app: None,
+            source_icon: None,
+            lang: None,
+            measure_value: None,
+            measure_unit: None,
+            image_key: None,
+            encrypted: false,
         }
     }
 
     /// Create new image type record
     pub fn new_image(id: i64, image_path: String, thumbnail: Option<String>, hash: String) -> Self {
+        let now = Utc::now();
         Self {
             id,
             content_type: ContentType::Image,
             plain_text: None,
             rich_text: None,
+            rtf: None,
             image_path: Some(image_path),
             image_thumbnail: thumbnail,
+            width: None,
+            height: None,
+            byte_size: None,
             preview: "[Image]".to_string(),
             hash,
-            created_at: Utc::now(),
+            created_at: now,
+            updated_at: now,
+            is_pinned: false,
+            pin_until: None,
+            source_app: None,
+            source_icon: None,
+            lang: None,
+            measure_value: None,
+            measure_unit: None,
+            image_key: None,
+            encrypted: false,
+        }
+    }
+
+    /// Create new unsupported format placeholder record
+    pub fn new_unsupported(id: i64, formats: String, hash: String) -> Self {
+        let now = Utc::now();
+        Self {
+            id,
+            content_type: ContentType::Unsupported,
+            plain_text: None,
+            rich_text: None,
+            rtf: None,
+            image_path: None,
+            image_thumbnail: None,
+            width: None,
+            height: None,
+            byte_size: None,
+            preview: format!("[Unsupported: {}]", formats),
+            hash,
+            created_at: now,
+            updated_at: now,
+            is_pinned: false,
+            pin_until: None,
+            source_app: None,
+            source_icon: None,
+            lang: None,
+            measure_value: None,
+            measure_unit: None,
+            image_key: None,
+            encrypted: false,
+        }
+    }
+
+    /// Create new file list record
+    pub fn new_files(id: i64, paths: &[String], hash: String) -> Self {
+        let now = Utc::now();
+        let names: Vec<&str> = paths
+            .iter()
+            .map(|p| std::path::Path::new(p).file_name().and_then(|n| n.to_str()).unwrap_or(p))
+            .collect();
+        let preview = match names.as_slice() {
+            [name] => format!("[File] {}", name),
+            _ => format!("[{} Files] {}", names.len(), names.join(", ")),
+        };
+        Self {
+            id,
+            content_type: ContentType::File,
+            plain_text: serde_json::to_string(paths).ok(),
+            rich_text: None,
+            rtf: None,
+            image_path: None,
+            image_thumbnail: None,
+            width: None,
+            height: None,
+            byte_size: None,
+            preview: Self::generate_preview(&preview, DEFAULT_PREVIEW_LENGTH),
+            hash,
+            created_at: now,
+            updated_at: now,
             is_pinned: false,
+            pin_until: None,
+            source_app: None,
+            source_icon: None,
+            lang: None,
+            measure_value: None,
+            measure_unit: None,
+            image_key: None,
+            encrypted: false,
+        }
+    }
+
+    /// Get the paths of a file list record
+    pub fn file_paths(&self) -> Vec<String> {
+        if self.content_type != ContentType::File {
+            return Vec::new();
+        }
+        self.plain_text
+            .as_deref()
+            .and_then(|json| serde_json::from_str(json).ok())
+            .unwrap_or_default()
+    }
+
+    /// Get the color of a color record as "#RRGGBB"
+    pub fn color_hex(&self) -> Option<String> {
+        if self.content_type != ContentType::Color {
+            return None;
+        }
+        self.plain_text.as_deref().and_then(super::color::parse_color).map(super::color::to_hex)
+    }
+
+    /// Get the text as a file path if it names an existing file or directory
+    /// 
+    /// Only short, single-line, drive-letter paths are checked against the filesystem
+    /// (UNC paths are skipped since probing them can block on the network)
+    pub fn file_path(&self) -> Option<&str> {
+        const MAX_PATH_LEN: usize = 260;
+
+        if self.content_type != ContentType::Text {
+            return None;
+        }
+        let text = self.plain_text.as_deref()?.trim().trim_matches('"');
+        if text.is_empty() || text.len() > MAX_PATH_LEN || text.contains(['\n', '\r']) {
+            return None;
+        }
+
+        let bytes = text.as_bytes();
+        let is_drive_path = bytes.len() >= 3
+            && bytes[0].is_ascii_alphabetic()
+            && bytes[1] == b':'
+            && (bytes[2] == b'\\' || bytes[2] == b'/');
+        if !is_drive_path {
+            return None;
         }
+
+        std::path::Path::new(text).exists().then_some(text)
     }
 
     /// Generate preview text
-    fn generate_preview(text: &str, max_len: usize) -> String {
+    /// 
+    /// Cuts after max_len user-perceived characters (grapheme clusters), 0 gives an empty preview
+    pub(crate) fn generate_preview(text: &str, max_len: usize) -> String {
+        use unicode_segmentation::UnicodeSegmentation;
+
+        if max_len == 0 {
+            return String::new();
+        }
         let text = text.trim();
-        if text.chars().count() <= max_len {
-            text.to_string()
-        } else {
-            let truncated: String = text.chars().take(max_len).collect();
-            format!("{}...", truncated)
+        match text.grapheme_indices(true).nth(max_len) {
+            Some((end, _)) => format!("{}...", &text[..end]),
+            None => text.to_string(),
         }
     }
 }
@@ -134,19 +351,75 @@ pub struct ClipboardItemView {
     pub content_type: ContentType,
     pub preview: String,
     pub image_thumbnail: Option<String>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub byte_size: Option<i64>,
     pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
     pub is_pinned: bool,
+    pub pin_until: Option<DateTime<Utc>>,
+    pub source_app: Option<String>,
+    pub source_icon: Option<String>,
+    pub lang: Option<String>,
+    pub measure_value: Option<f64>,
+    pub measure_unit: Option<String>,
+    /// Whether the text is the path of an existing file
+    pub is_file_path: bool,
+    /// Color of a color record ("#RRGGBB", for the swatch)
+    pub color_hex: Option<String>,
+    pub encrypted: bool,
 }
 
 impl From<ClipboardItem> for ClipboardItemView {
     fn from(item: ClipboardItem) -> Self {
+        let is_file_path = item.file_path().is_some();
+        let color_hex = item.color_hex();
         Self {
             id: item.id,
             content_type: item.content_type,
             preview: item.preview,
             image_thumbnail: item.image_thumbnail,
+            width: item.width,
+            height: item.height,
+            byte_size: item.byte_size,
             created_at: item.created_at,
+            updated_at: item.updated_at,
             is_pinned: item.is_pinned,
+            pin_until: item.pin_until,
+            source_app: item.source_app,
+            source_icon: item.source_icon,
+            lang: item.lang,
+            measure_value: item.measure_value,
+            measure_unit: item.measure_unit,
+            is_file_path,
+            color_hex,
+            encrypted: item.encrypted,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_preview_length_yields_empty_preview() {
+        assert_eq!(ClipboardItem::generate_preview("hello", 0), "");
+    }
+
+    #[test]
+    fn short_text_is_not_truncated() {
+        assert_eq!(ClipboardItem::generate_preview("  hello  ", 10), "hello");
+    }
+
+    #[test]
+    fn long_cjk_text_is_cut_on_grapheme_boundary() {
+        let text = "剪贴板历史记录管理工具";
+        let preview = ClipboardItem::generate_preview(text, 4);
+        assert_eq!(preview, "剪贴板历...");
+
+        // A combining sequence counts as one grapheme and is never split
+        let preview = ClipboardItem::generate_preview("e\u{301}e\u{301}e\u{301}", 2);
+        assert_eq!(preview, "e\u{301}e\u{301}...");
+    }
+}
@@ -4,30 +4,100 @@
 
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
-use std::thread;
+use std::time::{Duration, Instant};
+use std::thread::{self, JoinHandle};
 
 use arboard::Clipboard;
-use parking_lot::Mutex;
+use parking_lot::{Mutex, MutexGuard};
 use blake3::Hasher;
 
 use super::models::ContentType;
 
+/// Maximum time to wait for the worker thread to exit on stop (milliseconds)
+const STOP_TIMEOUT_MS: u64 = 2000;
+
+/// Serializes clipboard reads (monitor) and writes (paste commands)
+/// 
+/// Without it a capture can run while a paste is half done and record
+/// garbled or empty content
+static CLIPBOARD_ACCESS: Mutex<()> = Mutex::new(());
+
+/// Take exclusive access to the system clipboard, held until the guard is dropped
+pub fn lock_clipboard() -> MutexGuard<'static, ()> {
+    CLIPBOARD_ACCESS.lock()
+}
+
 /// Clipboard content snapshot
 #[derive(Debug, Clone)]
 pub struct ClipboardSnapshot {
     /// Content type
     pub content_type: ContentType,
-    /// Plain text content
+    /// Plain text content (format names for unsupported content)
     pub plain_text: Option<String>,
     /// Rich text HTML
     pub rich_text: Option<String>,
+    /// RTF provided alongside the text (not part of the hash)
+    pub rtf: Option<String>,
     /// Image data (PNG format)
     pub image_data: Option<Vec<u8>>,
     /// Content hash
     pub hash: String,
 }
 
+impl ClipboardSnapshot {
+    /// Capture the current system clipboard content
+    pub fn capture() -> Option<Self> {
+        let _access = lock_clipboard();
+        let mut clipboard = match Clipboard::new() {
+            Ok(cb) => cb,
+            Err(e) => {
+                log::error!("Failed to create clipboard instance: {}", e);
+                return None;
+            }
+        };
+        ClipboardMonitor::read_clipboard(&mut clipboard)
+    }
+
+    /// Write this snapshot back to the system clipboard
+    pub fn write_to_clipboard(&self) -> Result<(), String> {
+        let _access = lock_clipboard();
+        let mut clipboard = Clipboard::new().map_err(|e| e.to_string())?;
+
+        match self.content_type {
+            ContentType::Image => {
+                let data = self.image_data.as_ref().ok_or("Image data is missing")?;
+                let img = image::load_from_memory(data)
+                    .map_err(|e| e.to_string())?
+                    .into_rgba8();
+                let (width, height) = img.dimensions();
+                clipboard.set_image(arboard::ImageData {
+                    width: width as usize,
+                    height: height as usize,
+                    bytes: std::borrow::Cow::Owned(img.into_raw()),
+                }).map_err(|e| e.to_string())
+            }
+            ContentType::RichText => {
+                let html = self.rich_text.as_deref().ok_or("Rich text is missing")?;
+                let text = self.plain_text.clone().unwrap_or_else(|| super::html::strip_tags(html));
+                clipboard.set_text(text).map_err(|e| e.to_string())?;
+                ClipboardMonitor::add_html_format(html)?;
+                self.rtf.as_deref().map_or(Ok(()), ClipboardMonitor::add_rtf_format)
+            }
+            ContentType::Text | ContentType::Link | ContentType::Color => {
+                let text = self.plain_text.as_deref().ok_or("Text is missing")?;
+                clipboard.set_text(text).map_err(|e| e.to_string())?;
+                self.rtf.as_deref().map_or(Ok(()), ClipboardMonitor::add_rtf_format)
+            }
+            ContentType::File => {
+                let json = self.plain_text.as_deref().ok_or("File list is missing")?;
+                let paths: Vec<String> = serde_json::from_str(json).map_err(|e| e.to_string())?;
+                ClipboardMonitor::write_file_list(&paths)
+            }
+            ContentType::Unsupported => Err("Unsupported content cannot be written back".to_string()),
+        }
+    }
+}
+
 /// Clipboard monitor
 pub struct ClipboardMonitor {
     /// Whether running
@@ -38,6 +108,8 @@ pub struct ClipboardMonitor {
     last_hash: Arc<Mutex<String>>,
     /// Whether paused (used when the app writes to clipboard)
     paused: Arc<AtomicBool>,
+    /// Worker thread handle (joined on stop)
+    handle: Mutex<Option<JoinHandle<()>>>,
 }
 
 impl ClipboardMonitor {
@@ -48,6 +120,7 @@ impl ClipboardMonitor {
             poll_interval_ms,
             last_hash: Arc::new(Mutex::new(String::new())),
             paused: Arc::new(AtomicBool::new(false)),
+            handle: Mutex::new(None),
         }
     }
 
@@ -69,36 +142,38 @@ impl ClipboardMonitor {
         let paused = Arc::clone(&self.paused);
         let interval = self.poll_interval_ms;
 
-        thread::spawn(move || {
-            log::info!("Clipboard monitor started with {}ms interval", interval);
+        let handle = thread::spawn(move || {
+            // Wait for clipboard update notifications on Windows, poll if the listener can't be registered
+            #[cfg(target_os = "windows")]
+            match listener::ClipboardListener::new() {
+                Ok(listener) => {
+                    log::info!("Clipboard monitor started (clipboard update events)");
+
+                    // Check once on start, like the first poll
+                    let mut pending = true;
+                    while running.load(Ordering::SeqCst) {
+                        if listener.wait(Duration::from_millis(interval)) {
+                            pending = true;
+                        }
+                        // Changes while paused are checked after resuming (same as polling)
+                        if pending && !paused.load(Ordering::SeqCst) {
+                            pending = false;
+                            Self::check_clipboard(&last_hash, &callback);
+                        }
+                    }
 
-            while running.load(Ordering::SeqCst) {
-                // If paused, skip this detection
-                if paused.load(Ordering::SeqCst) {
-                    thread::sleep(Duration::from_millis(interval));
-                    continue;
+                    log::info!("Clipboard monitor stopped");
+                    return;
                 }
+                Err(e) => log::warn!("Failed to register clipboard listener, falling back to polling: {}", e),
+            }
 
-                // Create new Clipboard instance each loop to ensure getting latest data
-                let mut clipboard = match Clipboard::new() {
-                    Ok(cb) => cb,
-                    Err(e) => {
-                        log::error!("Failed to create clipboard instance: {}", e);
-                        thread::sleep(Duration::from_millis(interval));
-                        continue;
-                    }
-                };
+            log::info!("Clipboard monitor started with {}ms interval", interval);
 
-                // Try to read clipboard content
-                if let Some(snapshot) = Self::read_clipboard(&mut clipboard) {
-                    let mut last = last_hash.lock();
-                    
-                    if snapshot.hash != *last {
-                        log::debug!("[Monitor] New content detected: {:?}", snapshot.content_type);
-                        *last = snapshot.hash.clone();
-                        drop(last);
-                        callback(snapshot);
-                    }
+            while running.load(Ordering::SeqCst) {
+                // If paused, skip this detection
+                if !paused.load(Ordering::SeqCst) {
+                    Self::check_clipboard(&last_hash, &callback);
                 }
 
                 thread::sleep(Duration::from_millis(interval));
@@ -106,11 +181,73 @@ impl ClipboardMonitor {
 
             log::info!("Clipboard monitor stopped");
         });
+
+        *self.handle.lock() = Some(handle);
+    }
+
+    /// Read the clipboard and report the content if it changed since the last check
+    fn check_clipboard<F>(last_hash: &Mutex<String>, callback: &F)
+    where
+        F: Fn(ClipboardSnapshot),
+    {
+        let snapshot = {
+            let _access = lock_clipboard();
+            
+            // Create new Clipboard instance each time to ensure getting latest data
+            let mut clipboard = match Clipboard::new() {
+                Ok(cb) => cb,
+                Err(e) => {
+                    log::error!("Failed to create clipboard instance: {}", e);
+                    return;
+                }
+            };
+            Self::read_clipboard(&mut clipboard)
+        };
+
+        // Report new content (outside the clipboard lock)
+        if let Some(snapshot) = snapshot {
+            Self::report_if_new(last_hash, snapshot, callback);
+        }
+    }
+
+    /// Pass the snapshot to the callback unless it is the content seen last
+    fn report_if_new<F>(last_hash: &Mutex<String>, snapshot: ClipboardSnapshot, callback: &F)
+    where
+        F: Fn(ClipboardSnapshot),
+    {
+        let mut last = last_hash.lock();
+        
+        if snapshot.hash != *last {
+            log::debug!("[Monitor] New content detected: {:?}", snapshot.content_type);
+            *last = snapshot.hash.clone();
+            drop(last);
+            callback(snapshot);
+        }
     }
 
-    /// Stop monitoring
+    /// Stop monitoring and wait for the worker thread to exit
+    /// 
+    /// Waits up to STOP_TIMEOUT_MS, if the thread is still busy after that
+    /// (e.g. blocked on a clipboard read) it is detached and exits on its own
     pub fn stop(&self) {
         self.running.store(false, Ordering::SeqCst);
+
+        let Some(handle) = self.handle.lock().take() else {
+            return;
+        };
+
+        let deadline = Instant::now() + Duration::from_millis(STOP_TIMEOUT_MS);
+        while !handle.is_finished() {
+            if Instant::now() >= deadline {
+                log::warn!("Clipboard monitor thread did not exit within {}ms", STOP_TIMEOUT_MS);
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        if handle.join().is_err() {
+            log::error!("Clipboard monitor thread panicked");
+        }
     }
 
     /// Pause monitoring
@@ -123,8 +260,59 @@ impl ClipboardMonitor {
         self.paused.store(false, Ordering::SeqCst);
     }
 
+    /// Record the current clipboard content as already seen
+    pub fn mark_current_as_seen(&self) {
+        if let Some(snapshot) = ClipboardSnapshot::capture() {
+            *self.last_hash.lock() = snapshot.hash;
+        }
+    }
+
+    /// Read the current clipboard content and record it as seen
+    /// 
+    /// Returns the snapshot so the caller can save it, the polling loop
+    /// won't report the same content again
+    pub fn capture_now(&self) -> Option<ClipboardSnapshot> {
+        let snapshot = ClipboardSnapshot::capture()?;
+        self.mark_as_seen(&snapshot);
+        Some(snapshot)
+    }
+
+    /// Record the snapshot as the content seen last
+    fn mark_as_seen(&self, snapshot: &ClipboardSnapshot) {
+        *self.last_hash.lock() = snapshot.hash.clone();
+    }
+
+    /// Polling interval (milliseconds)
+    pub fn poll_interval_ms(&self) -> u64 {
+        self.poll_interval_ms
+    }
+
+    /// Whether the monitor thread is running
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    /// Whether monitoring is paused
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
     /// Read clipboard content
     fn read_clipboard(clipboard: &mut Clipboard) -> Option<ClipboardSnapshot> {
+        // 0. Use a PNG provided by the source app as is (keeps alpha the DIB/RGBA conversion may drop)
+        if crate::config::get_settings().prefer_png_clipboard {
+            if let Some((image_data, hash)) = Self::read_png_format() {
+                return Some(ClipboardSnapshot {
+                    content_type: ContentType::Image,
+                    plain_text: None,
+                    rich_text: None,
+                    rtf: None,
+                    image_data: Some(image_data),
+                    hash,
+                });
+            }
+        }
+
         // 1. First check direct image data in clipboard (arboard)
         match clipboard.get_image() {
             Ok(image) => {
@@ -141,6 +329,7 @@ impl ClipboardMonitor {
                         content_type: ContentType::Image,
                         plain_text: None,
                         rich_text: None,
+                        rtf: None,
                         image_data: Some(image_data),
                         hash,
                     });
@@ -155,6 +344,7 @@ impl ClipboardMonitor {
                         content_type: ContentType::Image,
                         plain_text: None,
                         rich_text: None,
+                        rtf: None,
                         image_data: Some(image_data),
                         hash,
                     });
@@ -171,34 +361,143 @@ impl ClipboardMonitor {
                 content_type: ContentType::Image,
                 plain_text: None,
                 rich_text: None,
+                rtf: None,
                 image_data: Some(image_data),
                 hash,
             });
         }
 
-        // 3. Check text
+        // 3. Check other copied files (documents, folders)
+        if let Some(files) = Self::read_file_list() {
+            if let Ok(json) = serde_json::to_string(&files) {
+                let hash = Self::compute_hash(json.as_bytes());
+                return Some(ClipboardSnapshot {
+                    content_type: ContentType::File,
+                    plain_text: Some(json),
+                    rich_text: None,
+                    rtf: None,
+                    image_data: None,
+                    hash,
+                });
+            }
+        }
+
+        // 4. Check text (with the CF_HTML markup for rich text)
         if let Ok(text) = clipboard.get_text() {
             if !text.is_empty() {
                 let hash = Self::compute_hash(text.as_bytes());
+                let rich_text = Self::read_html_format();
                 
                 return Some(ClipboardSnapshot {
-                    content_type: ContentType::Text,
+                    content_type: if rich_text.is_some() { ContentType::RichText } else { ContentType::Text },
                     plain_text: Some(text),
-                    rich_text: None,
+                    rich_text,
+                    rtf: Self::read_rtf_format(),
                     image_data: None,
                     hash,
                 });
             }
         }
 
+        // 5. Record the available formats of content that can't be stored
+        let formats = Self::read_format_names().unwrap_or_else(|e| {
+            log::debug!("[Clipboard] Failed to enumerate formats: {}", e);
+            Vec::new()
+        });
+        if !formats.is_empty() {
+            let names = formats.join(", ");
+            // Include the clipboard sequence number so each new copy gets a distinct hash
+            let seq = clipboard_win::seq_num().map(|n| n.get()).unwrap_or(0);
+            let hash = Self::compute_hash(format!("unsupported:{}:{}", seq, names).as_bytes());
+            return Some(ClipboardSnapshot {
+                content_type: ContentType::Unsupported,
+                plain_text: Some(names),
+                rich_text: None,
+                rtf: None,
+                image_data: None,
+                hash,
+            });
+        }
+
         None
     }
 
-    /// Try to read images from clipboard file list
-    fn read_image_files() -> Option<Vec<u8>> {
-        use std::path::Path;
+    /// Read the registered "Rich Text Format" clipboard format
+    fn read_rtf_format() -> Option<String> {
+        use clipboard_win::{formats, get_clipboard, register_format};
+
+        let format = register_format("Rich Text Format")?;
+        let data: Vec<u8> = get_clipboard(formats::RawData(format.get())).ok()?;
+        // RTF escapes non-ASCII characters, the data ends at the terminating NUL
+        let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+        let rtf = String::from_utf8_lossy(&data[..end]).into_owned();
+        rtf.starts_with("{\\rtf").then_some(rtf)
+    }
+
+    /// Read the registered "HTML Format" (CF_HTML) clipboard format
+    fn read_html_format() -> Option<String> {
+        use clipboard_win::{formats, get_clipboard, register_format};
+
+        let format = register_format("HTML Format")?;
+        let data: Vec<u8> = get_clipboard(formats::RawData(format.get())).ok()?;
+        super::html::from_cf_html(&data).filter(|html| !html.trim().is_empty())
+    }
+
+    /// Add HTML as CF_HTML to the formats already on the clipboard
+    /// 
+    /// Doesn't take the clipboard lock, call it right after setting the text
+    /// while holding lock_clipboard
+    pub fn add_html_format(html: &str) -> Result<(), String> {
+        let format = clipboard_win::register_format("HTML Format")
+            .ok_or("Failed to register HTML clipboard format")?;
+        let _clip = clipboard_win::Clipboard::new_attempts(10)
+            .map_err(|e| format!("Failed to open clipboard: {}", e))?;
+
+        let mut data = super::html::to_cf_html(html).into_bytes();
+        data.push(0);
+        clipboard_win::raw::set_without_clear(format.get(), &data)
+            .map_err(|e| format!("Failed to set HTML: {}", e))
+    }
+
+    /// Add RTF to the formats already on the clipboard
+    /// 
+    /// Doesn't take the clipboard lock, call it right after setting the text
+    /// while holding lock_clipboard
+    pub fn add_rtf_format(rtf: &str) -> Result<(), String> {
+        let format = clipboard_win::register_format("Rich Text Format")
+            .ok_or("Failed to register RTF clipboard format")?;
+        let _clip = clipboard_win::Clipboard::new_attempts(10)
+            .map_err(|e| format!("Failed to open clipboard: {}", e))?;
+
+        let mut data = rtf.as_bytes().to_vec();
+        data.push(0);
+        clipboard_win::raw::set_without_clear(format.get(), &data)
+            .map_err(|e| format!("Failed to set RTF: {}", e))
+    }
 
-        // Try to get file list (fully qualified path)
+    /// Whether the clipboard holds no formats at all
+    pub fn is_clipboard_empty() -> bool {
+        clipboard_win::count_formats() == Some(0)
+    }
+
+    /// Get the names of all formats currently on the clipboard
+    /// 
+    /// Doesn't take the clipboard lock (it is called during reads), callers
+    /// outside the monitor should hold lock_clipboard
+    pub fn read_format_names() -> Result<Vec<String>, String> {
+        let _clip = clipboard_win::Clipboard::new_attempts(10)
+            .map_err(|e| format!("Failed to open clipboard: {}", e))?;
+
+        Ok(clipboard_win::EnumFormats::new()
+            .map(|format| {
+                clipboard_win::raw::format_name_big(format)
+                    .unwrap_or_else(|| format!("Format {}", format))
+            })
+            .collect())
+    }
+
+    /// Read the clipboard file list (fully qualified paths)
+    fn read_file_list() -> Option<Vec<String>> {
         let files: Vec<String> = match clipboard_win::get_clipboard::<Vec<String>, _>(clipboard_win::formats::FileList) {
             Ok(f) => {
                 log::info!("[Clipboard] FileList detected: {} files", f.len());
@@ -214,6 +513,24 @@ impl ClipboardMonitor {
             log::debug!("[Clipboard] FileList is empty");
             return None;
         }
+        Some(files)
+    }
+
+    /// Replace the clipboard content with a file list (CF_HDROP) Explorer can paste
+    /// 
+    /// Doesn't take the clipboard lock, callers should hold lock_clipboard
+    pub fn write_file_list(paths: &[String]) -> Result<(), String> {
+        let _clip = clipboard_win::Clipboard::new_attempts(10)
+            .map_err(|e| format!("Failed to open clipboard: {}", e))?;
+        clipboard_win::raw::set_file_list_with(paths, clipboard_win::options::DoClear)
+            .map_err(|e| format!("Failed to set file list: {}", e))
+    }
+
+    /// Try to read images from clipboard file list
+    fn read_image_files() -> Option<Vec<u8>> {
+        use std::path::Path;
+
+        let files = Self::read_file_list()?;
 
         // Iterate to find the first valid image file
         for file_path in &files {
@@ -261,6 +578,27 @@ impl ClipboardMonitor {
         None
     }
 
+    /// Read the registered "PNG" clipboard format (provided by browsers and image editors)
+    fn read_png_format() -> Option<(Vec<u8>, String)> {
+        use clipboard_win::{formats, get_clipboard, register_format};
+
+        let format = register_format("PNG")?;
+        let png_data: Vec<u8> = get_clipboard(formats::RawData(format.get())).ok()?;
+        Self::accept_png_data(png_data)
+    }
+
+    /// Keep "PNG" format data byte for byte (alpha included) if it really is a PNG image
+    fn accept_png_data(png_data: Vec<u8>) -> Option<(Vec<u8>, String)> {
+        if image::guess_format(&png_data).ok() != Some(image::ImageFormat::Png) {
+            log::debug!("[Clipboard] PNG clipboard format doesn't contain PNG data");
+            return None;
+        }
+
+        let hash = Self::compute_hash(&png_data);
+        log::info!("[Clipboard] Got PNG clipboard data: {} bytes", png_data.len());
+        Some((png_data, hash))
+    }
+
     /// Try to read DIB format image from clipboard (for supporting third-party screenshot tools like PixPin)
     fn read_dib_image() -> Option<(Vec<u8>, String)> {
         use clipboard_win::{formats, get_clipboard};
@@ -283,7 +621,7 @@ impl ClipboardMonitor {
 
         // Calculate hash (based on raw data)
         let hash = Self::compute_hash(&bitmap_data);
-        log::info!("[Clipboard] DIB hash: {}", &hash[..8]);
+        log::info!("[Clipboard] DIB hash: {}", short_hash(&hash));
 
         // Try to decode BMP data and convert to PNG
         match image::load_from_memory(&bitmap_data) {
@@ -325,11 +663,21 @@ impl ClipboardMonitor {
     }
 
     /// Compute content hash
-    fn compute_hash(data: &[u8]) -> String {
+    pub fn compute_hash(data: &[u8]) -> String {
         let mut hasher = Hasher::new();
         hasher.update(data);
         hasher.finalize().to_hex().to_string()
     }
+
+    /// Compute the canonical key of an encoded image
+    /// 
+    /// Derived from the dimensions and decoded RGBA pixels, so the same picture
+    /// read as PNG, DIB or an image file gets the same key
+    pub fn compute_image_key(data: &[u8]) -> Option<String> {
+        let img = image::load_from_memory(data).ok()?.into_rgba8();
+        let (width, height) = img.dimensions();
+        Some(format!("{}x{}:{}", width, height, Self::compute_hash(img.as_raw())))
+    }
 }
 
 impl Default for ClipboardMonitor {
@@ -337,3 +685,231 @@ impl Default for ClipboardMonitor {
         Self::new(150) // Default 150ms polling interval
     }
 }
+
+/// Shortened hash for logging (up to 8 characters, never panics on short input)
+pub fn short_hash(hash: &str) -> &str {
+    match hash.char_indices().nth(8) {
+        Some((end, _)) => &hash[..end],
+        None => hash,
+    }
+}
+
+/// Clipboard update notifications (WM_CLIPBOARDUPDATE) on a hidden message-only window
+#[cfg(target_os = "windows")]
+mod listener {
+    use std::cell::Cell;
+    use std::time::Duration;
+
+    use windows::core::{w, PCWSTR};
+    use windows::Win32::Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, WPARAM};
+    use windows::Win32::System::DataExchange::{AddClipboardFormatListener, RemoveClipboardFormatListener};
+    use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        CreateWindowExW, DefWindowProcW, DestroyWindow, DispatchMessageW, MsgWaitForMultipleObjects, PeekMessageW,
+        RegisterClassW, HMENU, HWND_MESSAGE, MSG, PM_REMOVE, QS_ALLINPUT, WINDOW_EX_STYLE, WINDOW_STYLE,
+        WM_CLIPBOARDUPDATE, WNDCLASSW,
+    };
+
+    thread_local! {
+        /// Set by the window procedure when the clipboard changed (the window lives on the monitor thread)
+        static CLIPBOARD_UPDATED: Cell<bool> = const { Cell::new(false) };
+    }
+
+    unsafe extern "system" fn window_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        if msg == WM_CLIPBOARDUPDATE {
+            CLIPBOARD_UPDATED.with(|updated| updated.set(true));
+            return LRESULT(0);
+        }
+        DefWindowProcW(hwnd, msg, wparam, lparam)
+    }
+
+    /// Clipboard format listener, must be created and used on the same thread
+    pub struct ClipboardListener {
+        hwnd: HWND,
+    }
+
+    impl ClipboardListener {
+        /// Create the message window and register it as clipboard format listener
+        pub fn new() -> windows::core::Result<Self> {
+            unsafe {
+                let instance = HINSTANCE(GetModuleHandleW(PCWSTR::null())?.0);
+                let class_name = w!("EveryPasteClipboardListener");
+                let class = WNDCLASSW {
+                    lpfnWndProc: Some(window_proc),
+                    hInstance: instance,
+                    lpszClassName: class_name,
+                    ..Default::default()
+                };
+                // Fails harmlessly when the class is already registered (monitor restart)
+                RegisterClassW(&class);
+
+                let hwnd = CreateWindowExW(
+                    WINDOW_EX_STYLE::default(),
+                    class_name,
+                    PCWSTR::null(),
+                    WINDOW_STYLE::default(),
+                    0,
+                    0,
+                    0,
+                    0,
+                    HWND_MESSAGE,
+                    HMENU::default(),
+                    instance,
+                    None,
+                )?;
+                if let Err(e) = AddClipboardFormatListener(hwnd) {
+                    let _ = DestroyWindow(hwnd);
+                    return Err(e);
+                }
+
+                Ok(Self { hwnd })
+            }
+        }
+
+        /// Wait up to timeout for window messages
+        ///
+        /// Returns whether a clipboard update arrived
+        pub fn wait(&self, timeout: Duration) -> bool {
+            unsafe {
+                MsgWaitForMultipleObjects(None, false, timeout.as_millis() as u32, QS_ALLINPUT);
+                let mut msg = MSG::default();
+                while PeekMessageW(&mut msg, HWND::default(), 0, 0, PM_REMOVE).as_bool() {
+                    DispatchMessageW(&msg);
+                }
+            }
+            CLIPBOARD_UPDATED.with(|updated| updated.replace(false))
+        }
+    }
+
+    impl Drop for ClipboardListener {
+        fn drop(&mut self) {
+            unsafe {
+                let _ = RemoveClipboardFormatListener(self.hwnd);
+                let _ = DestroyWindow(self.hwnd);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat, RgbImage};
+
+    fn encode(img: &image::DynamicImage, format: ImageFormat) -> Vec<u8> {
+        let mut data = Vec::new();
+        img.write_to(&mut std::io::Cursor::new(&mut data), format).unwrap();
+        data
+    }
+
+    /// Small image with varying pixels
+    fn gradient(width: u32, height: u32) -> image::DynamicImage {
+        image::DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |x, y| {
+            image::Rgb([(x * 16) as u8, (y * 16) as u8, 128])
+        }))
+    }
+
+    fn text_snapshot(text: &str) -> ClipboardSnapshot {
+        ClipboardSnapshot {
+            content_type: ContentType::Text,
+            plain_text: Some(text.to_string()),
+            rich_text: None,
+            rtf: None,
+            image_data: None,
+            hash: ClipboardMonitor::compute_hash(text.as_bytes()),
+        }
+    }
+
+    /// Feed the snapshots to the monitor like successive polls, returning the reported texts
+    fn poll(monitor: &ClipboardMonitor, snapshots: &[&ClipboardSnapshot]) -> Vec<String> {
+        let reported = std::cell::RefCell::new(Vec::new());
+        for snapshot in snapshots {
+            ClipboardMonitor::report_if_new(&monitor.last_hash, (*snapshot).clone(), &|s: ClipboardSnapshot| {
+                reported.borrow_mut().push(s.plain_text.unwrap());
+            });
+        }
+        reported.into_inner()
+    }
+
+    #[test]
+    fn startup_capture_is_not_reported_again_by_the_first_poll() {
+        let monitor = ClipboardMonitor::new(150);
+        let startup = text_snapshot("on clipboard at launch");
+        // What capture_now does with the content it returns for saving
+        monitor.mark_as_seen(&startup);
+
+        let next = text_snapshot("copied later");
+        assert_eq!(poll(&monitor, &[&startup, &startup, &next]), vec!["copied later"]);
+    }
+
+    #[test]
+    fn first_poll_reports_content_present_at_start() {
+        let monitor = ClipboardMonitor::new(150);
+        let startup = text_snapshot("on clipboard at launch");
+        assert_eq!(poll(&monitor, &[&startup, &startup]), vec!["on clipboard at launch"]);
+    }
+
+    #[test]
+    fn short_hash_handles_short_and_full_hashes() {
+        assert_eq!(short_hash(""), "");
+        assert_eq!(short_hash("abc"), "abc");
+        let full = ClipboardMonitor::compute_hash(b"content");
+        assert_eq!(full.len(), 64);
+        assert_eq!(short_hash(&full), &full[..8]);
+    }
+
+    #[test]
+    fn transparent_png_format_data_is_kept_as_is() {
+        let img = image::DynamicImage::ImageRgba8(image::RgbaImage::from_fn(4, 4, |x, _| {
+            image::Rgba([255, 0, 0, if x < 2 { 0 } else { 255 }])
+        }));
+        let png = encode(&img, ImageFormat::Png);
+
+        let (data, hash) = ClipboardMonitor::accept_png_data(png.clone()).unwrap();
+        assert_eq!(data, png);
+        assert_eq!(hash, ClipboardMonitor::compute_hash(&png));
+        let decoded = image::load_from_memory(&data).unwrap().to_rgba8();
+        assert_eq!(decoded.get_pixel(0, 0)[3], 0);
+        assert_eq!(decoded.get_pixel(3, 0)[3], 255);
+    }
+
+    #[test]
+    fn png_format_without_png_data_falls_back_to_dib() {
+        let bmp = encode(&gradient(4, 4), ImageFormat::Bmp);
+        assert!(ClipboardMonitor::accept_png_data(bmp).is_none());
+        assert!(ClipboardMonitor::accept_png_data(Vec::new()).is_none());
+    }
+
+    #[test]
+    fn image_key_matches_across_png_and_dib() {
+        let img = gradient(8, 6);
+        let png_key = ClipboardMonitor::compute_image_key(&encode(&img, ImageFormat::Png));
+        // CF_BITMAP is read as BMP data (see read_dib_image)
+        let dib_key = ClipboardMonitor::compute_image_key(&encode(&img, ImageFormat::Bmp));
+        assert!(png_key.is_some());
+        assert_eq!(png_key, dib_key);
+    }
+
+    #[test]
+    fn image_key_of_jpeg_matches_its_decoded_pixels() {
+        // JPEG is lossy, a copied JPEG file matches the PNG/DIB the same decoded picture is put on the clipboard as
+        let jpeg = encode(&gradient(8, 6), ImageFormat::Jpeg);
+        let decoded = image::load_from_memory(&jpeg).unwrap();
+        let jpeg_key = ClipboardMonitor::compute_image_key(&jpeg);
+        assert!(jpeg_key.is_some());
+        assert_eq!(jpeg_key, ClipboardMonitor::compute_image_key(&encode(&decoded, ImageFormat::Png)));
+        assert_eq!(jpeg_key, ClipboardMonitor::compute_image_key(&encode(&decoded, ImageFormat::Bmp)));
+    }
+
+    #[test]
+    fn image_key_differs_for_different_images() {
+        let key = |img: &image::DynamicImage| ClipboardMonitor::compute_image_key(&encode(img, ImageFormat::Png));
+        assert_ne!(key(&gradient(8, 6)), key(&gradient(6, 8)));
+        assert_ne!(key(&gradient(8, 6)), key(&image::DynamicImage::ImageRgb8(RgbImage::new(8, 6))));
+    }
+
+    #[test]
+    fn image_key_of_invalid_data_is_none() {
+        assert_eq!(ClipboardMonitor::compute_image_key(b"not an image"), None);
+    }
+}
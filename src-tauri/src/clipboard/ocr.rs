@@ -0,0 +1,62 @@
+//! EveryPaste - Text recognition for images
+//!
+//! Uses the Windows OCR engine (Windows.Media.Ocr) with the user's profile languages
+
+use std::path::Path;
+
+/// Recognize the text in an image file
+///
+/// Returns the recognized text (lines separated by newlines), which may be empty
+#[cfg(target_os = "windows")]
+pub fn recognize_text(path: &Path) -> Result<String, String> {
+    use windows::Graphics::Imaging::{BitmapPixelFormat, SoftwareBitmap};
+    use windows::Media::Ocr::OcrEngine;
+    use windows::Storage::Streams::DataWriter;
+    use windows::Win32::System::WinRT::{RoInitialize, RO_INIT_MULTITHREADED};
+
+    let image = image::open(path).map_err(|e| format!("Failed to load image: {}", e))?;
+
+    // Scale down images the engine refuses to process
+    let max_dimension = OcrEngine::MaxImageDimension().map_err(|e| format!("OCR unavailable: {}", e))?;
+    let image = if image.width() > max_dimension || image.height() > max_dimension {
+        image.resize(max_dimension, max_dimension, image::imageops::FilterType::Triangle)
+    } else {
+        image
+    };
+
+    // The engine accepts Gray8 or Bgra8 bitmaps
+    let mut pixels = image.to_rgba8();
+    for pixel in pixels.pixels_mut() {
+        pixel.0.swap(0, 2);
+    }
+    let (width, height) = pixels.dimensions();
+
+    // Commands run on worker threads without a WinRT apartment (already initialized is fine)
+    let _ = unsafe { RoInitialize(RO_INIT_MULTITHREADED) };
+
+    let recognize = || -> windows::core::Result<String> {
+        let writer = DataWriter::new()?;
+        writer.WriteBytes(pixels.as_raw())?;
+        let buffer = writer.DetachBuffer()?;
+        let bitmap = SoftwareBitmap::CreateCopyFromBuffer(&buffer, BitmapPixelFormat::Bgra8, width as i32, height as i32)?;
+
+        let engine = OcrEngine::TryCreateFromUserProfileLanguages()?;
+        let result = engine.RecognizeAsync(&bitmap)?.get()?;
+
+        let lines = result.Lines()?;
+        let text = lines
+            .into_iter()
+            .filter_map(|line| line.Text().ok())
+            .map(|text| text.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        Ok(text)
+    };
+
+    recognize().map_err(|e| format!("OCR failed: {}", e))
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn recognize_text(_path: &Path) -> Result<String, String> {
+    Err("OCR is only supported on Windows".to_string())
+}
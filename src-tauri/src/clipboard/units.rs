@@ -0,0 +1,157 @@
+//! EveryPaste - Measurement parsing and unit conversion
+//!
+//! Recognizes clipboard text that is a single number with a unit (e.g. "72°F", "10 km")
+
+/// Physical dimension of a unit (only units of the same dimension convert)
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Dimension {
+    Length,
+    Mass,
+    Volume,
+    Temperature,
+}
+
+/// Unit definition
+struct Unit {
+    /// Canonical unit symbol
+    symbol: &'static str,
+    /// Accepted spellings (matched case-insensitively)
+    aliases: &'static [&'static str],
+    dimension: Dimension,
+    /// Factor to the dimension's base unit (m, kg, l), unused for temperature
+    factor: f64,
+}
+
+/// Supported units
+const UNITS: &[Unit] = &[
+    // Length (base: m)
+    Unit { symbol: "mm", aliases: &["mm"], dimension: Dimension::Length, factor: 0.001 },
+    Unit { symbol: "cm", aliases: &["cm"], dimension: Dimension::Length, factor: 0.01 },
+    Unit { symbol: "m", aliases: &["m"], dimension: Dimension::Length, factor: 1.0 },
+    Unit { symbol: "km", aliases: &["km"], dimension: Dimension::Length, factor: 1000.0 },
+    Unit { symbol: "in", aliases: &["in", "inch", "inches"], dimension: Dimension::Length, factor: 0.0254 },
+    Unit { symbol: "ft", aliases: &["ft", "feet", "foot"], dimension: Dimension::Length, factor: 0.3048 },
+    Unit { symbol: "yd", aliases: &["yd", "yard", "yards"], dimension: Dimension::Length, factor: 0.9144 },
+    Unit { symbol: "mi", aliases: &["mi", "mile", "miles"], dimension: Dimension::Length, factor: 1609.344 },
+    // Mass (base: kg)
+    Unit { symbol: "mg", aliases: &["mg"], dimension: Dimension::Mass, factor: 0.000001 },
+    Unit { symbol: "g", aliases: &["g"], dimension: Dimension::Mass, factor: 0.001 },
+    Unit { symbol: "kg", aliases: &["kg"], dimension: Dimension::Mass, factor: 1.0 },
+    Unit { symbol: "oz", aliases: &["oz"], dimension: Dimension::Mass, factor: 0.028349523125 },
+    Unit { symbol: "lb", aliases: &["lb", "lbs"], dimension: Dimension::Mass, factor: 0.45359237 },
+    // Volume (base: l)
+    Unit { symbol: "ml", aliases: &["ml"], dimension: Dimension::Volume, factor: 0.001 },
+    Unit { symbol: "l", aliases: &["l"], dimension: Dimension::Volume, factor: 1.0 },
+    Unit { symbol: "gal", aliases: &["gal", "gallon", "gallons"], dimension: Dimension::Volume, factor: 3.785411784 },
+    // Temperature (bare "c", "f" and "k" are too ambiguous, "10k" is rarely Kelvin)
+    Unit { symbol: "°C", aliases: &["°c", "℃", "celsius"], dimension: Dimension::Temperature, factor: 1.0 },
+    Unit { symbol: "°F", aliases: &["°f", "℉", "fahrenheit"], dimension: Dimension::Temperature, factor: 1.0 },
+    Unit { symbol: "K", aliases: &["kelvin"], dimension: Dimension::Temperature, factor: 1.0 },
+];
+
+/// Find a unit by symbol or alias
+fn find_unit(name: &str) -> Option<&'static Unit> {
+    let name = name.trim().to_lowercase();
+    UNITS.iter().find(|unit| unit.aliases.contains(&name.as_str()))
+}
+
+/// Find a unit by its canonical symbol (as stored with a record) or an alias
+fn resolve_unit(name: &str) -> Option<&'static Unit> {
+    UNITS.iter().find(|unit| unit.symbol == name.trim()).or_else(|| find_unit(name))
+}
+
+/// Parse text that is exactly a number followed by a unit
+///
+/// Returns the value and the canonical unit symbol
+pub fn parse_measurement(text: &str) -> Option<(f64, &'static str)> {
+    let text = text.trim();
+
+    // Split the leading number (optional sign, digits, decimal point)
+    let number_len = text
+        .char_indices()
+        .find(|&(i, c)| !(c.is_ascii_digit() || c == '.' || (i == 0 && (c == '-' || c == '+'))))
+        .map(|(i, _)| i)?;
+    let (number, unit) = text.split_at(number_len);
+
+    if !number.chars().any(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let value: f64 = number.parse().ok()?;
+
+    // Allow a single space between number and unit
+    let unit = unit.strip_prefix(' ').unwrap_or(unit);
+    if unit.is_empty() || unit.starts_with(' ') {
+        return None;
+    }
+
+    find_unit(unit).map(|unit| (value, unit.symbol))
+}
+
+/// Convert a value between units of the same dimension
+pub fn convert(value: f64, from: &str, to: &str) -> Result<f64, String> {
+    let from_unit = resolve_unit(from).ok_or_else(|| format!("Unknown unit: {}", from))?;
+    let to_unit = resolve_unit(to).ok_or_else(|| format!("Unknown unit: {}", to))?;
+
+    if from_unit.dimension != to_unit.dimension {
+        return Err(format!("Cannot convert {} to {}", from_unit.symbol, to_unit.symbol));
+    }
+
+    if from_unit.dimension == Dimension::Temperature {
+        let celsius = match from_unit.symbol {
+            "°F" => (value - 32.0) * 5.0 / 9.0,
+            "K" => value - 273.15,
+            _ => value,
+        };
+        return Ok(match to_unit.symbol {
+            "°F" => celsius * 9.0 / 5.0 + 32.0,
+            "K" => celsius + 273.15,
+            _ => celsius,
+        });
+    }
+
+    Ok(value * from_unit.factor / to_unit.factor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_number_with_unit() {
+        assert_eq!(parse_measurement("72°F"), Some((72.0, "°F")));
+        assert_eq!(parse_measurement("10 km"), Some((10.0, "km")));
+        assert_eq!(parse_measurement("-3.5 °C"), Some((-3.5, "°C")));
+        assert_eq!(parse_measurement("300 kelvin"), Some((300.0, "K")));
+        assert_eq!(parse_measurement("20 Celsius"), Some((20.0, "°C")));
+        assert_eq!(parse_measurement("6 feet"), Some((6.0, "ft")));
+    }
+
+    #[test]
+    fn rejects_ambiguous_temperature_letters() {
+        assert_eq!(parse_measurement("10k"), None);
+        assert_eq!(parse_measurement("5 f"), None);
+        assert_eq!(parse_measurement("5 c"), None);
+    }
+
+    #[test]
+    fn rejects_quote_marks() {
+        assert_eq!(parse_measurement("12\""), None);
+        assert_eq!(parse_measurement("6'"), None);
+    }
+
+    #[test]
+    fn rejects_text_around_the_measurement() {
+        assert_eq!(parse_measurement("10 km away"), None);
+        assert_eq!(parse_measurement("about 10 km"), None);
+        assert_eq!(parse_measurement("10  km"), None);
+        assert_eq!(parse_measurement("km"), None);
+    }
+
+    #[test]
+    fn converts_by_symbol_or_alias() {
+        assert_eq!(convert(100.0, "°C", "°F"), Ok(212.0));
+        assert_eq!(convert(0.0, "°C", "K"), Ok(273.15));
+        assert_eq!(convert(1.0, "km", "m"), Ok(1000.0));
+        assert!(convert(1.0, "km", "kg").is_err());
+    }
+}
@@ -6,9 +6,9 @@ use tauri::{AppHandle, Manager, Emitter};
 use arboard::Clipboard;
 use serde::{Deserialize, Serialize};
 
-use crate::clipboard::{ClipboardItem, ClipboardItemView, ContentType};
+use crate::clipboard::{self, ocr, units, ClipboardItem, ClipboardItemView, ClipboardMonitor, ClipboardSnapshot, ContentType};
 use crate::storage;
-use crate::config::{self, Settings, Theme, StorageLimit};
+use crate::config::{self, Settings, Theme, StorageLimit, LineEndings, PasteKeystroke, StartupWindow, DuplicateAction};
 
 /// Command execution result
 #[derive(Debug, Serialize)]
@@ -36,10 +36,25 @@ impl<T> CommandResult<T> {
     }
 }
 
-/// Get clipboard history list
+/// Get clipboard history list (`offset` skips records for paging)
 #[tauri::command]
-pub fn get_clipboard_history(limit: Option<i32>) -> CommandResult<Vec<ClipboardItemView>> {
-    match storage::get_all_items(limit) {
+pub fn get_clipboard_history(limit: Option<i32>, offset: Option<i32>) -> CommandResult<Vec<ClipboardItemView>> {
+    // Serve the first page from memory when the cache covers the request
+    if offset.is_none_or(|n| n <= 0) {
+        let cache_limit = limit.filter(|&n| n > 0).map(|n| n as usize);
+        if let Some(views) = storage::get_recent_items(cache_limit) {
+            return CommandResult::ok(views);
+        }
+        if cache_limit.is_some_and(|n| n <= storage::RECENT_CACHE_SIZE) {
+            if let Err(e) = storage::refresh_recent_cache() {
+                log::warn!("Failed to refresh recent records cache: {}", e);
+            } else if let Some(views) = storage::get_recent_items(cache_limit) {
+                return CommandResult::ok(views);
+            }
+        }
+    }
+    
+    match storage::get_all_items(limit, offset) {
         Ok(items) => {
             let views: Vec<ClipboardItemView> = items.into_iter().map(|i| i.into()).collect();
             CommandResult::ok(views)
@@ -48,6 +63,58 @@ pub fn get_clipboard_history(limit: Option<i32>) -> CommandResult<Vec<ClipboardI
     }
 }
 
+/// Number of quick-paste slots (Ctrl+Shift+1..9)
+const QUICK_PASTE_SLOTS: i32 = 9;
+
+/// Get the records mapped to the quick-paste slots, in slot order
+/// 
+/// Slot N pastes the Nth record of the history list
+#[tauri::command]
+pub fn get_quick_paste_slots() -> CommandResult<Vec<ClipboardItemView>> {
+    match storage::get_all_items(Some(QUICK_PASTE_SLOTS), None) {
+        Ok(items) => CommandResult::ok(items.into_iter().map(|i| i.into()).collect()),
+        Err(e) => CommandResult::err(format!("Failed to get quick-paste slots: {}", e)),
+    }
+}
+
+/// A page of records for display with the total number of matching records
+#[derive(Debug, Serialize)]
+pub struct ItemsPageView {
+    pub items: Vec<ClipboardItemView>,
+    pub total: i64,
+}
+
+impl From<storage::ItemsPage> for ItemsPageView {
+    fn from(page: storage::ItemsPage) -> Self {
+        Self {
+            items: page.items.into_iter().map(|i| i.into()).collect(),
+            total: page.total,
+        }
+    }
+}
+
+/// Get a page of clipboard history with the total record count
+#[tauri::command]
+pub fn get_history_page(limit: i32, offset: i32) -> CommandResult<ItemsPageView> {
+    match storage::get_items_page_with_total(limit, offset) {
+        Ok(page) => CommandResult::ok(page.into()),
+        Err(e) => CommandResult::err(format!("Failed to get clipboard history: {}", e)),
+    }
+}
+
+/// Get a page of search results with the total number of matches
+#[tauri::command]
+pub fn search_clipboard_page(query: String, limit: i32, offset: i32) -> CommandResult<ItemsPageView> {
+    if query.is_empty() {
+        return get_history_page(limit, offset);
+    }
+    
+    match storage::search_page_with_total(&query, limit, offset) {
+        Ok(page) => CommandResult::ok(page.into()),
+        Err(e) => CommandResult::err(format!("Search failed: {}", e)),
+    }
+}
+
 /// Get complete content of a single clipboard record
 #[tauri::command]
 pub fn get_clipboard_item(id: i64) -> CommandResult<ClipboardItem> {
@@ -58,283 +125,2301 @@ pub fn get_clipboard_item(id: i64) -> CommandResult<ClipboardItem> {
     }
 }
 
-/// Paste specified record (copy to system clipboard)
+/// Complete record with image content inlined
+#[derive(Debug, Serialize)]
+pub struct FullItem {
+    #[serde(flatten)]
+    pub item: ClipboardItem,
+    /// Image file content (Base64 data URI), only set for image records
+    pub image_data: Option<String>,
+}
+
+/// Get a record with its rich text and image content in a single call
 #[tauri::command]
-pub fn paste_item(app: AppHandle, id: i64, as_plain_text: bool) -> CommandResult<bool> {
-    // Get record
+pub fn get_item_full(app: AppHandle, id: i64) -> CommandResult<FullItem> {
     let item = match storage::get_item_by_id(id) {
         Ok(Some(item)) => item,
         Ok(None) => return CommandResult::err(format!("Item not found: {}", id)),
         Err(e) => return CommandResult::err(format!("Failed to get item: {}", e)),
     };
 
-    // Create clipboard instance
-    let mut clipboard = match Clipboard::new() {
-        Ok(cb) => cb,
-        Err(e) => return CommandResult::err(format!("Failed to access clipboard: {}", e)),
+    let image_data = match (&item.content_type, &item.image_path) {
+        (ContentType::Image, Some(image_path)) if !item.encrypted => {
+            let full_path = match app.path().app_data_dir() {
+                Ok(dir) => dir.join(image_path),
+                Err(e) => return CommandResult::err(format!("Failed to get app data dir: {}", e)),
+            };
+            match std::fs::read(&full_path) {
+                Ok(bytes) => Some(image_data_uri(&bytes)),
+                Err(e) => return CommandResult::err(format!("Failed to read image file: {}", e)),
+            }
+        }
+        _ => None,
     };
 
+    CommandResult::ok(FullItem { item, image_data })
+}
+
+/// Base64 data URI of an image file (stored images may be PNG, JPEG or WebP)
+fn image_data_uri(bytes: &[u8]) -> String {
+    use base64::Engine;
+
+    let mime = image::guess_format(bytes).map(|format| format.to_mime_type()).unwrap_or("image/png");
+    format!("data:{};base64,{}", mime, base64::engine::general_purpose::STANDARD.encode(bytes))
+}
+
+/// Write a record's content to the system clipboard
+fn set_clipboard_to_item(app: &AppHandle, item: &ClipboardItem, as_plain_text: bool) -> Result<(), String> {
+    if item.encrypted {
+        return Err("Encrypted items must be decrypted before pasting".to_string());
+    }
+
+    // Create clipboard instance (the monitor can't read while the content is being set)
+    let _access = clipboard::lock_clipboard();
+    let mut clipboard = Clipboard::new().map_err(|e| format!("Failed to access clipboard: {}", e))?;
+
     // Paste according to content type
     match item.content_type {
-        ContentType::Text => {
+        ContentType::Text | ContentType::Link | ContentType::Color => {
             if let Some(text) = &item.plain_text {
-                if let Err(e) = clipboard.set_text(text) {
-                    return CommandResult::err(format!("Failed to set clipboard text: {}", e));
-                }
+                let text = config::get_settings().normalize_line_endings.apply(text);
+                clipboard.set_text(text).map_err(|e| format!("Failed to set clipboard text: {}", e))?;
             }
         }
         ContentType::RichText => {
-            // If plain text requested or no rich text, use plain text
-            let text = if as_plain_text {
-                item.plain_text.as_deref()
-            } else {
-                // Prefer plain text (rich text not directly supported yet)
-                item.plain_text.as_deref()
-            };
+            // Plain text always goes on the clipboard as the alternative
+            if let Some(text) = item.plain_text.as_deref() {
+                clipboard.set_text(text).map_err(|e| format!("Failed to set clipboard text: {}", e))?;
+            }
             
-            if let Some(text) = text {
-                if let Err(e) = clipboard.set_text(text) {
-                    return CommandResult::err(format!("Failed to set clipboard text: {}", e));
+            // Add the HTML as CF_HTML unless plain text was requested
+            if !as_plain_text {
+                if let Some(html) = &item.rich_text {
+                    if let Err(e) = ClipboardMonitor::add_html_format(html) {
+                        log::warn!("Failed to set clipboard HTML, pasting plain text: {}", e);
+                    }
                 }
             }
         }
         ContentType::Image => {
             // Image needs to be loaded from file
-            if let Some(image_path) = &item.image_path {
-                // Parse full image path
-                let full_path = match app.path().app_data_dir() {
-                    Ok(path_buf) => path_buf.join(image_path),
-                    Err(e) => return CommandResult::err(format!("Failed to get app data dir: {}", e)),
-                };
-                
-                log::info!("Pasting image from: {:?}", full_path);
-                
-                // Read image from file
-                let img = match image::open(&full_path) {
-                    Ok(i) => i.into_rgba8(),
-                    Err(e) => return CommandResult::err(format!("Failed to read image file: {}", e)),
-                };
-                
-                let (width, height) = img.dimensions();
-                let image_data = arboard::ImageData {
-                    width: width as usize,
-                    height: height as usize,
-                    bytes: std::borrow::Cow::Owned(img.into_raw()),
-                };
-                
-                if let Err(e) = clipboard.set_image(image_data) {
-                     return CommandResult::err(format!("Failed to set clipboard image: {}", e));
-                }
-            } else {
-                 return CommandResult::err("Image path is missing".to_string());
+            let image_path = item.image_path.as_ref().ok_or("Image path is missing")?;
+            
+            // Parse full image path
+            let full_path = app.path().app_data_dir()
+                .map_err(|e| format!("Failed to get app data dir: {}", e))?
+                .join(image_path);
+            
+            log::info!("Pasting image from: {:?}", full_path);
+            
+            // Read image from file
+            let img = image::open(&full_path)
+                .map_err(|e| format!("Failed to read image file: {}", e))?
+                .into_rgba8();
+            
+            let (width, height) = img.dimensions();
+            let image_data = arboard::ImageData {
+                width: width as usize,
+                height: height as usize,
+                bytes: std::borrow::Cow::Owned(img.into_raw()),
+            };
+            
+            clipboard.set_image(image_data).map_err(|e| format!("Failed to set clipboard image: {}", e))?;
+        }
+        ContentType::File => {
+            // Skip files that were moved or deleted since they were copied
+            let (existing, missing): (Vec<String>, Vec<String>) = item.file_paths()
+                .into_iter()
+                .partition(|path| std::path::Path::new(path).exists());
+            if !missing.is_empty() {
+                log::warn!("Skipping {} missing file(s): {:?}", missing.len(), missing);
+            }
+            if existing.is_empty() {
+                return Err("None of the copied files exist anymore".to_string());
             }
+            ClipboardMonitor::write_file_list(&existing)?;
+        }
+        ContentType::Unsupported => {
+            return Err("Unsupported content cannot be pasted".to_string());
         }
     }
 
-    CommandResult::ok(true)
+    // RTF-aware targets (word processors) pick the RTF over the text and HTML
+    if let (false, Some(rtf)) = (as_plain_text, &item.rtf) {
+        if let Err(e) = ClipboardMonitor::add_rtf_format(rtf) {
+            log::warn!("Failed to add RTF to the clipboard: {}", e);
+        }
+    }
+
+    Ok(())
 }
 
-/// Delete specified record
-#[tauri::command]
-pub fn delete_item(id: i64) -> CommandResult<bool> {
-    match storage::delete_item(id) {
-        Ok(deleted) => CommandResult::ok(deleted),
-        Err(e) => CommandResult::err(format!("Failed to delete item: {}", e)),
-    }
+/// Result of a paste request
+#[derive(Debug, Serialize)]
+pub struct PasteOutcome {
+    /// Content was copied to the system clipboard
+    pub pasted: bool,
+    /// Content exceeds the large paste threshold, call confirm_large_paste to proceed
+    pub requires_confirmation: bool,
+    /// Estimated size of the pasted content in bytes
+    pub size_bytes: u64,
 }
 
-/// Clear all history records
-#[tauri::command]
-pub fn clear_all_history() -> CommandResult<bool> {
-    match storage::clear_all_items() {
-        Ok(()) => CommandResult::ok(true),
-        Err(e) => CommandResult::err(format!("Failed to clear history: {}", e)),
+/// Estimate how many bytes a record puts on the clipboard
+/// 
+/// Images are measured decoded (RGBA), since that is what the target app receives
+fn estimate_paste_size(app: &AppHandle, item: &ClipboardItem) -> u64 {
+    match item.content_type {
+        ContentType::Text | ContentType::RichText | ContentType::Link | ContentType::Color | ContentType::File => {
+            item.plain_text.as_ref().map(|t| t.len() as u64).unwrap_or(0)
+        }
+        ContentType::Image => {
+            let full_path = match (&item.image_path, app.path().app_data_dir()) {
+                (Some(image_path), Ok(data_dir)) => data_dir.join(image_path),
+                _ => return 0,
+            };
+            match image::image_dimensions(&full_path) {
+                Ok((width, height)) => width as u64 * height as u64 * 4,
+                Err(_) => std::fs::metadata(&full_path).map(|m| m.len()).unwrap_or(0),
+            }
+        }
+        ContentType::Unsupported => 0,
     }
 }
 
-/// Search clipboard records
-#[tauri::command]
-pub fn search_clipboard(query: String, limit: Option<i32>) -> CommandResult<Vec<ClipboardItemView>> {
-    if query.is_empty() {
-        return get_clipboard_history(limit);
+/// Copy a record to the system clipboard, optionally checking the large paste threshold
+fn paste_item_checked(app: &AppHandle, id: i64, as_plain_text: bool, check_size: bool) -> Result<PasteOutcome, String> {
+    // Get record
+    let item = storage::get_item_by_id(id)
+        .map_err(|e| format!("Failed to get item: {}", e))?
+        .ok_or_else(|| format!("Item not found: {}", id))?;
+
+    let size_bytes = estimate_paste_size(app, &item);
+    if check_size {
+        if let Some(threshold_kb) = config::get_settings().large_paste_threshold_kb {
+            if size_bytes > threshold_kb as u64 * 1024 {
+                log::info!("Paste of item {} ({} bytes) needs confirmation", id, size_bytes);
+                return Ok(PasteOutcome { pasted: false, requires_confirmation: true, size_bytes });
+            }
+        }
     }
-    
-    match storage::search_items(&query, limit) {
-        Ok(items) => {
-            let views: Vec<ClipboardItemView> = items.into_iter().map(|i| i.into()).collect();
-            CommandResult::ok(views)
+
+    // Save current clipboard content so it can be restored after the paste
+    crate::save_clipboard_for_restore();
+
+    set_clipboard_to_item(app, &item, as_plain_text)?;
+    if let Err(e) = storage::record_paste(id) {
+        log::warn!("Failed to update use count: {}", e);
+    }
+    if config::get_settings().bump_on_paste {
+        match storage::bump_item(id) {
+            Ok(_) => {
+                let _ = app.emit("clipboard-updated", ());
+            }
+            Err(e) => log::warn!("Failed to move pasted item to the top: {}", e),
         }
-        Err(e) => CommandResult::err(format!("Search failed: {}", e)),
     }
+    Ok(PasteOutcome { pasted: true, requires_confirmation: false, size_bytes })
 }
 
-/// Get current settings
+/// Paste specified record (copy to system clipboard)
+/// 
+/// Content above the large paste threshold is not pasted, the result asks
+/// the UI to confirm first
 #[tauri::command]
-pub fn get_settings(app: AppHandle) -> CommandResult<Settings> {
-    let mut settings = config::get_settings();
-    
-    // Get actual auto-start status from autostart plugin
-    use tauri_plugin_autostart::ManagerExt;
-    if let Ok(is_enabled) = app.autolaunch().is_enabled() {
-        settings.auto_start = is_enabled;
+pub fn paste_item(app: AppHandle, id: i64, as_plain_text: bool) -> CommandResult<PasteOutcome> {
+    match paste_item_checked(&app, id, as_plain_text, true) {
+        Ok(outcome) => CommandResult::ok(outcome),
+        Err(e) => CommandResult::err(e),
     }
-    
-    CommandResult::ok(settings)
 }
 
-/// Settings update request from frontend
-#[derive(Debug, Deserialize)]
-pub struct SettingsUpdate {
-    pub theme: Option<String>,
-    pub storage_limit: Option<i32>,
-    pub auto_start: Option<bool>,
-    pub shortcut: Option<String>,
+/// Paste specified record without the large paste check, after the user confirmed
+#[tauri::command]
+pub fn confirm_large_paste(app: AppHandle, id: i64, as_plain_text: bool) -> CommandResult<PasteOutcome> {
+    match paste_item_checked(&app, id, as_plain_text, false) {
+        Ok(outcome) => CommandResult::ok(outcome),
+        Err(e) => CommandResult::err(e),
+    }
 }
 
-/// Update settings
+/// Swap the system clipboard with a stored record
+/// 
+/// The current clipboard content is saved as a record first, its id is
+/// returned so the UI can offer to swap back
 #[tauri::command]
-pub fn update_settings(updates: SettingsUpdate, _app: AppHandle) -> CommandResult<Settings> {
-    let mut settings = config::get_settings();
-    
-    // Update theme
-    if let Some(theme) = updates.theme {
-        settings.theme = match theme.as_str() {
-            "dark" => Theme::Dark,
-            _ => Theme::Light,
-        };
+pub fn swap_clipboard(app: AppHandle, id: i64) -> CommandResult<i64> {
+    let item = match storage::get_item_by_id(id) {
+        Ok(Some(item)) => item,
+        Ok(None) => return CommandResult::err(format!("Item not found: {}", id)),
+        Err(e) => return CommandResult::err(format!("Failed to get item: {}", e)),
+    };
+
+    let result = crate::with_monitor_paused(|| {
+        let snapshot = ClipboardSnapshot::capture().ok_or("Clipboard is empty")?;
+        let previous_id = crate::handle_new_clipboard_content(&app, snapshot)
+            .ok_or("Failed to save current clipboard content")?;
+        set_clipboard_to_item(&app, &item, false)?;
+        Ok::<i64, String>(previous_id)
+    });
+
+    match result {
+        Ok(previous_id) => CommandResult::ok(previous_id),
+        Err(e) => CommandResult::err(e),
     }
-    
-    // Update storage limit
-    if let Some(limit) = updates.storage_limit {
-        settings.storage_limit = StorageLimit::from_i32(limit);
-        
-        // Cleanup old records exceeding limit
-        if limit > 0 {
-            if let Err(e) = storage::cleanup_old_items(limit) {
-                log::warn!("Failed to cleanup old items: {}", e);
-            }
+}
+
+/// Toggle the pinned state of a record
+/// 
+/// Returns the new pinned state
+#[tauri::command]
+pub fn toggle_pin(app: AppHandle, id: i64) -> CommandResult<bool> {
+    let item = match storage::get_item_by_id(id) {
+        Ok(Some(item)) => item,
+        Ok(None) => return CommandResult::err(format!("Item not found: {}", id)),
+        Err(e) => return CommandResult::err(format!("Failed to get item: {}", e)),
+    };
+
+    let pinned = !item.is_pinned;
+    match storage::set_pinned(id, pinned) {
+        Ok(true) => {
+            let _ = app.emit("clipboard-updated", ());
+            CommandResult::ok(pinned)
         }
+        Ok(false) => CommandResult::err(format!("Item not found: {}", id)),
+        Err(e) => CommandResult::err(format!("Failed to set pin: {}", e)),
     }
-    
-    // Update auto-start
-    if let Some(auto_start) = updates.auto_start {
-        settings.auto_start = auto_start;
-        
-        // Use tauri-plugin-autostart plugin
-        use tauri_plugin_autostart::ManagerExt;
-        
-        let autostart_manager = _app.autolaunch();
-        let result = if auto_start {
-            autostart_manager.enable()
-        } else {
-            autostart_manager.disable()
-        };
-        
-        match result {
-            Ok(_) => {
-                log::info!("Autostart {} successfully", if auto_start { "enabled" } else { "disabled" });
-            },
-            Err(e) => {
-                log::error!("Failed to update autostart: {}", e);
-                return CommandResult::err(format!("Failed to update autostart: {}", e));
+}
+
+/// Pin a record until a date, or remove a timed pin (iso_date = None)
+/// 
+/// Accepts an RFC 3339 timestamp or a date (YYYY-MM-DD, pinned through the end of that local day)
+#[tauri::command]
+pub fn set_pin_until(app: AppHandle, id: i64, iso_date: Option<String>) -> CommandResult<bool> {
+    use chrono::{DateTime, Local, NaiveDate, Utc};
+
+    let until = match iso_date.as_deref() {
+        None => None,
+        Some(value) => {
+            let parsed = DateTime::parse_from_rfc3339(value)
+                .map(|dt| dt.with_timezone(&Utc))
+                .ok()
+                .or_else(|| {
+                    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+                    date.succ_opt()?
+                        .and_time(chrono::NaiveTime::MIN)
+                        .and_local_timezone(Local)
+                        .earliest()
+                        .map(|dt| dt.with_timezone(&Utc))
+                });
+            match parsed {
+                Some(until) if until > Utc::now() => Some(until),
+                Some(_) => return CommandResult::err(format!("Pin date is in the past: {}", value)),
+                None => return CommandResult::err(format!("Invalid date: {}", value)),
             }
         }
-    }
-    
-    // Update shortcut
-    if let Some(ref shortcut) = updates.shortcut {
-        settings.shortcut = shortcut.clone();
-        
-        // Dynamically register new shortcut
-        use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
-        
-        // Parse shortcut string, convert "Win" to "super" as required by Tauri
-        let shortcut_str = shortcut.to_lowercase().replace("win", "super");
-        if let Ok(new_shortcut) = shortcut_str.parse::<Shortcut>() {
-            // Unregister all possible old shortcuts first
-            let _ = _app.global_shortcut().unregister_all();
-            
-            // Register new shortcut
-            let result = _app.global_shortcut().on_shortcut(new_shortcut, move |app, _shortcut, event| {
-                if event.state == ShortcutState::Pressed {
-                    log::debug!("Custom shortcut pressed - toggling window");
-                    if let Some(window) = app.get_webview_window("main") {
-                        if window.is_visible().unwrap_or(false) {
-                            let _ = window.hide();
-                        } else {
-                            let _ = window.show();
-                            let _ = window.set_focus();
-                            let _ = app.emit("window-shown", ());
-                            let _ = app.emit("focus-first-item", ());
-                        }
-                    }
-                }
-            });
-            
-            match result {
-                Ok(()) => log::info!("Custom shortcut '{}' registered successfully", shortcut),
-                Err(e) => {
-                    log::error!("Failed to register custom shortcut '{}': {}", shortcut, e);
-                    return CommandResult::err(format!("Failed to register shortcut: {}", e));
-                }
-            }
-        } else {
-            log::error!("Invalid shortcut format: {}", shortcut);
-            return CommandResult::err(format!("Invalid shortcut format: {}", shortcut));
+    };
+
+    match storage::set_pin_until(id, until) {
+        Ok(true) => {
+            let _ = app.emit("clipboard-updated", ());
+            CommandResult::ok(true)
         }
-    }
-    
-    // Save settings
-    match config::update_settings(settings.clone()) {
-        Ok(()) => CommandResult::ok(settings),
-        Err(e) => CommandResult::err(format!("Failed to save settings: {}", e)),
+        Ok(false) => CommandResult::err(format!("Item not found: {}", id)),
+        Err(e) => CommandResult::err(format!("Failed to set pin: {}", e)),
     }
 }
 
-
-/// Show main window
+/// Duplicate a record as a new, independent copy
+/// 
+/// The copy gets a fresh timestamp and a new hash (the source hash is UNIQUE),
+/// image records get their own image file
 #[tauri::command]
-pub fn show_main_window(app: AppHandle) -> CommandResult<bool> {
-    if let Some(window) = app.get_webview_window("main") {
-        let _ = window.show();
-        let _ = window.set_focus();
-        CommandResult::ok(true)
-    } else {
-        CommandResult::err("Main window not found".to_string())
+pub fn duplicate_item(app: AppHandle, id: i64) -> CommandResult<i64> {
+    let mut item = match storage::get_item_by_id(id) {
+        Ok(Some(item)) => item,
+        Ok(None) => return CommandResult::err(format!("Item not found: {}", id)),
+        Err(e) => return CommandResult::err(format!("Failed to get item: {}", e)),
+    };
+
+    if item.encrypted {
+        return CommandResult::err(format!("Cannot duplicate encrypted item: {}", id));
     }
-}
 
-/// Hide main window
-#[tauri::command]
-pub fn hide_main_window(app: AppHandle) -> CommandResult<bool> {
-    if let Some(window) = app.get_webview_window("main") {
-        let _ = window.hide();
-        CommandResult::ok(true)
-    } else {
-        CommandResult::err("Main window not found".to_string())
+    let copy_marker = uuid::Uuid::new_v4();
+    item.hash = ClipboardMonitor::compute_hash(format!("{}:copy:{}", item.hash, copy_marker).as_bytes());
+    item.created_at = chrono::Utc::now();
+    item.updated_at = item.created_at;
+    item.is_pinned = false;
+    item.pin_until = None;
+    // The canonical image key is UNIQUE as well, copies don't take part in image deduplication
+    item.image_key = None;
+
+    if let Some(image_path) = &item.image_path {
+        let data_dir = match app.path().app_data_dir() {
+            Ok(dir) => dir,
+            Err(e) => return CommandResult::err(format!("Failed to get app data dir: {}", e)),
+        };
+        let new_path = format!("images/{}.png", copy_marker);
+        if let Err(e) = std::fs::copy(data_dir.join(image_path), data_dir.join(&new_path)) {
+            return CommandResult::err(format!("Failed to copy image file: {}", e));
+        }
+        item.image_path = Some(new_path);
     }
-}
 
-/// Get total record count
-#[tauri::command]
-pub fn get_history_count() -> CommandResult<i64> {
-    match storage::get_item_count() {
-        Ok(count) => CommandResult::ok(count),
-        Err(e) => CommandResult::err(format!("Failed to get count: {}", e)),
+    match storage::insert_clipboard_item(&item) {
+        Ok(new_id) => {
+            let _ = app.emit("clipboard-updated", ());
+            CommandResult::ok(new_id)
+        }
+        Err(e) => CommandResult::err(format!("Failed to duplicate item: {}", e)),
     }
 }
 
-/// Check if this is the first run
+/// Append the current clipboard text to a text record (builds up a scratchpad item)
 #[tauri::command]
-pub fn is_first_run() -> CommandResult<bool> {
-    CommandResult::ok(config::is_first_run())
+pub fn append_clipboard_to_item(app: AppHandle, id: i64, separator: String) -> CommandResult<bool> {
+    let item = match storage::get_item_by_id(id) {
+        Ok(Some(item)) => item,
+        Ok(None) => return CommandResult::err(format!("Item not found: {}", id)),
+        Err(e) => return CommandResult::err(format!("Failed to get item: {}", e)),
+    };
+
+    if item.content_type != ContentType::Text || item.encrypted {
+        return CommandResult::err(format!("Item is not a text item: {}", id));
+    }
+
+    let read = {
+        let _access = clipboard::lock_clipboard();
+        Clipboard::new().and_then(|mut clipboard| clipboard.get_text())
+    };
+    let appended = match read {
+        Ok(text) if !text.is_empty() => text,
+        Ok(_) => return CommandResult::err("Clipboard text is empty".to_string()),
+        Err(e) => return CommandResult::err(format!("Failed to read clipboard text: {}", e)),
+    };
+
+    let text = format!("{}{}{}", item.plain_text.unwrap_or_default(), separator, appended);
+    let hash = ClipboardMonitor::compute_hash(text.as_bytes());
+
+    // Hashes are UNIQUE within a profile, refuse to turn this record into a copy of another one
+    match storage::get_item_id_by_hash(&hash) {
+        Ok(Some(existing)) if existing != id => {
+            return CommandResult::err(format!("Identical content already exists as item {}", existing));
+        }
+        Ok(_) => {}
+        Err(e) => return CommandResult::err(format!("Failed to check hash: {}", e)),
+    }
+
+    let preview = ClipboardItem::generate_preview(&text, config::get_settings().preview_length);
+    match storage::update_item_text(id, &ContentType::Text, &text, &preview, &hash) {
+        Ok(()) => {
+            let _ = app.emit("clipboard-updated", ());
+            CommandResult::ok(true)
+        }
+        Err(e) => CommandResult::err(format!("Failed to update item: {}", e)),
+    }
 }
 
-/// Mark first run as completed
+/// Replace the text of a text, rich text, link or color record
+/// 
+/// The record is classified again from the new text (rich text becomes plain text)
 #[tauri::command]
-pub fn complete_first_run() -> CommandResult<bool> {
-    match config::mark_first_run_completed() {
-        Ok(()) => CommandResult::ok(true),
-        Err(e) => CommandResult::err(format!("Failed to mark first run completed: {}", e)),
+pub fn update_item_text(app: AppHandle, id: i64, new_text: String) -> CommandResult<bool> {
+    let item = match storage::get_item_by_id(id) {
+        Ok(Some(item)) => item,
+        Ok(None) => return CommandResult::err(format!("Item not found: {}", id)),
+        Err(e) => return CommandResult::err(format!("Failed to get item: {}", e)),
+    };
+
+    let is_text = matches!(
+        item.content_type,
+        ContentType::Text | ContentType::RichText | ContentType::Link | ContentType::Color
+    );
+    if !is_text || item.encrypted {
+        return CommandResult::err(format!("Item is not a text item: {}", id));
+    }
+    if new_text.trim().is_empty() {
+        return CommandResult::err("Text is empty".to_string());
+    }
+
+    let hash = ClipboardMonitor::compute_hash(new_text.as_bytes());
+
+    // Hashes are UNIQUE within a profile, refuse to turn this record into a copy of another one
+    match storage::get_item_id_by_hash(&hash) {
+        Ok(Some(existing)) if existing != id => {
+            return CommandResult::err(format!("Identical content already exists as item {}", existing));
+        }
+        Ok(_) => {}
+        Err(e) => return CommandResult::err(format!("Failed to check hash: {}", e)),
+    }
+
+    let color = clipboard::color::parse_color(&new_text).map(clipboard::color::to_hex);
+    let (content_type, preview) = if clipboard::link::is_single_url(&new_text) {
+        (ContentType::Link, None)
+    } else if let Some(hex) = color {
+        (ContentType::Color, Some(hex))
+    } else {
+        (ContentType::Text, None)
+    };
+    let preview = preview.unwrap_or_else(|| {
+        ClipboardItem::generate_preview(&new_text, config::get_settings().preview_length)
+    });
+
+    match storage::update_item_text(id, &content_type, &new_text, &preview, &hash) {
+        Ok(()) => {
+            let _ = app.emit("clipboard-updated", ());
+            CommandResult::ok(true)
+        }
+        Err(e) => CommandResult::err(format!("Failed to update item: {}", e)),
+    }
+}
+
+/// Recognize the text in an image record and save it as a new text record
+/// 
+/// Returns the id of the text record (an existing one if the same text was already saved)
+#[tauri::command]
+pub fn image_to_text_item(app: AppHandle, id: i64) -> CommandResult<i64> {
+    let item = match storage::get_item_by_id(id) {
+        Ok(Some(item)) => item,
+        Ok(None) => return CommandResult::err(format!("Item not found: {}", id)),
+        Err(e) => return CommandResult::err(format!("Failed to get item: {}", e)),
+    };
+
+    let image_path = match (&item.content_type, &item.image_path) {
+        (ContentType::Image, Some(image_path)) if !item.encrypted => image_path,
+        _ => return CommandResult::err(format!("Item is not an image: {}", id)),
+    };
+
+    let full_path = match app.path().app_data_dir() {
+        Ok(dir) => dir.join(image_path),
+        Err(e) => return CommandResult::err(format!("Failed to get app data dir: {}", e)),
+    };
+
+    let text = match ocr::recognize_text(&full_path) {
+        Ok(text) => text.trim().to_string(),
+        Err(e) => return CommandResult::err(e),
+    };
+    if text.is_empty() {
+        return CommandResult::err("No text recognized in image".to_string());
+    }
+
+    let hash = ClipboardMonitor::compute_hash(text.as_bytes());
+    match storage::get_item_id_by_hash(&hash) {
+        Ok(Some(existing)) => return CommandResult::ok(existing),
+        Ok(None) => {}
+        Err(e) => return CommandResult::err(format!("Failed to check hash: {}", e)),
+    }
+
+    let mut text_item = ClipboardItem::new_text_with_len(0, text, hash, config::get_settings().preview_length);
+    text_item.source_app = item.source_app;
+    text_item.source_icon = item.source_icon;
+
+    match storage::insert_clipboard_item(&text_item) {
+        Ok(new_id) => {
+            let _ = app.emit("clipboard-updated", ());
+            CommandResult::ok(new_id)
+        }
+        Err(e) => CommandResult::err(format!("Failed to save text item: {}", e)),
+    }
+}
+
+/// Recognize the text in an image record, kept so searches find the image
+/// 
+/// Runs on a background thread, later calls return the stored text
+#[tauri::command]
+pub async fn ocr_item(app: AppHandle, id: i64) -> CommandResult<String> {
+    match storage::get_item_ocr_text(id) {
+        Ok(Some(text)) => return CommandResult::ok(text),
+        Ok(None) => {}
+        Err(e) => return CommandResult::err(format!("Failed to get OCR text: {}", e)),
+    }
+
+    let item = match storage::get_item_by_id(id) {
+        Ok(Some(item)) => item,
+        Ok(None) => return CommandResult::err(format!("Item not found: {}", id)),
+        Err(e) => return CommandResult::err(format!("Failed to get item: {}", e)),
+    };
+
+    let image_path = match (&item.content_type, &item.image_path) {
+        (ContentType::Image, Some(image_path)) if !item.encrypted => image_path,
+        _ => return CommandResult::err(format!("Item is not an image: {}", id)),
+    };
+
+    let full_path = match app.path().app_data_dir() {
+        Ok(dir) => dir.join(image_path),
+        Err(e) => return CommandResult::err(format!("Failed to get app data dir: {}", e)),
+    };
+
+    let text = match tauri::async_runtime::spawn_blocking(move || ocr::recognize_text(&full_path)).await {
+        Ok(Ok(text)) => text.trim().to_string(),
+        Ok(Err(e)) => return CommandResult::err(e),
+        Err(e) => return CommandResult::err(format!("OCR task failed: {}", e)),
+    };
+
+    if let Err(e) = storage::set_item_ocr_text(id, &text) {
+        return CommandResult::err(format!("Failed to save OCR text: {}", e));
+    }
+    CommandResult::ok(text)
+}
+
+/// Text content of an encrypted record
+#[derive(Serialize, Deserialize)]
+struct EncryptedPayload {
+    plain_text: Option<String>,
+    rich_text: Option<String>,
+    /// Missing in payloads written before RTF was stored
+    #[serde(default)]
+    rtf: Option<String>,
+}
+
+/// Encrypt a record at rest with a passphrase
+/// 
+/// Text is stored only as ciphertext, image files are replaced by an
+/// encrypted copy and the thumbnail is removed
+#[tauri::command]
+pub fn encrypt_item(app: AppHandle, id: i64, passphrase: String) -> CommandResult<bool> {
+    use base64::Engine;
+
+    if passphrase.is_empty() {
+        return CommandResult::err("Passphrase must not be empty".to_string());
+    }
+
+    let item = match storage::get_item_by_id(id) {
+        Ok(Some(item)) => item,
+        Ok(None) => return CommandResult::err(format!("Item not found: {}", id)),
+        Err(e) => return CommandResult::err(format!("Failed to get item: {}", e)),
+    };
+    if item.encrypted {
+        return CommandResult::err(format!("Item is already encrypted: {}", id));
+    }
+    if item.content_type == ContentType::Unsupported {
+        return CommandResult::err(format!("Item has no content to encrypt: {}", id));
+    }
+
+    let payload = EncryptedPayload { plain_text: item.plain_text, rich_text: item.rich_text, rtf: item.rtf };
+    let ciphertext = match serde_json::to_vec(&payload)
+        .map_err(|e| e.to_string())
+        .and_then(|json| storage::encrypt_bytes(&json, &passphrase).map_err(|e| e.to_string()))
+    {
+        Ok(ciphertext) => ciphertext,
+        Err(e) => return CommandResult::err(format!("Failed to encrypt item: {}", e)),
+    };
+
+    // Write an encrypted copy of the image file (the original is removed once the record points to the copy)
+    let data_dir = match app.path().app_data_dir() {
+        Ok(dir) => dir,
+        Err(e) => return CommandResult::err(format!("Failed to get app data dir: {}", e)),
+    };
+    let mut image_path = None;
+    if let Some(path) = &item.image_path {
+        let encrypted_image = match std::fs::read(data_dir.join(path)) {
+            Ok(bytes) => match storage::encrypt_bytes(&bytes, &passphrase) {
+                Ok(encrypted) => encrypted,
+                Err(e) => return CommandResult::err(format!("Failed to encrypt image: {}", e)),
+            },
+            Err(e) => return CommandResult::err(format!("Failed to read image file: {}", e)),
+        };
+        let encrypted_path = format!("images/{}.enc", uuid::Uuid::new_v4());
+        if let Err(e) = std::fs::write(data_dir.join(&encrypted_path), encrypted_image) {
+            return CommandResult::err(format!("Failed to write encrypted image: {}", e));
+        }
+        image_path = Some(encrypted_path);
+    }
+
+    // The hash follows the ciphertext so it reveals nothing about the content
+    let hash = ClipboardMonitor::compute_hash(&ciphertext);
+    let ciphertext = base64::engine::general_purpose::STANDARD.encode(ciphertext);
+    if let Err(e) = storage::set_item_encrypted(id, &ciphertext, image_path.as_deref(), &hash) {
+        // The record still points to the original image, drop the unused copy
+        if let Some(encrypted_path) = &image_path {
+            let _ = std::fs::remove_file(data_dir.join(encrypted_path));
+        }
+        return CommandResult::err(format!("Failed to save encrypted item: {}", e));
+    }
+
+    if let Some(path) = &item.image_path {
+        if let Err(e) = std::fs::remove_file(data_dir.join(path)) {
+            log::warn!("Failed to remove unencrypted image {}: {}", path, e);
+        }
+    }
+    let _ = app.emit("clipboard-updated", ());
+    CommandResult::ok(true)
+}
+
+/// Decrypt an encrypted record for viewing
+/// 
+/// The record stays encrypted at rest, the decrypted content is only returned
+#[tauri::command]
+pub fn decrypt_item(app: AppHandle, id: i64, passphrase: String) -> CommandResult<FullItem> {
+    use base64::Engine;
+
+    let mut item = match storage::get_item_by_id(id) {
+        Ok(Some(item)) => item,
+        Ok(None) => return CommandResult::err(format!("Item not found: {}", id)),
+        Err(e) => return CommandResult::err(format!("Failed to get item: {}", e)),
+    };
+    if !item.encrypted {
+        return CommandResult::err(format!("Item is not encrypted: {}", id));
+    }
+
+    let ciphertext = match storage::get_item_ciphertext(id) {
+        Ok(Some(ciphertext)) => ciphertext,
+        Ok(None) => return CommandResult::err(format!("Encrypted content is missing: {}", id)),
+        Err(e) => return CommandResult::err(format!("Failed to get item: {}", e)),
+    };
+    let payload: EncryptedPayload = match base64::engine::general_purpose::STANDARD
+        .decode(ciphertext)
+        .map_err(|e| e.to_string())
+        .and_then(|blob| storage::decrypt_bytes(&blob, &passphrase).map_err(|e| e.to_string()))
+        .and_then(|json| serde_json::from_slice(&json).map_err(|e| e.to_string()))
+    {
+        Ok(payload) => payload,
+        Err(e) => return CommandResult::err(format!("Failed to decrypt item: {}", e)),
+    };
+    item.plain_text = payload.plain_text;
+    item.rich_text = payload.rich_text;
+    item.rtf = payload.rtf;
+
+    let mut image_data = None;
+    if let Some(path) = &item.image_path {
+        let data_dir = match app.path().app_data_dir() {
+            Ok(dir) => dir,
+            Err(e) => return CommandResult::err(format!("Failed to get app data dir: {}", e)),
+        };
+        let bytes = match std::fs::read(data_dir.join(path)) {
+            Ok(encrypted) => match storage::decrypt_bytes(&encrypted, &passphrase) {
+                Ok(bytes) => bytes,
+                Err(e) => return CommandResult::err(format!("Failed to decrypt image: {}", e)),
+            },
+            Err(e) => return CommandResult::err(format!("Failed to read image file: {}", e)),
+        };
+        image_data = Some(image_data_uri(&bytes));
+    }
+
+    CommandResult::ok(FullItem { item, image_data })
+}
+
+/// Open the file a path record points to, or reveal it in Explorer
+#[tauri::command]
+pub fn open_file_path(id: i64, reveal: bool) -> CommandResult<bool> {
+    let item = match storage::get_item_by_id(id) {
+        Ok(Some(item)) => item,
+        Ok(None) => return CommandResult::err(format!("Item not found: {}", id)),
+        Err(e) => return CommandResult::err(format!("Failed to get item: {}", e)),
+    };
+
+    let path = match item.file_path() {
+        Some(path) => path,
+        None => return CommandResult::err(format!("Item is not an existing file path: {}", id)),
+    };
+
+    let mut command = std::process::Command::new("explorer");
+    if reveal {
+        // Explorer expects the quotes around the path, not around the whole argument
+        #[cfg(target_os = "windows")]
+        {
+            use std::os::windows::process::CommandExt;
+            command.raw_arg(format!("/select,\"{}\"", path));
+        }
+    } else {
+        command.arg(path);
+    }
+
+    match command.spawn() {
+        Ok(_) => CommandResult::ok(true),
+        Err(e) => CommandResult::err(format!("Failed to open path: {}", e)),
+    }
+}
+
+/// Get a cached source application icon (Base64 data URI)
+/// 
+/// icon is the source_icon reference of a record
+#[tauri::command]
+pub fn get_source_icon(app: AppHandle, icon: String) -> CommandResult<String> {
+    use base64::Engine;
+
+    // Only serve files from the icon cache
+    if !icon.starts_with("icons/") || icon.contains("..") {
+        return CommandResult::err(format!("Invalid icon reference: {}", icon));
+    }
+
+    let icon_file = match app.path().app_data_dir() {
+        Ok(dir) => dir.join(&icon),
+        Err(e) => return CommandResult::err(format!("Failed to get app data dir: {}", e)),
+    };
+    match std::fs::read(icon_file) {
+        Ok(bytes) => CommandResult::ok(format!(
+            "data:image/png;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(bytes)
+        )),
+        Err(e) => CommandResult::err(format!("Failed to read icon: {}", e)),
+    }
+}
+
+/// Get a downscaled preview of an image record (Base64 data URI)
+#[tauri::command]
+pub fn get_image_preview(app: AppHandle, id: i64, max_dim: u32) -> CommandResult<String> {
+    use base64::Engine;
+    use std::io::Cursor;
+
+    let item = match storage::get_item_by_id(id) {
+        Ok(Some(item)) => item,
+        Ok(None) => return CommandResult::err(format!("Item not found: {}", id)),
+        Err(e) => return CommandResult::err(format!("Failed to get item: {}", e)),
+    };
+
+    let image_path = match &item.image_path {
+        Some(path) if item.content_type == ContentType::Image => path,
+        _ => return CommandResult::err(format!("Item is not an image: {}", id)),
+    };
+
+    let full_path = match app.path().app_data_dir() {
+        Ok(path_buf) => path_buf.join(image_path),
+        Err(e) => return CommandResult::err(format!("Failed to get app data dir: {}", e)),
+    };
+
+    let mut img = match image::open(&full_path) {
+        Ok(i) => i,
+        Err(e) => return CommandResult::err(format!("Failed to read image file: {}", e)),
+    };
+
+    // Only downscale, never enlarge small images
+    if max_dim > 0 && (img.width() > max_dim || img.height() > max_dim) {
+        img = img.thumbnail(max_dim, max_dim);
+    }
+
+    let mut png_data = Vec::new();
+    if let Err(e) = img.write_to(&mut Cursor::new(&mut png_data), image::ImageFormat::Png) {
+        return CommandResult::err(format!("Failed to encode preview: {}", e));
+    }
+
+    let base64_str = base64::engine::general_purpose::STANDARD.encode(&png_data);
+    CommandResult::ok(format!("data:image/png;base64,{}", base64_str))
+}
+
+/// Result of re-encoding stored images
+#[derive(Debug, Default, Serialize)]
+pub struct RecompressReport {
+    pub converted: usize,
+    pub skipped: usize,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+}
+
+/// Encode an image in the given format ("png", "jpeg" or "webp")
+/// 
+/// Returns the encoded data and file extension, quality only applies to JPEG
+fn encode_image(img: &image::DynamicImage, format: &str, quality: u8) -> Result<(Vec<u8>, &'static str), String> {
+    use std::io::Cursor;
+
+    let mut data = Vec::new();
+    let extension = match format {
+        "png" => {
+            img.write_to(&mut Cursor::new(&mut data), image::ImageFormat::Png)
+                .map_err(|e| e.to_string())?;
+            "png"
+        }
+        "jpeg" | "jpg" => {
+            // JPEG has no alpha channel
+            let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut data, quality.clamp(1, 100));
+            img.to_rgb8().write_with_encoder(encoder).map_err(|e| e.to_string())?;
+            "jpg"
+        }
+        "webp" => {
+            img.write_to(&mut Cursor::new(&mut data), image::ImageFormat::WebP)
+                .map_err(|e| e.to_string())?;
+            "webp"
+        }
+        _ => return Err(format!("Unsupported image format: {}", format)),
+    };
+    Ok((data, extension))
+}
+
+/// Re-encode all stored images to the given format and quality
+/// 
+/// Each image is written to a new file and the record updated before the
+/// old file is removed, images that fail are left untouched and skipped
+#[tauri::command]
+pub fn recompress_images(app: AppHandle, format: String, quality: u8) -> CommandResult<RecompressReport> {
+    let format = format.to_lowercase();
+    if !matches!(format.as_str(), "png" | "jpeg" | "jpg" | "webp") {
+        return CommandResult::err(format!("Unsupported image format: {}", format));
+    }
+
+    let data_dir = match app.path().app_data_dir() {
+        Ok(dir) => dir,
+        Err(e) => return CommandResult::err(format!("Failed to get app data dir: {}", e)),
+    };
+    let records = match storage::get_image_records() {
+        Ok(records) => records,
+        Err(e) => return CommandResult::err(format!("Failed to get image records: {}", e)),
+    };
+
+    let mut report = RecompressReport::default();
+    for (id, image_path) in records {
+        let old_file = data_dir.join(&image_path);
+        let result = std::fs::read(&old_file)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| {
+                let img = image::load_from_memory(&bytes).map_err(|e| e.to_string())?;
+                let (encoded, extension) = encode_image(&img, &format, quality)?;
+                Ok((bytes.len() as u64, encoded, extension))
+            })
+            .and_then(|(old_size, encoded, extension)| {
+                let new_path = format!("images/{}.{}", uuid::Uuid::new_v4(), extension);
+                let new_file = data_dir.join(&new_path);
+                std::fs::write(&new_file, &encoded).map_err(|e| e.to_string())?;
+
+                let thumbnail = crate::generate_thumbnail(&encoded);
+                if let Err(e) = storage::update_item_image(id, &new_path, thumbnail.as_deref(), encoded.len() as i64) {
+                    let _ = std::fs::remove_file(&new_file);
+                    return Err(e.to_string());
+                }
+                Ok((old_size, encoded.len() as u64))
+            });
+
+        match result {
+            Ok((old_size, new_size)) => {
+                if let Err(e) = std::fs::remove_file(&old_file) {
+                    log::warn!("Failed to remove old image file {}: {}", image_path, e);
+                }
+                report.converted += 1;
+                report.bytes_before += old_size;
+                report.bytes_after += new_size;
+            }
+            Err(e) => {
+                log::warn!("Skipping image record {} during recompression: {}", id, e);
+                report.skipped += 1;
+            }
+        }
+    }
+
+    log::info!(
+        "Recompressed {} images ({} skipped), {} -> {} bytes",
+        report.converted, report.skipped, report.bytes_before, report.bytes_after
+    );
+    let _ = app.emit("clipboard-updated", ());
+    CommandResult::ok(report)
+}
+
+/// Number of colors extracted into an image palette
+const PALETTE_SIZE: usize = 6;
+
+/// Extract the dominant colors of an image as hex strings (most frequent first)
+fn extract_palette(path: &std::path::Path) -> Result<Vec<String>, String> {
+    use color_quant::NeuQuant;
+
+    let img = image::open(path).map_err(|e| format!("Failed to read image file: {}", e))?;
+    // Palette quality doesn't need full resolution
+    let rgba = img.thumbnail(128, 128).into_rgba8();
+    let pixels = rgba.as_raw();
+
+    let quant = NeuQuant::new(10, PALETTE_SIZE, pixels);
+    let mut counts = [0usize; PALETTE_SIZE];
+    for pixel in pixels.chunks_exact(4) {
+        counts[quant.index_of(pixel)] += 1;
+    }
+
+    let color_map = quant.color_map_rgb();
+    let mut order: Vec<usize> = (0..PALETTE_SIZE).filter(|&i| counts[i] > 0).collect();
+    order.sort_by(|a, b| counts[*b].cmp(&counts[*a]));
+
+    Ok(order
+        .into_iter()
+        .map(|i| format!("#{:02x}{:02x}{:02x}", color_map[i * 3], color_map[i * 3 + 1], color_map[i * 3 + 2]))
+        .collect())
+}
+
+/// Get the dominant color palette of an image record
+/// 
+/// Computed on first request and cached in the database
+#[tauri::command]
+pub fn get_item_palette(app: AppHandle, id: i64) -> CommandResult<Vec<String>> {
+    // Return cached palette if available
+    match storage::get_item_palette(id) {
+        Ok(Some(json)) => {
+            if let Ok(palette) = serde_json::from_str::<Vec<String>>(&json) {
+                return CommandResult::ok(palette);
+            }
+        }
+        Ok(None) => {}
+        Err(e) => return CommandResult::err(format!("Failed to get palette: {}", e)),
+    }
+
+    let item = match storage::get_item_by_id(id) {
+        Ok(Some(item)) => item,
+        Ok(None) => return CommandResult::err(format!("Item not found: {}", id)),
+        Err(e) => return CommandResult::err(format!("Failed to get item: {}", e)),
+    };
+
+    let image_path = match &item.image_path {
+        Some(path) if item.content_type == ContentType::Image => path,
+        _ => return CommandResult::err(format!("Item is not an image: {}", id)),
+    };
+
+    let full_path = match app.path().app_data_dir() {
+        Ok(path_buf) => path_buf.join(image_path),
+        Err(e) => return CommandResult::err(format!("Failed to get app data dir: {}", e)),
+    };
+
+    let palette = match extract_palette(&full_path) {
+        Ok(palette) => palette,
+        Err(e) => return CommandResult::err(e),
+    };
+
+    if let Ok(json) = serde_json::to_string(&palette) {
+        if let Err(e) = storage::set_item_palette(id, &json) {
+            log::warn!("Failed to cache palette for item {}: {}", id, e);
+        }
+    }
+
+    CommandResult::ok(palette)
+}
+
+/// Encode the text of a record as a QR code image (Base64 data URI)
+#[tauri::command]
+pub fn item_as_qr(id: i64) -> CommandResult<String> {
+    use base64::Engine;
+    use qrcode::{QrCode, types::QrError};
+    use std::io::Cursor;
+
+    let item = match storage::get_item_by_id(id) {
+        Ok(Some(item)) => item,
+        Ok(None) => return CommandResult::err(format!("Item not found: {}", id)),
+        Err(e) => return CommandResult::err(format!("Failed to get item: {}", e)),
+    };
+
+    let text = match (&item.content_type, &item.plain_text) {
+        (ContentType::Text | ContentType::RichText | ContentType::Link | ContentType::Color, Some(text)) => text,
+        _ => return CommandResult::err(format!("Item has no text content: {}", id)),
+    };
+
+    let code = match QrCode::new(text.as_bytes()) {
+        Ok(code) => code,
+        Err(QrError::DataTooLong) => {
+            return CommandResult::err("Text is too long to encode as a QR code".to_string())
+        }
+        Err(e) => return CommandResult::err(format!("Failed to encode QR code: {}", e)),
+    };
+
+    let img = code.render::<image::Luma<u8>>().min_dimensions(256, 256).build();
+
+    let mut png_data = Vec::new();
+    if let Err(e) = img.write_to(&mut Cursor::new(&mut png_data), image::ImageFormat::Png) {
+        return CommandResult::err(format!("Failed to write QR code PNG: {}", e));
+    }
+
+    let base64_str = base64::engine::general_purpose::STANDARD.encode(&png_data);
+    CommandResult::ok(format!("data:image/png;base64,{}", base64_str))
+}
+
+/// Move a record to the trash
+#[tauri::command]
+pub fn delete_item(id: i64) -> CommandResult<bool> {
+    match storage::delete_item(id) {
+        Ok(deleted) => CommandResult::ok(deleted),
+        Err(e) => CommandResult::err(format!("Failed to delete item: {}", e)),
+    }
+}
+
+/// Get the records in the trash
+#[tauri::command]
+pub fn get_trash() -> CommandResult<Vec<ClipboardItemView>> {
+    match storage::get_trash() {
+        Ok(items) => {
+            let views: Vec<ClipboardItemView> = items.into_iter().map(|i| i.into()).collect();
+            CommandResult::ok(views)
+        }
+        Err(e) => CommandResult::err(format!("Failed to get trash: {}", e)),
+    }
+}
+
+/// Move a record out of the trash
+#[tauri::command]
+pub fn restore_item(app: AppHandle, id: i64) -> CommandResult<bool> {
+    match storage::restore_item(id) {
+        Ok(true) => {
+            let _ = app.emit("clipboard-updated", ());
+            CommandResult::ok(true)
+        }
+        Ok(false) => CommandResult::err(format!("Item not in trash: {}", id)),
+        Err(e) => CommandResult::err(format!("Failed to restore item: {}", e)),
+    }
+}
+
+/// Permanently delete all records in the trash
+/// 
+/// Returns the number of purged records
+#[tauri::command]
+pub fn purge_trash(app: AppHandle) -> CommandResult<usize> {
+    let data_dir = match app.path().app_data_dir() {
+        Ok(path_buf) => path_buf,
+        Err(e) => return CommandResult::err(format!("Failed to get app data dir: {}", e)),
+    };
+    
+    match storage::purge_trash(None, &data_dir) {
+        Ok(count) => CommandResult::ok(count),
+        Err(e) => CommandResult::err(format!("Failed to purge trash: {}", e)),
+    }
+}
+
+/// Apply a tag to multiple records
+#[tauri::command]
+pub fn tag_items(ids: Vec<i64>, tag: String) -> CommandResult<usize> {
+    let tag = tag.trim();
+    if tag.is_empty() {
+        return CommandResult::err("Tag name is empty".to_string());
+    }
+    
+    match storage::tag_items(&ids, tag) {
+        Ok(count) => CommandResult::ok(count),
+        Err(e) => CommandResult::err(format!("Failed to tag items: {}", e)),
+    }
+}
+
+/// Clear all history records
+/// 
+/// Returns the backup file path when backup_before_clear is enabled
+#[tauri::command]
+pub fn clear_all_history(app: AppHandle) -> CommandResult<Option<String>> {
+    let data_dir = match app.path().app_data_dir() {
+        Ok(path_buf) => path_buf,
+        Err(e) => return CommandResult::err(format!("Failed to get app data dir: {}", e)),
+    };
+    let mut backup_path = None;
+    
+    if config::get_settings().backup_before_clear {
+        let filename = format!("pre-clear-{}.json", chrono::Local::now().format("%Y%m%d-%H%M%S"));
+        let path = data_dir.join("backups").join(filename);
+        
+        // Abort clearing if the backup can't be written
+        if let Err(e) = storage::export_history(&data_dir, &path) {
+            return CommandResult::err(format!("Failed to back up history: {}", e));
+        }
+        
+        log::info!("History backed up to {:?} before clearing", path);
+        backup_path = Some(path.to_string_lossy().to_string());
+    }
+    
+    match storage::clear_all_items(&data_dir) {
+        Ok(()) => CommandResult::ok(backup_path),
+        Err(e) => CommandResult::err(format!("Failed to clear history: {}", e)),
+    }
+}
+
+/// Export the history to a CSV file for spreadsheets
+/// 
+/// Returns the number of exported records
+#[tauri::command]
+pub fn export_csv(path: String) -> CommandResult<usize> {
+    match storage::export_csv(std::path::Path::new(&path)) {
+        Ok(count) => {
+            log::info!("Exported {} records to {}", count, path);
+            CommandResult::ok(count)
+        }
+        Err(e) => CommandResult::err(format!("Failed to export CSV: {}", e)),
+    }
+}
+
+/// Export the records matching a filter (content type, date range, search query) to a JSON file
+/// 
+/// Returns the number of exported records
+#[tauri::command]
+pub fn export_filtered(app: AppHandle, filter: storage::ExportFilter, path: String) -> CommandResult<usize> {
+    let data_dir = match app.path().app_data_dir() {
+        Ok(path_buf) => path_buf,
+        Err(e) => return CommandResult::err(format!("Failed to get app data dir: {}", e)),
+    };
+    
+    match storage::export_filtered(&filter, &data_dir, std::path::Path::new(&path)) {
+        Ok(count) => {
+            log::info!("Exported {} records to {}", count, path);
+            CommandResult::ok(count)
+        }
+        Err(e) => CommandResult::err(format!("Failed to export history: {}", e)),
+    }
+}
+
+/// Import records from a JSON file written by export_history
+/// 
+/// Without `merge` the current history is replaced. Returns the number of inserted records
+#[tauri::command]
+pub fn import_history(app: AppHandle, path: String, merge: bool) -> CommandResult<i64> {
+    let data_dir = match app.path().app_data_dir() {
+        Ok(path_buf) => path_buf,
+        Err(e) => return CommandResult::err(format!("Failed to get app data dir: {}", e)),
+    };
+    
+    match storage::import_history(&data_dir, std::path::Path::new(&path), merge) {
+        Ok(count) => {
+            log::info!("Imported {} records from {}", count, path);
+            let _ = app.emit("clipboard-updated", ());
+            CommandResult::ok(count as i64)
+        }
+        Err(e) => CommandResult::err(format!("Failed to import history: {}", e)),
+    }
+}
+
+/// Import history saved by another clipboard manager
+/// 
+/// `format` is "ditto" (Ditto database) or "csv"
+#[tauri::command]
+pub fn import_external(app: AppHandle, format: String, path: String) -> CommandResult<storage::ImportReport> {
+    let Some(external_format) = storage::ExternalFormat::from_name(&format) else {
+        return CommandResult::err(format!("Unknown import format: {}", format));
+    };
+    
+    let preview_len = config::get_settings().preview_length;
+    match storage::import_external(external_format, std::path::Path::new(&path), preview_len) {
+        Ok(report) => {
+            log::info!("Imported {} records from {} ({} skipped)", report.imported, path, report.skipped);
+            let _ = app.emit("clipboard-updated", ());
+            CommandResult::ok(report)
+        }
+        Err(e) => CommandResult::err(format!("Failed to import history: {}", e)),
+    }
+}
+
+/// Largest compiled size accepted for a regex search pattern (bytes)
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+
+/// Search clipboard records
+/// 
+/// With `regex` the query is a regular expression matched against the text
+#[tauri::command]
+pub fn search_clipboard(query: String, limit: Option<i32>, regex: Option<bool>) -> CommandResult<Vec<ClipboardItemView>> {
+    if query.is_empty() {
+        return get_clipboard_history(limit, None);
+    }
+    
+    let result = if regex.unwrap_or(false) {
+        let pattern = match regex::RegexBuilder::new(&query).size_limit(REGEX_SIZE_LIMIT).build() {
+            Ok(pattern) => pattern,
+            Err(e) => return CommandResult::err(format!("Invalid regular expression: {}", e)),
+        };
+        storage::search_items_regex(&pattern, limit)
+    } else {
+        storage::search_items_fts(&query, limit)
+    };
+    
+    match result {
+        Ok(items) => {
+            let views: Vec<ClipboardItemView> = items.into_iter().map(|i| i.into()).collect();
+            CommandResult::ok(views)
+        }
+        Err(e) => CommandResult::err(format!("Search failed: {}", e)),
+    }
+}
+
+/// Search clipboard records ranked by relevance (prefix match, recency, paste count)
+#[tauri::command]
+pub fn search_ranked(query: String, limit: Option<i32>) -> CommandResult<Vec<ClipboardItemView>> {
+    if query.is_empty() {
+        return get_clipboard_history(limit, None);
+    }
+    
+    match storage::search_ranked(&query, limit) {
+        Ok(items) => {
+            let views: Vec<ClipboardItemView> = items.into_iter().map(|i| i.into()).collect();
+            CommandResult::ok(views)
+        }
+        Err(e) => CommandResult::err(format!("Search failed: {}", e)),
+    }
+}
+
+/// Search result for display
+#[derive(Debug, Serialize)]
+pub struct SearchHit {
+    #[serde(flatten)]
+    pub item: ClipboardItemView,
+    /// Field that matched the query (for highlighting)
+    pub matched_field: String,
+}
+
+/// Search record text, preview and tags together
+#[tauri::command]
+pub fn search_all(query: String, limit: Option<i32>) -> CommandResult<Vec<SearchHit>> {
+    match storage::search_all(&query, limit) {
+        Ok(matches) => {
+            let hits = matches
+                .into_iter()
+                .map(|m| SearchHit { item: m.item.into(), matched_field: m.matched_field })
+                .collect();
+            CommandResult::ok(hits)
+        }
+        Err(e) => CommandResult::err(format!("Search failed: {}", e)),
+    }
+}
+
+/// Convert the measurement stored in a record to another unit
+#[tauri::command]
+pub fn convert_item(id: i64, target_unit: String) -> CommandResult<f64> {
+    let item = match storage::get_item_by_id(id) {
+        Ok(Some(item)) => item,
+        Ok(None) => return CommandResult::err(format!("Item not found: {}", id)),
+        Err(e) => return CommandResult::err(format!("Failed to get item: {}", e)),
+    };
+
+    let (value, unit) = match (item.measure_value, &item.measure_unit) {
+        (Some(value), Some(unit)) => (value, unit),
+        _ => return CommandResult::err(format!("Item is not a measurement: {}", id)),
+    };
+
+    match units::convert(value, unit, &target_unit) {
+        Ok(converted) => CommandResult::ok(converted),
+        Err(e) => CommandResult::err(e),
+    }
+}
+
+/// Get records in the given language
+#[tauri::command]
+pub fn get_items_by_language(code: String, limit: Option<i32>) -> CommandResult<Vec<ClipboardItemView>> {
+    match storage::get_items_by_language(&code, limit) {
+        Ok(items) => {
+            let views: Vec<ClipboardItemView> = items.into_iter().map(|i| i.into()).collect();
+            CommandResult::ok(views)
+        }
+        Err(e) => CommandResult::err(format!("Failed to get items by language: {}", e)),
+    }
+}
+
+/// Get the most pasted records ("Most used" view)
+#[tauri::command]
+pub fn get_items_by_usage(limit: Option<i32>) -> CommandResult<Vec<ClipboardItemView>> {
+    match storage::get_items_by_usage(limit) {
+        Ok(items) => {
+            let views: Vec<ClipboardItemView> = items.into_iter().map(|i| i.into()).collect();
+            CommandResult::ok(views)
+        }
+        Err(e) => CommandResult::err(format!("Failed to get items by usage: {}", e)),
+    }
+}
+
+/// Get pinned records
+#[tauri::command]
+pub fn get_pinned_items() -> CommandResult<Vec<ClipboardItemView>> {
+    match storage::get_pinned_items() {
+        Ok(items) => {
+            let views: Vec<ClipboardItemView> = items.into_iter().map(|i| i.into()).collect();
+            CommandResult::ok(views)
+        }
+        Err(e) => CommandResult::err(format!("Failed to get pinned items: {}", e)),
+    }
+}
+
+/// Get records captured between two RFC 3339 timestamps (inclusive)
+#[tauri::command]
+pub fn get_items_in_range(start_rfc3339: String, end_rfc3339: String, limit: Option<i32>) -> CommandResult<Vec<ClipboardItemView>> {
+    use chrono::{DateTime, Utc};
+
+    let parse = |value: &str| DateTime::parse_from_rfc3339(value).map(|dt| dt.with_timezone(&Utc));
+    let start = match parse(&start_rfc3339) {
+        Ok(start) => start,
+        Err(e) => return CommandResult::err(format!("Invalid start time {}: {}", start_rfc3339, e)),
+    };
+    let end = match parse(&end_rfc3339) {
+        Ok(end) => end,
+        Err(e) => return CommandResult::err(format!("Invalid end time {}: {}", end_rfc3339, e)),
+    };
+    if start > end {
+        return CommandResult::err("Start time is after end time".to_string());
+    }
+    
+    match storage::get_items_in_range(start, end, limit) {
+        Ok(items) => {
+            let views: Vec<ClipboardItemView> = items.into_iter().map(|i| i.into()).collect();
+            CommandResult::ok(views)
+        }
+        Err(e) => CommandResult::err(format!("Failed to get items in range: {}", e)),
+    }
+}
+
+/// Get records of one content type ("text", "image", ...)
+#[tauri::command]
+pub fn get_items_by_type(content_type: String, limit: Option<i32>) -> CommandResult<Vec<ClipboardItemView>> {
+    let Some(content_type) = ContentType::from_str(&content_type) else {
+        return CommandResult::err(format!("Unknown content type: {}", content_type));
+    };
+    
+    match storage::get_items_by_type(&content_type, limit) {
+        Ok(items) => {
+            let views: Vec<ClipboardItemView> = items.into_iter().map(|i| i.into()).collect();
+            CommandResult::ok(views)
+        }
+        Err(e) => CommandResult::err(format!("Failed to get items by type: {}", e)),
+    }
+}
+
+/// Get records whose text has at least `min_chars` characters
+#[tauri::command]
+pub fn get_items_min_length(min_chars: u32, limit: Option<i32>) -> CommandResult<Vec<ClipboardItemView>> {
+    match storage::get_items_min_length(min_chars, limit) {
+        Ok(items) => {
+            let views: Vec<ClipboardItemView> = items.into_iter().map(|i| i.into()).collect();
+            CommandResult::ok(views)
+        }
+        Err(e) => CommandResult::err(format!("Failed to get items by length: {}", e)),
+    }
+}
+
+/// Get current settings
+#[tauri::command]
+pub fn get_settings(app: AppHandle) -> CommandResult<Settings> {
+    let mut settings = config::get_settings();
+    
+    // Get actual auto-start status from autostart plugin
+    use tauri_plugin_autostart::ManagerExt;
+    if let Ok(is_enabled) = app.autolaunch().is_enabled() {
+        settings.auto_start = is_enabled;
+    }
+    
+    CommandResult::ok(settings)
+}
+
+/// Stored setting value alongside the value actually in effect
+#[derive(Debug, Serialize)]
+pub struct EffectiveValue<T> {
+    pub configured: T,
+    /// None when the applied value can't be determined (e.g. nothing registered)
+    pub effective: Option<T>,
+}
+
+/// Settings whose applied state can differ from the stored value
+#[derive(Debug, Serialize)]
+pub struct EffectiveSettings {
+    pub auto_start: EffectiveValue<bool>,
+    pub shortcut: EffectiveValue<String>,
+    pub poll_interval_ms: EffectiveValue<u64>,
+}
+
+/// Get configured and actually applied values of settings that can diverge
+#[tauri::command]
+pub fn get_effective_settings(app: AppHandle) -> CommandResult<EffectiveSettings> {
+    use tauri_plugin_autostart::ManagerExt;
+    use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+
+    let settings = config::get_settings();
+
+    // The shortcut setup falls back to Win+V and then Ctrl+Shift+V
+    let candidates = [settings.shortcut.as_str(), "Win+V", "Ctrl+Shift+V"];
+    let registered_shortcut = candidates.iter().find(|name| {
+        name.to_lowercase()
+            .replace("win", "super")
+            .parse::<Shortcut>()
+            .map(|shortcut| app.global_shortcut().is_registered(shortcut))
+            .unwrap_or(false)
+    });
+
+    let live_interval = crate::CLIPBOARD_MONITOR
+        .lock()
+        .as_ref()
+        .filter(|monitor| monitor.is_running())
+        .map(|monitor| monitor.poll_interval_ms());
+
+    CommandResult::ok(EffectiveSettings {
+        auto_start: EffectiveValue {
+            configured: settings.auto_start,
+            effective: app.autolaunch().is_enabled().ok(),
+        },
+        shortcut: EffectiveValue {
+            configured: settings.shortcut.clone(),
+            effective: registered_shortcut.map(|name| name.to_string()),
+        },
+        poll_interval_ms: EffectiveValue {
+            configured: crate::MONITOR_POLL_INTERVAL_MS,
+            effective: live_interval,
+        },
+    })
+}
+
+/// Settings update request from frontend
+#[derive(Debug, Deserialize)]
+pub struct SettingsUpdate {
+    pub theme: Option<String>,
+    pub storage_limit: Option<i32>,
+    pub auto_start: Option<bool>,
+    /// Preview length in characters (0 gives empty previews)
+    pub preview_length: Option<usize>,
+    pub shortcut: Option<String>,
+    pub restore_clipboard_after_paste: Option<bool>,
+    /// Image max age in days (0 disables the age limit)
+    pub image_max_age_days: Option<u32>,
+    pub normalize_line_endings: Option<String>,
+    pub record_unsupported: Option<bool>,
+    pub backup_before_clear: Option<bool>,
+    pub min_image_dimension: Option<u32>,
+    pub detect_language: Option<bool>,
+    /// Maximum number of image files (0 disables the limit)
+    pub max_image_files: Option<usize>,
+    /// Post-paste linger time in milliseconds (0 hides immediately)
+    pub post_paste_linger_ms: Option<u32>,
+    pub capture_on_startup: Option<bool>,
+    pub merge_rich_and_plain: Option<bool>,
+    pub paste_keystroke: Option<String>,
+    /// Paste timing delays in milliseconds
+    pub paste_hide_delay_ms: Option<u32>,
+    pub paste_focus_delay_ms: Option<u32>,
+    /// Low-disk threshold in MB (0 disables the check)
+    pub min_free_disk_mb: Option<u64>,
+    pub startup_window: Option<String>,
+    pub detect_html_in_text: Option<bool>,
+    pub strip_image_metadata: Option<bool>,
+    pub prefer_png_clipboard: Option<bool>,
+    /// Large paste threshold in KB (0 = never ask)
+    pub large_paste_threshold_kb: Option<u32>,
+    pub restore_clipboard_delay_ms: Option<u32>,
+    /// Auto-pin regexes (invalid patterns are rejected)
+    pub auto_pin_patterns: Option<Vec<String>>,
+    /// Process name that pauses capture while in the foreground (empty = off)
+    pub pause_while_process: Option<String>,
+    /// Trash retention in days (0 keeps deleted records until the trash is emptied)
+    pub trash_retention_days: Option<u32>,
+    pub bump_on_paste: Option<bool>,
+    /// "skip" or "bump"
+    pub on_duplicate: Option<String>,
+}
+
+/// Upper bound for the configurable paste delays (milliseconds)
+const MAX_PASTE_DELAY_MS: u32 = 2000;
+
+/// Update settings
+#[tauri::command]
+pub fn update_settings(updates: SettingsUpdate, _app: AppHandle) -> CommandResult<Settings> {
+    // Validate auto-pin patterns before anything is applied
+    if let Some(patterns) = &updates.auto_pin_patterns {
+        if let Some(e) = patterns.iter().find_map(|p| regex::Regex::new(p).err()) {
+            return CommandResult::err(format!("Invalid auto-pin pattern: {}", e));
+        }
+    }
+    
+    let mut settings = config::get_settings();
+    
+    // Update theme
+    if let Some(theme) = updates.theme {
+        settings.theme = match theme.as_str() {
+            "dark" => Theme::Dark,
+            _ => Theme::Light,
+        };
+    }
+    
+    // Update storage limit
+    if let Some(limit) = updates.storage_limit {
+        settings.storage_limit = StorageLimit::from_i32(limit);
+        
+        // Cleanup old records exceeding limit
+        if limit > 0 {
+            if let Ok(data_dir) = _app.path().app_data_dir() {
+                if let Err(e) = storage::cleanup_old_items(limit, &data_dir) {
+                    log::warn!("Failed to cleanup old items: {}", e);
+                }
+            }
+        }
+    }
+    
+    // Update preview length (applies to new records)
+    if let Some(length) = updates.preview_length {
+        settings.preview_length = length;
+    }
+    
+    // Update auto-start
+    if let Some(auto_start) = updates.auto_start {
+        settings.auto_start = auto_start;
+        if let Err(e) = apply_auto_start(&_app, auto_start) {
+            return CommandResult::err(e);
+        }
+    }
+    
+    // Update shortcut
+    if let Some(ref shortcut) = updates.shortcut {
+        settings.shortcut = shortcut.clone();
+        if let Err(e) = register_toggle_shortcut(&_app, shortcut) {
+            return CommandResult::err(e);
+        }
+    }
+    
+    // Update clipboard restore behavior
+    if let Some(restore) = updates.restore_clipboard_after_paste {
+        settings.restore_clipboard_after_paste = restore;
+    }
+    
+    // Update image max age
+    if let Some(days) = updates.image_max_age_days {
+        settings.image_max_age_days = if days > 0 { Some(days) } else { None };
+        
+        if days > 0 {
+            if let Ok(data_dir) = _app.path().app_data_dir() {
+                if let Err(e) = storage::cleanup_expired_images(days, &data_dir) {
+                    log::warn!("Failed to cleanup expired images: {}", e);
+                }
+            }
+        }
+    }
+    
+    // Update line ending normalization
+    if let Some(mode) = updates.normalize_line_endings {
+        settings.normalize_line_endings = LineEndings::from_name(&mode);
+    }
+    
+    // Update unsupported format recording
+    if let Some(record) = updates.record_unsupported {
+        settings.record_unsupported = record;
+    }
+    
+    // Update backup before clear
+    if let Some(backup) = updates.backup_before_clear {
+        settings.backup_before_clear = backup;
+    }
+    
+    // Update minimum image dimension
+    if let Some(dim) = updates.min_image_dimension {
+        settings.min_image_dimension = dim;
+    }
+    
+    // Update language detection
+    if let Some(detect) = updates.detect_language {
+        settings.detect_language = detect;
+    }
+    
+    // Update image file limit
+    if let Some(max_files) = updates.max_image_files {
+        settings.max_image_files = if max_files > 0 { Some(max_files) } else { None };
+        
+        if max_files > 0 {
+            if let Ok(data_dir) = _app.path().app_data_dir() {
+                if let Err(e) = storage::cleanup_image_files(max_files, &data_dir) {
+                    log::warn!("Failed to cleanup image files: {}", e);
+                }
+            }
+        }
+    }
+    
+    // Update post-paste linger time
+    if let Some(linger_ms) = updates.post_paste_linger_ms {
+        settings.post_paste_linger_ms = if linger_ms > 0 { Some(linger_ms) } else { None };
+    }
+    
+    // Update startup capture
+    if let Some(capture) = updates.capture_on_startup {
+        settings.capture_on_startup = capture;
+    }
+    
+    // Update rich/plain text merging
+    if let Some(merge) = updates.merge_rich_and_plain {
+        settings.merge_rich_and_plain = merge;
+    }
+    
+    // Update paste keystroke
+    if let Some(keystroke) = updates.paste_keystroke {
+        settings.paste_keystroke = PasteKeystroke::from_name(&keystroke);
+    }
+    
+    // Update paste timing delays
+    if let Some(delay) = updates.paste_hide_delay_ms {
+        settings.paste_hide_delay_ms = delay.min(MAX_PASTE_DELAY_MS);
+    }
+    if let Some(delay) = updates.paste_focus_delay_ms {
+        settings.paste_focus_delay_ms = delay.min(MAX_PASTE_DELAY_MS);
+    }
+    
+    // Update low-disk threshold
+    if let Some(min_mb) = updates.min_free_disk_mb {
+        settings.min_free_disk_mb = if min_mb > 0 { Some(min_mb) } else { None };
+    }
+    
+    // Update startup window visibility
+    if let Some(startup) = updates.startup_window {
+        settings.startup_window = StartupWindow::from_name(&startup);
+    }
+    
+    // Update HTML-in-text detection
+    if let Some(detect) = updates.detect_html_in_text {
+        settings.detect_html_in_text = detect;
+    }
+    
+    // Update image metadata stripping
+    if let Some(strip) = updates.strip_image_metadata {
+        settings.strip_image_metadata = strip;
+    }
+    
+    // Update PNG clipboard format preference
+    if let Some(prefer) = updates.prefer_png_clipboard {
+        settings.prefer_png_clipboard = prefer;
+    }
+    
+    // Update large paste confirmation threshold
+    if let Some(threshold_kb) = updates.large_paste_threshold_kb {
+        settings.large_paste_threshold_kb = if threshold_kb > 0 { Some(threshold_kb) } else { None };
+    }
+    
+    // Update clipboard restore delay
+    if let Some(delay) = updates.restore_clipboard_delay_ms {
+        settings.restore_clipboard_delay_ms = delay.min(MAX_PASTE_DELAY_MS);
+    }
+    
+    // Update auto-pin patterns
+    if let Some(patterns) = updates.auto_pin_patterns {
+        settings.auto_pin_patterns = patterns;
+    }
+    
+    // Update process that pauses capture
+    if let Some(process) = updates.pause_while_process {
+        settings.pause_while_process = process.trim().to_string();
+    }
+    
+    // Update trash retention
+    if let Some(days) = updates.trash_retention_days {
+        settings.trash_retention_days = if days > 0 { Some(days) } else { None };
+        
+        if days > 0 {
+            if let Ok(data_dir) = _app.path().app_data_dir() {
+                if let Err(e) = storage::purge_trash(Some(days), &data_dir) {
+                    log::warn!("Failed to purge trash: {}", e);
+                }
+            }
+        }
+    }
+    
+    // Update bump on paste
+    if let Some(bump) = updates.bump_on_paste {
+        settings.bump_on_paste = bump;
+    }
+    
+    // Update duplicate handling
+    if let Some(action) = updates.on_duplicate {
+        settings.on_duplicate = DuplicateAction::from_name(&action);
+    }
+    
+    // Save settings
+    match config::update_settings(settings.clone()) {
+        Ok(()) => CommandResult::ok(settings),
+        Err(e) => CommandResult::err(format!("Failed to save settings: {}", e)),
+    }
+}
+
+
+/// Enable or disable launching at login
+fn apply_auto_start(app: &AppHandle, auto_start: bool) -> Result<(), String> {
+    // Use tauri-plugin-autostart plugin
+    use tauri_plugin_autostart::ManagerExt;
+    
+    let autostart_manager = app.autolaunch();
+    let result = if auto_start {
+        autostart_manager.enable()
+    } else {
+        autostart_manager.disable()
+    };
+    
+    match result {
+        Ok(_) => {
+            log::info!("Autostart {} successfully", if auto_start { "enabled" } else { "disabled" });
+            Ok(())
+        },
+        Err(e) => {
+            log::error!("Failed to update autostart: {}", e);
+            Err(format!("Failed to update autostart: {}", e))
+        }
+    }
+}
+
+/// Register the shortcut that toggles the main window (replacing the old one)
+fn register_toggle_shortcut(app: &AppHandle, shortcut: &str) -> Result<(), String> {
+    use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+    
+    // Parse shortcut string, convert "Win" to "super" as required by Tauri
+    let shortcut_str = shortcut.to_lowercase().replace("win", "super");
+    let Ok(new_shortcut) = shortcut_str.parse::<Shortcut>() else {
+        log::error!("Invalid shortcut format: {}", shortcut);
+        return Err(format!("Invalid shortcut format: {}", shortcut));
+    };
+    
+    // Unregister all possible old shortcuts first
+    let _ = app.global_shortcut().unregister_all();
+    
+    // Register new shortcut
+    let result = app.global_shortcut().on_shortcut(new_shortcut, move |app, _shortcut, event| {
+        if event.state == ShortcutState::Pressed {
+            log::debug!("Custom shortcut pressed - toggling window");
+            crate::cancel_auto_hide();
+            if let Some(window) = app.get_webview_window("main") {
+                if window.is_visible().unwrap_or(false) {
+                    let _ = window.hide();
+                    config::save_window_visible(false);
+                } else {
+                    let _ = window.show();
+                    config::save_window_visible(true);
+                    let _ = window.set_focus();
+                    let _ = app.emit("window-shown", ());
+                    let _ = app.emit("focus-first-item", ());
+                }
+            }
+        }
+    });
+    
+    match result {
+        Ok(()) => {
+            log::info!("Custom shortcut '{}' registered successfully", shortcut);
+            Ok(())
+        }
+        Err(e) => {
+            log::error!("Failed to register custom shortcut '{}': {}", shortcut, e);
+            Err(format!("Failed to register shortcut: {}", e))
+        }
+    }
+}
+
+/// Write the current settings to a JSON file (for backup or another machine)
+#[tauri::command]
+pub fn export_settings(path: String) -> CommandResult<bool> {
+    match config::get_settings().export_to_file(std::path::Path::new(&path)) {
+        Ok(()) => {
+            log::info!("Exported settings to {}", path);
+            CommandResult::ok(true)
+        }
+        Err(e) => CommandResult::err(format!("Failed to export settings: {}", e)),
+    }
+}
+
+/// Load settings from a JSON file written by export_settings and apply them
+/// 
+/// The active profile and monitoring toggle stay unchanged, they describe
+/// this machine's state rather than preferences
+#[tauri::command]
+pub fn import_settings(app: AppHandle, path: String) -> CommandResult<Settings> {
+    let mut imported = match Settings::import_from_file(std::path::Path::new(&path)) {
+        Ok(settings) => settings,
+        Err(e) => return CommandResult::err(format!("Invalid settings file: {}", e)),
+    };
+    let current = config::get_settings();
+    imported.active_profile = current.active_profile.clone();
+    imported.monitoring_enabled = current.monitoring_enabled;
+    
+    if imported.auto_start != current.auto_start {
+        if let Err(e) = apply_auto_start(&app, imported.auto_start) {
+            return CommandResult::err(e);
+        }
+    }
+    if imported.shortcut != current.shortcut {
+        if let Err(e) = register_toggle_shortcut(&app, &imported.shortcut) {
+            return CommandResult::err(e);
+        }
+    }
+    
+    match config::update_settings(imported.clone()) {
+        Ok(()) => {
+            log::info!("Imported settings from {}", path);
+            CommandResult::ok(imported)
+        }
+        Err(e) => CommandResult::err(format!("Failed to save settings: {}", e)),
+    }
+}
+
+/// Maximum length of a profile name
+const MAX_PROFILE_NAME_LEN: usize = 64;
+
+/// Get all history profiles
+#[tauri::command]
+pub fn get_profiles() -> CommandResult<Vec<String>> {
+    match storage::get_profiles() {
+        Ok(profiles) => CommandResult::ok(profiles),
+        Err(e) => CommandResult::err(format!("Failed to get profiles: {}", e)),
+    }
+}
+
+/// Switch the active history profile (a new name creates the profile)
+#[tauri::command]
+pub fn switch_profile(app: AppHandle, name: String) -> CommandResult<bool> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_PROFILE_NAME_LEN {
+        return CommandResult::err(format!("Invalid profile name: {:?}", name));
+    }
+
+    let mut settings = config::get_settings();
+    settings.active_profile = name.to_string();
+    if let Err(e) = config::update_settings(settings) {
+        return CommandResult::err(format!("Failed to save settings: {}", e));
+    }
+    storage::set_active_profile(name);
+    log::info!("Switched to profile: {}", name);
+
+    let _ = app.emit("profile-switched", name);
+    let _ = app.emit("clipboard-updated", ());
+    CommandResult::ok(true)
+}
+
+/// Set the tray tooltip (an empty text restores the default)
+#[tauri::command]
+pub fn set_tray_tooltip(text: String) -> CommandResult<bool> {
+    let text = text.trim();
+    match crate::tray::set_tooltip((!text.is_empty()).then_some(text)) {
+        Ok(()) => CommandResult::ok(true),
+        Err(e) => CommandResult::err(e),
+    }
+}
+
+/// Show main window
+#[tauri::command]
+pub fn show_main_window(app: AppHandle) -> CommandResult<bool> {
+    crate::cancel_auto_hide();
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.set_focus();
+        config::save_window_visible(true);
+        CommandResult::ok(true)
+    } else {
+        CommandResult::err("Main window not found".to_string())
+    }
+}
+
+/// Hide main window
+#[tauri::command]
+pub fn hide_main_window(app: AppHandle) -> CommandResult<bool> {
+    crate::cancel_auto_hide();
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.hide();
+        config::save_window_visible(false);
+        CommandResult::ok(true)
+    } else {
+        CommandResult::err("Main window not found".to_string())
+    }
+}
+
+/// Restart the post-paste auto-hide countdown (called on user interaction)
+/// 
+/// Returns whether an auto-hide was pending
+#[tauri::command]
+pub fn reset_auto_hide(app: AppHandle) -> CommandResult<bool> {
+    match config::get_settings().post_paste_linger_ms {
+        Some(linger_ms) if crate::is_auto_hide_pending() => {
+            crate::schedule_auto_hide(&app, linger_ms);
+            CommandResult::ok(true)
+        }
+        _ => CommandResult::ok(false),
+    }
+}
+
+/// Get total record count
+#[tauri::command]
+pub fn get_history_count() -> CommandResult<i64> {
+    match storage::get_item_count() {
+        Ok(count) => CommandResult::ok(count),
+        Err(e) => CommandResult::err(format!("Failed to get count: {}", e)),
+    }
+}
+
+/// Get the number of pinned records
+#[tauri::command]
+pub fn get_pinned_count() -> CommandResult<i64> {
+    match storage::get_pinned_count() {
+        Ok(count) => CommandResult::ok(count),
+        Err(e) => CommandResult::err(format!("Failed to get pinned count: {}", e)),
+    }
+}
+
+/// Storage limit utilization
+#[derive(Debug, Serialize)]
+pub struct LimitUtilization {
+    /// Unpinned records (pinned records don't count against the limit)
+    pub used: i64,
+    /// Configured limit, None when unlimited
+    pub limit: Option<i64>,
+    /// Percentage of the limit in use, None when unlimited
+    pub percent: Option<f64>,
+}
+
+/// Get how much of the storage limit is in use
+#[tauri::command]
+pub fn get_limit_utilization() -> CommandResult<LimitUtilization> {
+    let used = match storage::get_unpinned_count() {
+        Ok(count) => count,
+        Err(e) => return CommandResult::err(format!("Failed to get count: {}", e)),
+    };
+    
+    let limit = config::get_settings().storage_limit.as_i32();
+    let (limit, percent) = if limit > 0 {
+        (Some(limit as i64), Some(used as f64 * 100.0 / limit as f64))
+    } else {
+        (None, None)
+    };
+    
+    CommandResult::ok(LimitUtilization { used, limit, percent })
+}
+
+/// Clipboard monitor state
+#[derive(Debug, Serialize)]
+pub struct MonitorState {
+    pub running: bool,
+    pub paused: bool,
+}
+
+/// Get clipboard monitor state
+#[tauri::command]
+pub fn is_monitoring() -> CommandResult<MonitorState> {
+    let monitor = crate::CLIPBOARD_MONITOR.lock();
+    match monitor.as_ref() {
+        Some(m) => CommandResult::ok(MonitorState {
+            running: m.is_running(),
+            paused: m.is_paused(),
+        }),
+        None => CommandResult::ok(MonitorState {
+            running: false,
+            paused: false,
+        }),
+    }
+}
+
+/// Enable or disable clipboard recording, persisted across restarts
+#[tauri::command]
+pub fn set_monitoring(enabled: bool) -> CommandResult<MonitorState> {
+    let mut settings = config::get_settings();
+    settings.monitoring_enabled = enabled;
+    if let Err(e) = config::update_settings(settings) {
+        return CommandResult::err(format!("Failed to save settings: {}", e));
+    }
+    
+    if let Some(monitor) = crate::CLIPBOARD_MONITOR.lock().as_ref() {
+        if enabled && !crate::is_private_mode() {
+            // Don't record whatever was copied while disabled
+            monitor.mark_current_as_seen();
+            monitor.resume();
+        } else {
+            monitor.pause();
+        }
+    }
+    
+    log::info!("Clipboard monitoring {}", if enabled { "enabled" } else { "disabled" });
+    is_monitoring()
+}
+
+/// Pause recording until disabled again, or for `duration_secs` seconds if given
+#[tauri::command]
+pub fn enable_private_mode(app: AppHandle, duration_secs: Option<u64>) -> CommandResult<bool> {
+    let duration = duration_secs.filter(|&secs| secs > 0).map(std::time::Duration::from_secs);
+    crate::set_private_mode(&app, true, duration);
+    CommandResult::ok(true)
+}
+
+/// Leave private mode and resume recording
+#[tauri::command]
+pub fn disable_private_mode(app: AppHandle) -> CommandResult<bool> {
+    crate::set_private_mode(&app, false, None);
+    CommandResult::ok(true)
+}
+
+/// Whether private mode is active
+#[tauri::command]
+pub fn is_private_mode() -> CommandResult<bool> {
+    CommandResult::ok(crate::is_private_mode())
+}
+
+/// Restart clipboard monitor
+#[tauri::command]
+pub fn restart_monitor(app: AppHandle) -> CommandResult<bool> {
+    // Take the old monitor out first so the lock isn't held while joining
+    let old_monitor = crate::CLIPBOARD_MONITOR.lock().take();
+    if let Some(monitor) = old_monitor {
+        monitor.stop();
+    }
+    
+    crate::start_clipboard_monitor(app, false);
+    log::info!("Clipboard monitor restarted");
+    CommandResult::ok(true)
+}
+
+/// Get the database initialization error, if storage is unavailable
+#[tauri::command]
+pub fn get_storage_error() -> CommandResult<Option<String>> {
+    CommandResult::ok(crate::STORAGE_ERROR.lock().clone())
+}
+
+/// Retry database initialization after a startup failure
+#[tauri::command]
+pub fn retry_storage_init(app: AppHandle) -> CommandResult<bool> {
+    open_storage(&app, None)
+}
+
+/// Open the encrypted database with its password
+/// 
+/// A wrong password fails with "Wrong database password"
+#[tauri::command]
+pub fn unlock_database(app: AppHandle, password: String) -> CommandResult<bool> {
+    open_storage(&app, Some(&password))
+}
+
+/// Initialize storage, then reload settings from the database and start capturing
+fn open_storage(app: &AppHandle, password: Option<&str>) -> CommandResult<bool> {
+    if let Err(e) = crate::init_storage(app, password) {
+        return CommandResult::err(e);
+    }
+    
+    config::init_settings();
+    if crate::CLIPBOARD_MONITOR.lock().is_none() {
+        crate::start_clipboard_monitor(app.clone(), false);
+    }
+    
+    let _ = app.emit("clipboard-updated", ());
+    log::info!("Storage initialized after retry");
+    CommandResult::ok(true)
+}
+
+/// Whether the database is waiting for its password (see unlock_database)
+#[tauri::command]
+pub fn is_database_locked() -> CommandResult<bool> {
+    CommandResult::ok(crate::STORAGE_LOCKED.load(std::sync::atomic::Ordering::SeqCst))
+}
+
+/// Whether the database is encrypted with a password
+#[tauri::command]
+pub fn is_database_encrypted() -> CommandResult<bool> {
+    CommandResult::ok(storage::is_database_encrypted())
+}
+
+/// Encrypt the database with a password, or change the password (`old` is the current one)
+#[tauri::command]
+pub fn set_encryption_password(app: AppHandle, old: Option<String>, new: String) -> CommandResult<bool> {
+    if new.is_empty() {
+        return CommandResult::err("Password must not be empty".to_string());
+    }
+    let data_dir = match app.path().app_data_dir() {
+        Ok(path_buf) => path_buf,
+        Err(e) => return CommandResult::err(format!("Failed to get app data dir: {}", e)),
+    };
+    
+    match storage::set_encryption_password(&data_dir, old.as_deref(), &new) {
+        Ok(()) => {
+            CommandResult::ok(true)
+        }
+        Err(e @ storage::DatabaseError::WrongPassword) => CommandResult::err(e.to_string()),
+        Err(e) => CommandResult::err(format!("Failed to set database password: {}", e)),
+    }
+}
+
+/// Get the names of all formats currently on the system clipboard
+#[tauri::command]
+pub fn get_current_clipboard_formats() -> CommandResult<Vec<String>> {
+    let _access = clipboard::lock_clipboard();
+    match ClipboardMonitor::read_format_names() {
+        Ok(formats) => CommandResult::ok(formats),
+        Err(e) => CommandResult::err(e),
+    }
+}
+
+/// Get daily capture counts for the last N days
+#[tauri::command]
+pub fn get_daily_counts(days: u32) -> CommandResult<Vec<storage::DailyCount>> {
+    match storage::get_daily_counts(days) {
+        Ok(counts) => CommandResult::ok(counts),
+        Err(e) => CommandResult::err(format!("Failed to get daily counts: {}", e)),
+    }
+}
+
+/// Get a local usage summary of the last N days (busiest hour, common type, ...)
+#[tauri::command]
+pub fn get_usage_insights(days: u32) -> CommandResult<storage::UsageInsights> {
+    match storage::get_usage_insights(days) {
+        Ok(insights) => CommandResult::ok(insights),
+        Err(e) => CommandResult::err(format!("Failed to get usage insights: {}", e)),
+    }
+}
+
+/// Get storage statistics (record counts, database and image sizes, date range)
+#[tauri::command]
+pub fn get_storage_stats(app: AppHandle) -> CommandResult<storage::StorageStats> {
+    let data_dir = match app.path().app_data_dir() {
+        Ok(path_buf) => path_buf,
+        Err(e) => return CommandResult::err(format!("Failed to get app data dir: {}", e)),
+    };
+    
+    match storage::get_storage_stats(&data_dir) {
+        Ok(stats) => CommandResult::ok(stats),
+        Err(e) => CommandResult::err(format!("Failed to get storage stats: {}", e)),
+    }
+}
+
+/// Compact the database file after large deletions
+/// 
+/// Returns the number of bytes freed
+#[tauri::command]
+pub fn vacuum_database(app: AppHandle) -> CommandResult<u64> {
+    let data_dir = match app.path().app_data_dir() {
+        Ok(path_buf) => path_buf,
+        Err(e) => return CommandResult::err(format!("Failed to get app data dir: {}", e)),
+    };
+    
+    match storage::vacuum_database(&data_dir) {
+        Ok(freed) => CommandResult::ok(freed),
+        Err(e) => CommandResult::err(format!("Failed to vacuum database: {}", e)),
+    }
+}
+
+/// Back up the database and images into a folder
+/// 
+/// An encrypted database needs its `password`
+#[tauri::command]
+pub fn backup_database(app: AppHandle, dest_dir: String, password: Option<String>) -> CommandResult<bool> {
+    let data_dir = match app.path().app_data_dir() {
+        Ok(path_buf) => path_buf,
+        Err(e) => return CommandResult::err(format!("Failed to get app data dir: {}", e)),
+    };
+    
+    match storage::backup_database(&data_dir, std::path::Path::new(&dest_dir), password.as_deref()) {
+        Ok(()) => CommandResult::ok(true),
+        Err(e) => CommandResult::err(format!("Failed to back up database: {}", e)),
+    }
+}
+
+/// Replace the database and images with a backup made by backup_database
+/// 
+/// An encrypted backup needs its `password`. Settings are reloaded from the restored database
+#[tauri::command]
+pub fn restore_database(app: AppHandle, src_dir: String, password: Option<String>) -> CommandResult<bool> {
+    let data_dir = match app.path().app_data_dir() {
+        Ok(path_buf) => path_buf,
+        Err(e) => return CommandResult::err(format!("Failed to get app data dir: {}", e)),
+    };
+    
+    match storage::restore_database(&data_dir, std::path::Path::new(&src_dir), password.as_deref()) {
+        Ok(()) => {
+            config::init_settings();
+            let _ = app.emit("clipboard-updated", ());
+            CommandResult::ok(true)
+        }
+        Err(e) => {
+            // The database was closed before failing, reopen what is there now
+            if matches!(storage::get_item_count(), Err(storage::DatabaseError::NotInitialized)) {
+                let _ = crate::init_storage(&app, None);
+            }
+            CommandResult::err(format!("Failed to restore database: {}", e))
+        }
+    }
+}
+
+/// Records of a single day for display
+#[derive(Debug, Serialize)]
+pub struct DateGroupView {
+    pub date: String,
+    pub count: i64,
+    pub items: Vec<ClipboardItemView>,
+}
+
+/// Get a page of clipboard history grouped by local calendar day
+#[tauri::command]
+pub fn get_grouped_by_date(limit: i32, offset: i32) -> CommandResult<Vec<DateGroupView>> {
+    match storage::get_grouped_by_date(limit, offset) {
+        Ok(groups) => {
+            let views = groups
+                .into_iter()
+                .map(|group| DateGroupView {
+                    date: group.date,
+                    count: group.count,
+                    items: group.items.into_iter().map(|i| i.into()).collect(),
+                })
+                .collect();
+            CommandResult::ok(views)
+        }
+        Err(e) => CommandResult::err(format!("Failed to get grouped history: {}", e)),
+    }
+}
+
+/// Preview how many records a storage limit change would delete
+#[tauri::command]
+pub fn preview_cleanup(max_count: i32) -> CommandResult<usize> {
+    match storage::count_cleanup_candidates(max_count) {
+        Ok(count) => CommandResult::ok(count as usize),
+        Err(e) => CommandResult::err(format!("Failed to preview cleanup: {}", e)),
+    }
+}
+
+/// List image files that no record references (left behind by crashes or earlier bugs)
+#[tauri::command]
+pub fn find_orphan_images(app: AppHandle) -> CommandResult<Vec<String>> {
+    let data_dir = match app.path().app_data_dir() {
+        Ok(path_buf) => path_buf,
+        Err(e) => return CommandResult::err(format!("Failed to get app data dir: {}", e)),
+    };
+    
+    match storage::find_orphan_images(&data_dir) {
+        Ok(orphans) => CommandResult::ok(orphans.into_iter().map(|(path, _)| path).collect()),
+        Err(e) => CommandResult::err(format!("Failed to scan images: {}", e)),
+    }
+}
+
+/// Delete image files that no record references
+/// 
+/// Returns the number of bytes freed
+#[tauri::command]
+pub fn delete_orphan_images(app: AppHandle) -> CommandResult<u64> {
+    let data_dir = match app.path().app_data_dir() {
+        Ok(path_buf) => path_buf,
+        Err(e) => return CommandResult::err(format!("Failed to get app data dir: {}", e)),
+    };
+    
+    match storage::remove_orphan_images(&data_dir) {
+        Ok((_, freed)) => CommandResult::ok(freed),
+        Err(e) => CommandResult::err(format!("Failed to scan images: {}", e)),
+    }
+}
+
+/// Delete image files that no record references
+/// 
+/// Returns the number of removed files
+#[tauri::command]
+pub fn cleanup_orphan_images(app: AppHandle) -> CommandResult<i64> {
+    let data_dir = match app.path().app_data_dir() {
+        Ok(path_buf) => path_buf,
+        Err(e) => return CommandResult::err(format!("Failed to get app data dir: {}", e)),
+    };
+    
+    match storage::remove_orphan_images(&data_dir) {
+        Ok((removed, _)) => CommandResult::ok(removed as i64),
+        Err(e) => CommandResult::err(format!("Failed to scan images: {}", e)),
+    }
+}
+
+/// Check if this is the first run
+#[tauri::command]
+pub fn is_first_run() -> CommandResult<bool> {
+    CommandResult::ok(config::is_first_run())
+}
+
+/// Mark first run as completed
+#[tauri::command]
+pub fn complete_first_run() -> CommandResult<bool> {
+    match config::mark_first_run_completed() {
+        Ok(()) => CommandResult::ok(true),
+        Err(e) => CommandResult::err(format!("Failed to mark first run completed: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_data_uri_follows_the_stored_format() {
+        let img = image::DynamicImage::ImageRgb8(image::RgbImage::new(4, 4));
+        for (format, mime) in [("png", "image/png"), ("jpeg", "image/jpeg"), ("webp", "image/webp")] {
+            let (bytes, _) = encode_image(&img, format, 80).unwrap();
+            assert!(image_data_uri(&bytes).starts_with(&format!("data:{};base64,", mime)));
+        }
+    }
+
+    #[test]
+    fn encrypted_payload_keeps_rtf() {
+        let payload = EncryptedPayload {
+            plain_text: Some("text".to_string()),
+            rich_text: None,
+            rtf: Some("{\\rtf1 text}".to_string()),
+        };
+        let json = serde_json::to_vec(&payload).unwrap();
+        let restored: EncryptedPayload = serde_json::from_slice(&json).unwrap();
+        assert_eq!(restored.rtf.as_deref(), Some("{\\rtf1 text}"));
+    }
+
+    #[test]
+    fn encrypted_payload_without_rtf_still_decodes() {
+        let restored: EncryptedPayload = serde_json::from_str(r#"{"plain_text":"text","rich_text":null}"#).unwrap();
+        assert_eq!(restored.plain_text.as_deref(), Some("text"));
+        assert_eq!(restored.rtf, None);
     }
 }
@@ -2,15 +2,31 @@
 //! 
 //! Manages application user configuration
 
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+use std::time::Duration;
+
 use serde::{Deserialize, Serialize};
 use parking_lot::RwLock;
 use once_cell::sync::Lazy;
 
+use crate::clipboard::DEFAULT_PREVIEW_LENGTH;
 use crate::storage;
 
 /// Global settings instance
 static SETTINGS: Lazy<RwLock<Settings>> = Lazy::new(|| RwLock::new(Settings::default()));
 
+/// Delay before updated settings are written to the database (milliseconds)
+const SAVE_DEBOUNCE_MS: u64 = 300;
+
+/// Settings update counter (only the latest update in a debounce window saves)
+static SAVE_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Whether in-memory settings have unsaved changes
+static SAVE_PENDING: AtomicBool = AtomicBool::new(false);
+
 /// Theme type
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
@@ -25,6 +41,142 @@ impl Default for Theme {
     }
 }
 
+/// Line ending normalization mode
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEndings {
+    /// Keep line endings as copied
+    #[default]
+    Keep,
+    /// Convert to LF
+    Lf,
+    /// Convert to CRLF
+    Crlf,
+}
+
+impl LineEndings {
+    /// Convert from string to LineEndings
+    pub fn from_name(s: &str) -> Self {
+        match s {
+            "lf" => LineEndings::Lf,
+            "crlf" => LineEndings::Crlf,
+            _ => LineEndings::Keep,
+        }
+    }
+
+    /// Convert to string
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEndings::Keep => "keep",
+            LineEndings::Lf => "lf",
+            LineEndings::Crlf => "crlf",
+        }
+    }
+
+    /// Apply line ending normalization to text
+    pub fn apply(&self, text: &str) -> String {
+        match self {
+            LineEndings::Keep => text.to_string(),
+            LineEndings::Lf => text.replace("\r\n", "\n").replace('\r', "\n"),
+            LineEndings::Crlf => text
+                .replace("\r\n", "\n")
+                .replace('\r', "\n")
+                .replace('\n', "\r\n"),
+        }
+    }
+}
+
+/// Keystroke simulated to paste into the target application
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PasteKeystroke {
+    /// Ctrl+V
+    #[default]
+    CtrlV,
+    /// Shift+Insert (for terminals and legacy apps that ignore Ctrl+V)
+    ShiftInsert,
+}
+
+impl PasteKeystroke {
+    /// Convert from string to PasteKeystroke
+    pub fn from_name(s: &str) -> Self {
+        match s {
+            "shift_insert" => PasteKeystroke::ShiftInsert,
+            _ => PasteKeystroke::CtrlV,
+        }
+    }
+
+    /// Convert to string
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PasteKeystroke::CtrlV => "ctrl_v",
+            PasteKeystroke::ShiftInsert => "shift_insert",
+        }
+    }
+}
+
+/// Handling of content that is already in the history
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateAction {
+    /// Keep the existing record where it is
+    #[default]
+    Skip,
+    /// Move the existing record to the top of the history
+    Bump,
+}
+
+impl DuplicateAction {
+    /// Convert from string to DuplicateAction
+    pub fn from_name(s: &str) -> Self {
+        match s {
+            "bump" => DuplicateAction::Bump,
+            _ => DuplicateAction::Skip,
+        }
+    }
+
+    /// Convert to string
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DuplicateAction::Skip => "skip",
+            DuplicateAction::Bump => "bump",
+        }
+    }
+}
+
+/// Window visibility on startup
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum StartupWindow {
+    /// Start hidden in the tray
+    #[default]
+    Hidden,
+    /// Show the window on startup
+    Shown,
+    /// Restore the visibility from the last session
+    LastState,
+}
+
+impl StartupWindow {
+    /// Convert from string to StartupWindow
+    pub fn from_name(s: &str) -> Self {
+        match s {
+            "shown" => StartupWindow::Shown,
+            "last_state" => StartupWindow::LastState,
+            _ => StartupWindow::Hidden,
+        }
+    }
+
+    /// Convert to string
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StartupWindow::Hidden => "hidden",
+            StartupWindow::Shown => "shown",
+            StartupWindow::LastState => "last_state",
+        }
+    }
+}
+
 /// Storage limit options
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum StorageLimit {
@@ -88,7 +240,10 @@ impl StorageLimit {
 }
 
 /// User settings
+/// 
+/// Fields missing from imported JSON keep their defaults
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Settings {
     /// Theme
     pub theme: Theme,
@@ -101,6 +256,65 @@ pub struct Settings {
 
     /// Global shortcut
     pub shortcut: String,
+    /// Restore the previous clipboard content after a paste
+    pub restore_clipboard_after_paste: bool,
+    /// Maximum age of image records in days (None means no age limit)
+    pub image_max_age_days: Option<u32>,
+    /// Line ending normalization applied on capture and paste
+    /// 
+    /// Text is normalized before hashing, so the same text copied with
+    /// different line endings is deduplicated when normalization is enabled
+    pub normalize_line_endings: LineEndings,
+    /// Record a placeholder for clipboard content in unsupported formats
+    pub record_unsupported: bool,
+    /// Back up the history to a JSON file before clearing it
+    pub backup_before_clear: bool,
+    /// Images smaller than this in both dimensions are not captured (0 disables)
+    pub min_image_dimension: u32,
+    /// Detect the language of captured text
+    pub detect_language: bool,
+    /// Maximum number of image files kept on disk (None means no limit)
+    pub max_image_files: Option<usize>,
+    /// Keep the window visible this long after a paste before auto-hiding (None hides immediately)
+    pub post_paste_linger_ms: Option<u32>,
+    /// Record the content already on the clipboard when the app starts
+    pub capture_on_startup: bool,
+    /// Treat rich text and plain text with the same visible text as one record
+    pub merge_rich_and_plain: bool,
+    /// Keystroke simulated to paste
+    pub paste_keystroke: PasteKeystroke,
+    /// Wait after hiding the window before restoring focus (milliseconds)
+    pub paste_hide_delay_ms: u32,
+    /// Wait after restoring focus before sending the paste keystroke (milliseconds)
+    pub paste_focus_delay_ms: u32,
+    /// Stop capturing when free space on the data drive drops below this many MB (None disables the check)
+    pub min_free_disk_mb: Option<u64>,
+    /// Window visibility on startup (the first run always shows the window)
+    pub startup_window: StartupWindow,
+    /// Active history profile (records of other profiles are hidden)
+    pub active_profile: String,
+    /// Store plain text that is HTML markup as rich text
+    pub detect_html_in_text: bool,
+    /// Re-encode captured images so no EXIF/metadata (camera, GPS location) is stored
+    pub strip_image_metadata: bool,
+    /// Prefer the PNG clipboard format over DIB/RGBA images when an app provides both
+    pub prefer_png_clipboard: bool,
+    /// Record clipboard changes (false keeps the monitor paused, also across restarts)
+    pub monitoring_enabled: bool,
+    /// Ask for confirmation before pasting content larger than this many KB (None = never ask)
+    pub large_paste_threshold_kb: Option<u32>,
+    /// Delay before restoring the previous clipboard content after a paste (milliseconds)
+    pub restore_clipboard_delay_ms: u32,
+    /// Regular expressions, captured text matching any of them is pinned automatically
+    pub auto_pin_patterns: Vec<String>,
+    /// Pause capture while this process (e.g. "zoom.exe") is in the foreground (empty = off)
+    pub pause_while_process: String,
+    /// Days deleted records stay in the trash before they are purged (None keeps them until the trash is emptied)
+    pub trash_retention_days: Option<u32>,
+    /// Move a pasted record to the top of the history
+    pub bump_on_paste: bool,
+    /// What happens when content already in the history is copied again
+    pub on_duplicate: DuplicateAction,
 }
 
 impl Default for Settings {
@@ -109,8 +323,36 @@ impl Default for Settings {
             theme: Theme::Light,
             storage_limit: StorageLimit::Limit100,
             auto_start: false,
-            preview_length: 100,
+            preview_length: DEFAULT_PREVIEW_LENGTH,
             shortcut: "Alt+V".to_string(),
+            restore_clipboard_after_paste: false,
+            image_max_age_days: None,
+            normalize_line_endings: LineEndings::Keep,
+            record_unsupported: false,
+            backup_before_clear: false,
+            min_image_dimension: 0,
+            detect_language: false,
+            max_image_files: None,
+            post_paste_linger_ms: None,
+            capture_on_startup: false,
+            merge_rich_and_plain: false,
+            paste_keystroke: PasteKeystroke::CtrlV,
+            paste_hide_delay_ms: 50,
+            paste_focus_delay_ms: 100,
+            min_free_disk_mb: None,
+            startup_window: StartupWindow::Hidden,
+            active_profile: storage::DEFAULT_PROFILE.to_string(),
+            detect_html_in_text: false,
+            strip_image_metadata: false,
+            prefer_png_clipboard: false,
+            monitoring_enabled: true,
+            large_paste_threshold_kb: Some(1024),
+            restore_clipboard_delay_ms: 200,
+            auto_pin_patterns: Vec::new(),
+            pause_while_process: String::new(),
+            trash_retention_days: Some(30),
+            bump_on_paste: false,
+            on_duplicate: DuplicateAction::Skip,
         }
     }
 }
@@ -140,15 +382,181 @@ impl Settings {
             settings.auto_start = auto_start_str == "true";
         }
         
+        // Load preview length
+        if let Ok(Some(length_str)) = storage::get_setting("preview_length") {
+            if let Ok(length) = length_str.parse::<usize>() {
+                settings.preview_length = length;
+            }
+        }
+        
 
         // Load shortcut
         if let Ok(Some(shortcut)) = storage::get_setting("shortcut") {
             settings.shortcut = shortcut;
         }
         
+        // Load clipboard restore setting
+        if let Ok(Some(restore_str)) = storage::get_setting("restore_clipboard_after_paste") {
+            settings.restore_clipboard_after_paste = restore_str == "true";
+        }
+        
+        // Load image max age (empty means no age limit)
+        if let Ok(Some(age_str)) = storage::get_setting("image_max_age_days") {
+            settings.image_max_age_days = age_str.parse::<u32>().ok();
+        }
+        
+        // Load line ending normalization mode
+        if let Ok(Some(mode_str)) = storage::get_setting("normalize_line_endings") {
+            settings.normalize_line_endings = LineEndings::from_name(&mode_str);
+        }
+        
+        // Load unsupported format recording setting
+        if let Ok(Some(record_str)) = storage::get_setting("record_unsupported") {
+            settings.record_unsupported = record_str == "true";
+        }
+        
+        // Load backup before clear setting
+        if let Ok(Some(backup_str)) = storage::get_setting("backup_before_clear") {
+            settings.backup_before_clear = backup_str == "true";
+        }
+        
+        // Load minimum image dimension
+        if let Ok(Some(dim_str)) = storage::get_setting("min_image_dimension") {
+            if let Ok(dim) = dim_str.parse::<u32>() {
+                settings.min_image_dimension = dim;
+            }
+        }
+        
+        // Load language detection setting
+        if let Ok(Some(detect_str)) = storage::get_setting("detect_language") {
+            settings.detect_language = detect_str == "true";
+        }
+        
+        // Load image file limit (empty means no limit)
+        if let Ok(Some(max_str)) = storage::get_setting("max_image_files") {
+            settings.max_image_files = max_str.parse::<usize>().ok();
+        }
+        
+        // Load post-paste linger time (empty means hide immediately)
+        if let Ok(Some(linger_str)) = storage::get_setting("post_paste_linger_ms") {
+            settings.post_paste_linger_ms = linger_str.parse::<u32>().ok();
+        }
+        
+        // Load startup capture
+        if let Ok(Some(capture_str)) = storage::get_setting("capture_on_startup") {
+            settings.capture_on_startup = capture_str == "true";
+        }
+        
+        // Load rich/plain text merging
+        if let Ok(Some(merge_str)) = storage::get_setting("merge_rich_and_plain") {
+            settings.merge_rich_and_plain = merge_str == "true";
+        }
+        
+        // Load paste keystroke
+        if let Ok(Some(keystroke_str)) = storage::get_setting("paste_keystroke") {
+            settings.paste_keystroke = PasteKeystroke::from_name(&keystroke_str);
+        }
+        
+        // Load paste hide delay
+        if let Ok(Some(delay_str)) = storage::get_setting("paste_hide_delay_ms") {
+            if let Ok(delay) = delay_str.parse::<u32>() {
+                settings.paste_hide_delay_ms = delay;
+            }
+        }
+        
+        // Load paste focus delay
+        if let Ok(Some(delay_str)) = storage::get_setting("paste_focus_delay_ms") {
+            if let Ok(delay) = delay_str.parse::<u32>() {
+                settings.paste_focus_delay_ms = delay;
+            }
+        }
+        
+        // Load low-disk threshold (empty means no check)
+        if let Ok(Some(min_str)) = storage::get_setting("min_free_disk_mb") {
+            settings.min_free_disk_mb = min_str.parse::<u64>().ok();
+        }
+        
+        if let Ok(Some(startup_str)) = storage::get_setting("startup_window") {
+            settings.startup_window = StartupWindow::from_name(&startup_str);
+        }
+        
+        if let Ok(Some(profile)) = storage::get_setting("active_profile") {
+            if !profile.is_empty() {
+                settings.active_profile = profile;
+            }
+        }
+        
+        // Load HTML-in-text detection setting
+        if let Ok(Some(detect_str)) = storage::get_setting("detect_html_in_text") {
+            settings.detect_html_in_text = detect_str == "true";
+        }
+        
+        if let Ok(Some(strip_str)) = storage::get_setting("strip_image_metadata") {
+            settings.strip_image_metadata = strip_str == "true";
+        }
+        
+        if let Ok(Some(prefer_str)) = storage::get_setting("prefer_png_clipboard") {
+            settings.prefer_png_clipboard = prefer_str == "true";
+        }
+        
+        if let Ok(Some(enabled_str)) = storage::get_setting("monitoring_enabled") {
+            settings.monitoring_enabled = enabled_str == "true";
+        }
+        
+        // Load large paste threshold (empty means never ask)
+        if let Ok(Some(threshold_str)) = storage::get_setting("large_paste_threshold_kb") {
+            settings.large_paste_threshold_kb = threshold_str.parse::<u32>().ok();
+        }
+        
+        // Load clipboard restore delay
+        if let Ok(Some(delay_str)) = storage::get_setting("restore_clipboard_delay_ms") {
+            if let Ok(delay) = delay_str.parse::<u32>() {
+                settings.restore_clipboard_delay_ms = delay;
+            }
+        }
+        
+        // Load auto-pin patterns (JSON array)
+        if let Ok(Some(patterns_json)) = storage::get_setting("auto_pin_patterns") {
+            if let Ok(patterns) = serde_json::from_str(&patterns_json) {
+                settings.auto_pin_patterns = patterns;
+            }
+        }
+        
+        if let Ok(Some(process)) = storage::get_setting("pause_while_process") {
+            settings.pause_while_process = process;
+        }
+        
+        // Load trash retention (empty means deleted records are kept until purged)
+        if let Ok(Some(days_str)) = storage::get_setting("trash_retention_days") {
+            settings.trash_retention_days = days_str.parse::<u32>().ok();
+        }
+        
+        // Load bump on paste setting
+        if let Ok(Some(bump_str)) = storage::get_setting("bump_on_paste") {
+            settings.bump_on_paste = bump_str == "true";
+        }
+        
+        // Load duplicate handling
+        if let Ok(Some(action_str)) = storage::get_setting("on_duplicate") {
+            settings.on_duplicate = DuplicateAction::from_name(&action_str);
+        }
+        
         settings
     }
 
+    /// Write settings as JSON to a file
+    pub fn export_to_file(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Read settings from a JSON file written by export_to_file
+    pub fn import_from_file(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let json = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
     /// Save settings (to database)
     pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
         let theme_str = match self.theme {
@@ -158,8 +566,55 @@ impl Settings {
         storage::save_setting("theme", theme_str)?;
         storage::save_setting("storage_limit", &self.storage_limit.as_i32().to_string())?;
         storage::save_setting("auto_start", &self.auto_start.to_string())?;
+        storage::save_setting("preview_length", &self.preview_length.to_string())?;
 
         storage::save_setting("shortcut", &self.shortcut)?;
+        storage::save_setting("restore_clipboard_after_paste", &self.restore_clipboard_after_paste.to_string())?;
+        storage::save_setting(
+            "image_max_age_days",
+            &self.image_max_age_days.map(|d| d.to_string()).unwrap_or_default(),
+        )?;
+        storage::save_setting("normalize_line_endings", self.normalize_line_endings.as_str())?;
+        storage::save_setting("record_unsupported", &self.record_unsupported.to_string())?;
+        storage::save_setting("backup_before_clear", &self.backup_before_clear.to_string())?;
+        storage::save_setting("min_image_dimension", &self.min_image_dimension.to_string())?;
+        storage::save_setting("detect_language", &self.detect_language.to_string())?;
+        storage::save_setting(
+            "max_image_files",
+            &self.max_image_files.map(|n| n.to_string()).unwrap_or_default(),
+        )?;
+        storage::save_setting(
+            "post_paste_linger_ms",
+            &self.post_paste_linger_ms.map(|ms| ms.to_string()).unwrap_or_default(),
+        )?;
+        storage::save_setting("capture_on_startup", &self.capture_on_startup.to_string())?;
+        storage::save_setting("merge_rich_and_plain", &self.merge_rich_and_plain.to_string())?;
+        storage::save_setting("paste_keystroke", self.paste_keystroke.as_str())?;
+        storage::save_setting("paste_hide_delay_ms", &self.paste_hide_delay_ms.to_string())?;
+        storage::save_setting("paste_focus_delay_ms", &self.paste_focus_delay_ms.to_string())?;
+        storage::save_setting(
+            "min_free_disk_mb",
+            &self.min_free_disk_mb.map(|mb| mb.to_string()).unwrap_or_default(),
+        )?;
+        storage::save_setting("startup_window", self.startup_window.as_str())?;
+        storage::save_setting("active_profile", &self.active_profile)?;
+        storage::save_setting("detect_html_in_text", &self.detect_html_in_text.to_string())?;
+        storage::save_setting("strip_image_metadata", &self.strip_image_metadata.to_string())?;
+        storage::save_setting("prefer_png_clipboard", &self.prefer_png_clipboard.to_string())?;
+        storage::save_setting("monitoring_enabled", &self.monitoring_enabled.to_string())?;
+        storage::save_setting(
+            "large_paste_threshold_kb",
+            &self.large_paste_threshold_kb.map(|kb| kb.to_string()).unwrap_or_default(),
+        )?;
+        storage::save_setting("restore_clipboard_delay_ms", &self.restore_clipboard_delay_ms.to_string())?;
+        storage::save_setting("auto_pin_patterns", &serde_json::to_string(&self.auto_pin_patterns)?)?;
+        storage::save_setting("pause_while_process", &self.pause_while_process)?;
+        storage::save_setting(
+            "trash_retention_days",
+            &self.trash_retention_days.map(|d| d.to_string()).unwrap_or_default(),
+        )?;
+        storage::save_setting("bump_on_paste", &self.bump_on_paste.to_string())?;
+        storage::save_setting("on_duplicate", self.on_duplicate.as_str())?;
         
         Ok(())
     }
@@ -171,15 +626,44 @@ pub fn get_settings() -> Settings {
 }
 
 /// Update settings
+/// 
+/// The in-memory settings change immediately, the database write is debounced
+/// so rapid changes within SAVE_DEBOUNCE_MS are coalesced into a single save
 pub fn update_settings(settings: Settings) -> Result<(), Box<dyn std::error::Error>> {
-    settings.save()?;
     *SETTINGS.write() = settings;
+    SAVE_PENDING.store(true, Ordering::SeqCst);
+    let generation = SAVE_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(SAVE_DEBOUNCE_MS));
+        if SAVE_GENERATION.load(Ordering::SeqCst) == generation {
+            if let Err(e) = flush_settings() {
+                log::error!("Failed to save settings: {}", e);
+            }
+        }
+    });
+    
+    Ok(())
+}
+
+/// Write pending settings changes to the database immediately
+pub fn flush_settings() -> Result<(), Box<dyn std::error::Error>> {
+    if !SAVE_PENDING.swap(false, Ordering::SeqCst) {
+        return Ok(());
+    }
+    
+    if let Err(e) = get_settings().save() {
+        SAVE_PENDING.store(true, Ordering::SeqCst);
+        return Err(e);
+    }
+    
     Ok(())
 }
 
 /// Initialize settings (load from database)
 pub fn init_settings() {
     let settings = Settings::load();
+    storage::set_active_profile(&settings.active_profile);
     *SETTINGS.write() = settings;
     log::info!("Settings initialized");
 }
@@ -192,8 +676,46 @@ pub fn is_first_run() -> bool {
     }
 }
 
+/// Record whether the main window is visible (restored by `StartupWindow::LastState`)
+pub fn save_window_visible(visible: bool) {
+    if let Err(e) = storage::save_setting("window_last_visible", if visible { "true" } else { "false" }) {
+        log::warn!("Failed to save window visibility: {}", e);
+    }
+}
+
+/// Whether the main window was visible when the last session ended
+pub fn was_window_visible() -> bool {
+    matches!(storage::get_setting("window_last_visible"), Ok(Some(val)) if val == "true")
+}
+
 /// Mark first run as completed
 pub fn mark_first_run_completed() -> Result<(), Box<dyn std::error::Error>> {
     storage::save_setting("first_run_completed", "true")?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// CR, CRLF and LF line endings in one text
+    const MIXED: &str = "a\rb\r\nc\nd";
+    
+    #[test]
+    fn line_endings_keep_leaves_text_unchanged() {
+        assert_eq!(LineEndings::Keep.apply(MIXED), MIXED);
+    }
+    
+    #[test]
+    fn line_endings_lf_converts_cr_and_crlf() {
+        assert_eq!(LineEndings::Lf.apply(MIXED), "a\nb\nc\nd");
+        assert_eq!(LineEndings::Lf.apply("\r\n\r\n"), "\n\n");
+    }
+    
+    #[test]
+    fn line_endings_crlf_converts_cr_and_lf() {
+        assert_eq!(LineEndings::Crlf.apply(MIXED), "a\r\nb\r\nc\r\nd");
+        // Existing CRLF isn't doubled
+        assert_eq!(LineEndings::Crlf.apply("x\r\ny"), "x\r\ny");
+    }
+}
@@ -10,18 +10,20 @@ pub mod storage;
 pub mod tray;
 
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
 use tauri::{AppHandle, Manager, Emitter};
 use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
 use parking_lot::Mutex;
 
-use clipboard::{ClipboardMonitor, ClipboardSnapshot, ClipboardItem, ContentType};
+use clipboard::{short_hash, ClipboardMonitor, ClipboardSnapshot, ClipboardItem, ContentType};
 use storage::init_database;
 use config::init_settings;
 
 /// Global clipboard monitor instance
-static CLIPBOARD_MONITOR: once_cell::sync::Lazy<Arc<Mutex<Option<ClipboardMonitor>>>> =
+pub(crate) static CLIPBOARD_MONITOR: once_cell::sync::Lazy<Arc<Mutex<Option<ClipboardMonitor>>>> =
     once_cell::sync::Lazy::new(|| Arc::new(Mutex::new(None)));
 
 /// Store the previous active window handle (used to restore focus)
@@ -29,6 +31,50 @@ static CLIPBOARD_MONITOR: once_cell::sync::Lazy<Arc<Mutex<Option<ClipboardMonito
 static PREVIOUS_WINDOW: once_cell::sync::Lazy<Arc<Mutex<isize>>> =
     once_cell::sync::Lazy::new(|| Arc::new(Mutex::new(0)));
 
+/// Last window of another application that took focus from EveryPaste
+/// (paste target when the saved previous window no longer exists)
+#[cfg(target_os = "windows")]
+static LAST_EXTERNAL_WINDOW: once_cell::sync::Lazy<Arc<Mutex<isize>>> =
+    once_cell::sync::Lazy::new(|| Arc::new(Mutex::new(0)));
+
+/// Clipboard state saved before a paste
+pub(crate) enum SavedClipboard {
+    /// The clipboard was empty (cleared again on restore)
+    Empty,
+    Content(ClipboardSnapshot),
+}
+
+/// Clipboard state saved before a paste (restored once the paste completes)
+pub(crate) static SAVED_CLIPBOARD: once_cell::sync::Lazy<Arc<Mutex<Option<SavedClipboard>>>> =
+    once_cell::sync::Lazy::new(|| Arc::new(Mutex::new(None)));
+
+/// Save the current clipboard state if restoring after a paste is enabled
+pub(crate) fn save_clipboard_for_restore() {
+    if !config::get_settings().restore_clipboard_after_paste {
+        return;
+    }
+    let saved = match ClipboardSnapshot::capture() {
+        Some(snapshot) => Some(SavedClipboard::Content(snapshot)),
+        None if ClipboardMonitor::is_clipboard_empty() => Some(SavedClipboard::Empty),
+        None => None,
+    };
+    *SAVED_CLIPBOARD.lock() = saved;
+}
+
+/// Database initialization error (set while storage is unavailable)
+pub(crate) static STORAGE_ERROR: once_cell::sync::Lazy<Mutex<Option<String>>> =
+    once_cell::sync::Lazy::new(|| Mutex::new(None));
+
+/// Whether the database is encrypted and waiting for its password
+pub(crate) static STORAGE_LOCKED: AtomicBool = AtomicBool::new(false);
+
+/// Whether a disk-low event was emitted (reset once space is available again)
+static DISK_LOW_NOTIFIED: AtomicBool = AtomicBool::new(false);
+
+/// Deadline for hiding the window after a paste (None when no auto-hide is pending)
+static AUTO_HIDE_DEADLINE: once_cell::sync::Lazy<Mutex<Option<Instant>>> =
+    once_cell::sync::Lazy::new(|| Mutex::new(None));
+
 /// Get the application data directory
 fn get_data_dir(app: &AppHandle) -> PathBuf {
     app.path()
@@ -36,43 +82,214 @@ fn get_data_dir(app: &AppHandle) -> PathBuf {
         .unwrap_or_else(|_| PathBuf::from("."))
 }
 
+/// Initialize the database (`password` opens an encrypted database)
+/// 
+/// On failure the error is kept for the UI and a storage-error event is emitted
+/// (database-locked when the database is encrypted), the app keeps running
+/// without capture until initialization is retried
+pub(crate) fn init_storage(app: &AppHandle, password: Option<&str>) -> Result<(), String> {
+    let data_dir = get_data_dir(app);
+    log::info!("Data directory: {:?}", data_dir);
+    
+    match init_database(&data_dir, password) {
+        Ok(()) => {
+            *STORAGE_ERROR.lock() = None;
+            STORAGE_LOCKED.store(false, Ordering::SeqCst);
+            Ok(())
+        }
+        Err(e @ (storage::DatabaseError::Locked | storage::DatabaseError::WrongPassword)) => {
+            let message = e.to_string();
+            log::warn!("{}", message);
+            *STORAGE_ERROR.lock() = Some(message.clone());
+            STORAGE_LOCKED.store(true, Ordering::SeqCst);
+            let _ = app.emit("database-locked", &message);
+            Err(message)
+        }
+        Err(e) => {
+            let message = format!("Failed to open database at {:?}: {}", data_dir.join("data.db"), e);
+            log::error!("{}", message);
+            *STORAGE_ERROR.lock() = Some(message.clone());
+            STORAGE_LOCKED.store(false, Ordering::SeqCst);
+            let _ = app.emit("storage-error", &message);
+            Err(message)
+        }
+    }
+}
+
 /// Handle new clipboard content
-fn handle_new_clipboard_content(app: &AppHandle, snapshot: ClipboardSnapshot) {
-    log::info!("[Handler] Processing new clipboard content: {:?}, hash: {}", snapshot.content_type, &snapshot.hash[..8]);
+/// 
+/// Returns the id of the saved record, or of the existing record for duplicate content
+pub(crate) fn handle_new_clipboard_content(app: &AppHandle, mut snapshot: ClipboardSnapshot) -> Option<i64> {
+    log::info!("[Handler] Processing new clipboard content: {:?}, hash: {}", snapshot.content_type, short_hash(&snapshot.hash));
+    
+    // Skip unsupported format placeholders unless recording is enabled
+    if snapshot.content_type == ContentType::Unsupported && !config::get_settings().record_unsupported {
+        return None;
+    }
+    
+    // Normalize line endings before deduplication (the hash follows the normalized text)
+    let line_endings = config::get_settings().normalize_line_endings;
+    if snapshot.content_type == ContentType::Text && line_endings != config::LineEndings::Keep {
+        if let Some(text) = &snapshot.plain_text {
+            let normalized = line_endings.apply(text);
+            if normalized != *text {
+                snapshot.hash = ClipboardMonitor::compute_hash(normalized.as_bytes());
+                snapshot.plain_text = Some(normalized);
+            }
+        }
+    }
+    
+    // HTML markup put into the plain-text slot is stored as rich text (markup kept, text extracted)
+    if snapshot.content_type == ContentType::Text && config::get_settings().detect_html_in_text {
+        if let Some(text) = snapshot.plain_text.take() {
+            if clipboard::html::looks_like_html(&text) {
+                log::info!("[Handler] Plain text is HTML markup, storing as rich text");
+                snapshot.content_type = ContentType::RichText;
+                snapshot.plain_text = Some(clipboard::html::strip_tags(&text));
+                snapshot.rich_text = Some(text);
+            } else {
+                snapshot.plain_text = Some(text);
+            }
+        }
+    }
+    
+    // Images are deduplicated by their canonical key, so the same picture read
+    // from a different clipboard format isn't saved twice
+    let mut image_key = None;
+    if snapshot.content_type == ContentType::Image {
+        image_key = snapshot.image_data.as_deref().and_then(ClipboardMonitor::compute_image_key);
+        if let Some(key) = &image_key {
+            match storage::get_item_id_by_image_key(key) {
+                Ok(Some(id)) => {
+                    log::info!("[Handler] Image already exists (key: {}), skipping", key);
+                    if resurface_duplicate(id, config::get_settings().on_duplicate) {
+                        let _ = app.emit("clipboard-updated", ());
+                    }
+                    return Some(id);
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    log::error!("[Handler] Failed to check image key: {}", e);
+                    return None;
+                }
+            }
+        }
+    }
     
     // Check if content already exists
     match storage::hash_exists(&snapshot.hash) {
         Ok(true) => {
-            log::info!("[Handler] Content already exists (hash: {}), skipping", &snapshot.hash[..8]);
-            return;
+            log::info!("[Handler] Content already exists (hash: {}), skipping", short_hash(&snapshot.hash));
+            let id = storage::get_item_id_by_hash(&snapshot.hash).ok().flatten();
+            if let Some(id) = id {
+                if resurface_duplicate(id, config::get_settings().on_duplicate) {
+                    let _ = app.emit("clipboard-updated", ());
+                }
+            }
+            return id;
         },
         Ok(false) => {
-            log::info!("[Handler] New content, proceeding to save (hash: {})", &snapshot.hash[..8]);
+            log::info!("[Handler] New content, proceeding to save (hash: {})", short_hash(&snapshot.hash));
         },
         Err(e) => {
             log::error!("[Handler] Failed to check hash existence: {}", e);
-            return;
+            return None;
+        }
+    }
+    
+    // Merge rich text and plain text with the same visible text:
+    // - plain text matching the plain part of a rich text record is skipped
+    // - rich text matching a plain text record upgrades that record in place
+    if config::get_settings().merge_rich_and_plain {
+        if let Some(merged_id) = merge_with_text_record(app, &snapshot) {
+            return Some(merged_id);
+        }
+    }
+    
+    // Stop capturing while the data drive is almost full
+    if let Some(min_free_mb) = config::get_settings().min_free_disk_mb {
+        if !check_free_space(app, min_free_mb) {
+            return None;
+        }
+    }
+    
+    // Record the application the content was copied from
+    let source_path = source_process_path();
+    let source_app = source_path.as_ref().and_then(|path| {
+        std::path::Path::new(path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+    });
+    let source_icon = source_path.and_then(|path| cached_source_icon(app, &path));
+    
+    // A single URL is stored as a link, a single color code as a color
+    if snapshot.content_type == ContentType::Text {
+        if let Some(text) = snapshot.plain_text.as_deref() {
+            if clipboard::link::is_single_url(text) {
+                snapshot.content_type = ContentType::Link;
+            } else if clipboard::color::parse_color(text).is_some() {
+                snapshot.content_type = ContentType::Color;
+            }
         }
     }
     
     // Create clipboard record
-    let item: ClipboardItem = match snapshot.content_type {
+    let preview_len = config::get_settings().preview_length;
+    let rtf = snapshot.rtf.take();
+    let mut item: ClipboardItem = match snapshot.content_type {
         ContentType::Text => {
             if let Some(text) = snapshot.plain_text {
-                ClipboardItem::new_text(0, text, snapshot.hash)
+                ClipboardItem::new_text_with_len(0, text, snapshot.hash, preview_len)
+            } else {
+                return None;
+            }
+        }
+        ContentType::Link => {
+            if let Some(text) = snapshot.plain_text {
+                let mut item = ClipboardItem::new_text_with_len(0, text, snapshot.hash, preview_len);
+                item.content_type = ContentType::Link;
+                item
+            } else {
+                return None;
+            }
+        }
+        ContentType::Color => {
+            if let Some(text) = snapshot.plain_text {
+                let hex = clipboard::color::parse_color(&text).map(clipboard::color::to_hex)?;
+                let mut item = ClipboardItem::new_text_with_len(0, text, snapshot.hash, preview_len);
+                item.content_type = ContentType::Color;
+                item.preview = hex;
+                item
             } else {
-                return;
+                return None;
             }
         }
         ContentType::RichText => {
             if let (Some(plain), Some(html)) = (snapshot.plain_text, snapshot.rich_text) {
-                ClipboardItem::new_rich_text(0, plain, html, snapshot.hash)
+                ClipboardItem::new_rich_text_with_len(0, plain, html, snapshot.hash, preview_len)
             } else {
-                return;
+                return None;
             }
         }
         ContentType::Image => {
-            if let Some(image_data) = snapshot.image_data {
+            if let Some(mut image_data) = snapshot.image_data {
+                // Skip tiny images (icons, UI elements)
+                if is_tiny_image(&image_data, config::get_settings().min_image_dimension) {
+                    log::info!("[Handler] Skipping small image");
+                    return None;
+                }
+                
+                // Drop metadata (EXIF location, camera details) before anything is written to disk
+                if config::get_settings().strip_image_metadata {
+                    match strip_image_metadata(&image_data) {
+                        Some(stripped) => image_data = stripped,
+                        None => {
+                            log::error!("[Handler] Failed to strip image metadata, skipping image");
+                            return None;
+                        }
+                    }
+                }
+                
                 // Save image to file
                 let data_dir = get_data_dir(app);
                 let images_dir = data_dir.join("images");
@@ -83,24 +300,70 @@ fn handle_new_clipboard_content(app: &AppHandle, snapshot: ClipboardSnapshot) {
                 
                 if std::fs::write(&image_path, &image_data).is_err() {
                     log::error!("Failed to save image");
-                    return;
+                    return None;
                 }
                 
                 // Generate thumbnail (Base64)
                 let thumbnail = generate_thumbnail(&image_data);
                 
-                ClipboardItem::new_image(
+                let mut item = ClipboardItem::new_image(
                     0,
                     format!("images/{}", filename),
                     thumbnail,
                     snapshot.hash,
-                )
+                );
+                if let Some((width, height)) = image_dimensions(&image_data) {
+                    item.width = Some(width as i32);
+                    item.height = Some(height as i32);
+                }
+                item.byte_size = Some(image_data.len() as i64);
+                item
             } else {
-                return;
+                return None;
+            }
+        }
+        ContentType::File => {
+            let paths: Vec<String> = snapshot.plain_text.as_deref()
+                .and_then(|json| serde_json::from_str(json).ok())?;
+            ClipboardItem::new_files(0, &paths, snapshot.hash)
+        }
+        ContentType::Unsupported => {
+            if let Some(formats) = snapshot.plain_text {
+                ClipboardItem::new_unsupported(0, formats, snapshot.hash)
+            } else {
+                return None;
             }
         }
     };
     
+    item.source_app = source_app;
+    item.source_icon = source_icon;
+    item.rtf = rtf;
+    item.image_key = image_key;
+    
+    // Detect language of text content
+    if config::get_settings().detect_language && item.content_type != ContentType::File {
+        if let Some(text) = &item.plain_text {
+            item.lang = detect_language(text);
+        }
+    }
+    
+    // Detect single measurements (e.g. "72°F") so the UI can offer conversions
+    if item.content_type == ContentType::Text {
+        if let Some((value, unit)) = item.plain_text.as_deref().and_then(clipboard::units::parse_measurement) {
+            item.measure_value = Some(value);
+            item.measure_unit = Some(unit.to_string());
+        }
+    }
+    
+    // Pin text matching one of the auto-pin patterns
+    if let Some(text) = &item.plain_text {
+        if matches_auto_pin_pattern(text) {
+            log::info!("[Handler] Text matches an auto-pin pattern, pinning");
+            item.is_pinned = true;
+        }
+    }
+    
     // Save to database
     match storage::insert_clipboard_item(&item) {
         Ok(id) => {
@@ -110,24 +373,408 @@ fn handle_new_clipboard_content(app: &AppHandle, snapshot: ClipboardSnapshot) {
             let settings = config::get_settings();
             let limit = settings.storage_limit.as_i32();
             if limit > 0 {
-                if let Err(e) = storage::cleanup_old_items(limit) {
+                if let Err(e) = storage::cleanup_old_items(limit, &get_data_dir(app)) {
                     log::warn!("Failed to cleanup old items: {}", e);
                 }
             }
             
+            // Purge records that stayed in the trash past the retention time
+            if let Some(days) = settings.trash_retention_days {
+                if let Err(e) = storage::purge_trash(Some(days), &get_data_dir(app)) {
+                    log::warn!("Failed to purge trash: {}", e);
+                }
+            }
+            
+            // Cleanup images exceeding the image age limit
+            if let Some(days) = settings.image_max_age_days {
+                if let Err(e) = storage::cleanup_expired_images(days, &get_data_dir(app)) {
+                    log::warn!("Failed to cleanup expired images: {}", e);
+                }
+            }
+            
+            // Cleanup images exceeding the image file limit
+            if let Some(max_files) = settings.max_image_files {
+                if let Err(e) = storage::cleanup_image_files(max_files, &get_data_dir(app)) {
+                    log::warn!("Failed to cleanup image files: {}", e);
+                }
+            }
+            
+            // Keep the recent records cache warm for the next window open
+            if let Err(e) = storage::refresh_recent_cache() {
+                log::warn!("Failed to refresh recent records cache: {}", e);
+            }
+            
             // Notify frontend to refresh
             if let Err(e) = app.emit("clipboard-updated", ()) {
                 log::warn!("Failed to emit clipboard-updated event: {}", e);
             }
+            
+            Some(id)
         }
         Err(e) => {
             log::error!("Failed to save clipboard item: {}", e);
+            None
+        }
+    }
+}
+
+/// Check that the data drive has at least min_free_mb free
+/// 
+/// Emits a disk-low event the first time space runs low
+fn check_free_space(app: &AppHandle, min_free_mb: u64) -> bool {
+    let Some(free_bytes) = free_disk_space(&get_data_dir(app)) else {
+        return true;
+    };
+    
+    let free_mb = free_bytes / (1024 * 1024);
+    if free_mb >= min_free_mb {
+        DISK_LOW_NOTIFIED.store(false, Ordering::SeqCst);
+        return true;
+    }
+    
+    if !DISK_LOW_NOTIFIED.swap(true, Ordering::SeqCst) {
+        log::warn!("[Handler] Low disk space ({} MB free, minimum {} MB), capture stopped", free_mb, min_free_mb);
+        let _ = app.emit("disk-low", serde_json::json!({ "free_mb": free_mb, "min_free_mb": min_free_mb }));
+    }
+    false
+}
+
+/// Get the free space available to the user on the volume containing path (bytes)
+#[cfg(target_os = "windows")]
+fn free_disk_space(path: &std::path::Path) -> Option<u64> {
+    use windows::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+    use windows::core::HSTRING;
+
+    let mut free_bytes = 0u64;
+    let path = HSTRING::from(path.as_os_str());
+    unsafe { GetDiskFreeSpaceExW(&path, Some(&mut free_bytes), None, None) }.ok()?;
+    Some(free_bytes)
+}
+
+#[cfg(not(target_os = "windows"))]
+fn free_disk_space(_path: &std::path::Path) -> Option<u64> {
+    None
+}
+
+/// Get the executable path of the application that produced the clipboard content
+/// 
+/// Uses the foreground window, or the previously active window when
+/// EveryPaste itself is in the foreground
+#[cfg(target_os = "windows")]
+fn source_process_path() -> Option<String> {
+    use windows::Win32::Foundation::{CloseHandle, HWND};
+    use windows::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId};
+    use windows::core::PWSTR;
+
+    unsafe {
+        let mut hwnd = GetForegroundWindow();
+        let mut pid = 0u32;
+        GetWindowThreadProcessId(hwnd, Some(&mut pid));
+        
+        if pid == std::process::id() {
+            hwnd = HWND(*PREVIOUS_WINDOW.lock() as *mut _);
+            pid = 0;
+            GetWindowThreadProcessId(hwnd, Some(&mut pid));
+        }
+        if pid == 0 {
+            return None;
+        }
+        
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+        let mut buffer = [0u16; 1024];
+        let mut len = buffer.len() as u32;
+        let result = QueryFullProcessImageNameW(process, PROCESS_NAME_WIN32, PWSTR(buffer.as_mut_ptr()), &mut len);
+        let _ = CloseHandle(process);
+        result.ok()?;
+        
+        Some(String::from_utf16_lossy(&buffer[..len as usize]))
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn source_process_path() -> Option<String> {
+    None
+}
+
+/// Get the cached icon of an executable, extracting it on first use
+/// 
+/// Icons are stored as icons/<path hash>.png in the data directory,
+/// returns the relative path
+fn cached_source_icon(app: &AppHandle, exe_path: &str) -> Option<String> {
+    let key = ClipboardMonitor::compute_hash(exe_path.to_lowercase().as_bytes());
+    let relative_path = format!("icons/{}.png", &key[..16]);
+    let icon_file = get_data_dir(app).join(&relative_path);
+    
+    if !icon_file.exists() {
+        let png_data = extract_icon_png(exe_path)?;
+        if let Some(parent) = icon_file.parent() {
+            std::fs::create_dir_all(parent).ok()?;
+        }
+        if let Err(e) = std::fs::write(&icon_file, png_data) {
+            log::warn!("Failed to cache icon for {}: {}", exe_path, e);
+            return None;
+        }
+    }
+    
+    Some(relative_path)
+}
+
+/// Extract the small shell icon of a file as PNG
+#[cfg(target_os = "windows")]
+fn extract_icon_png(path: &str) -> Option<Vec<u8>> {
+    use windows::core::HSTRING;
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::Graphics::Gdi::{
+        DeleteObject, GetDC, GetDIBits, GetObjectW, ReleaseDC, BITMAP, BITMAPINFO, BITMAPINFOHEADER,
+        BI_RGB, DIB_RGB_COLORS,
+    };
+    use windows::Win32::Storage::FileSystem::FILE_FLAGS_AND_ATTRIBUTES;
+    use windows::Win32::UI::Shell::{SHGetFileInfoW, SHFILEINFOW, SHGFI_ICON, SHGFI_SMALLICON};
+    use windows::Win32::UI::WindowsAndMessaging::{DestroyIcon, GetIconInfo, ICONINFO};
+    
+    unsafe {
+        let mut file_info = SHFILEINFOW::default();
+        let result = SHGetFileInfoW(
+            &HSTRING::from(path),
+            FILE_FLAGS_AND_ATTRIBUTES(0),
+            Some(&mut file_info),
+            std::mem::size_of::<SHFILEINFOW>() as u32,
+            SHGFI_ICON | SHGFI_SMALLICON,
+        );
+        if result == 0 || file_info.hIcon.is_invalid() {
+            return None;
+        }
+        
+        let mut icon_info = ICONINFO::default();
+        let has_info = GetIconInfo(file_info.hIcon, &mut icon_info).is_ok();
+        let _ = DestroyIcon(file_info.hIcon);
+        if !has_info {
+            return None;
+        }
+        
+        // Read the color bitmap as top-down 32-bit BGRA
+        let mut bitmap = BITMAP::default();
+        let pixels = if !icon_info.hbmColor.is_invalid()
+            && GetObjectW(
+                icon_info.hbmColor,
+                std::mem::size_of::<BITMAP>() as i32,
+                Some(&mut bitmap as *mut _ as *mut _),
+            ) != 0
+        {
+            let (width, height) = (bitmap.bmWidth, bitmap.bmHeight);
+            let mut bitmap_info = BITMAPINFO {
+                bmiHeader: BITMAPINFOHEADER {
+                    biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+                    biWidth: width,
+                    biHeight: -height,
+                    biPlanes: 1,
+                    biBitCount: 32,
+                    biCompression: BI_RGB.0,
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            let mut pixels = vec![0u8; (width * height * 4) as usize];
+            let hdc = GetDC(HWND::default());
+            let lines = GetDIBits(
+                hdc,
+                icon_info.hbmColor,
+                0,
+                height as u32,
+                Some(pixels.as_mut_ptr() as *mut _),
+                &mut bitmap_info,
+                DIB_RGB_COLORS,
+            );
+            ReleaseDC(HWND::default(), hdc);
+            (lines != 0).then_some((width as u32, height as u32, pixels))
+        } else {
+            None
+        };
+        
+        let _ = DeleteObject(icon_info.hbmColor);
+        let _ = DeleteObject(icon_info.hbmMask);
+        
+        let (width, height, mut pixels) = pixels?;
+        for pixel in pixels.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+        // Icons without an alpha channel are fully opaque
+        if pixels.chunks_exact(4).all(|pixel| pixel[3] == 0) {
+            pixels.chunks_exact_mut(4).for_each(|pixel| pixel[3] = 255);
+        }
+        
+        let img = image::RgbaImage::from_raw(width, height, pixels)?;
+        let mut png_data = Vec::new();
+        img.write_to(&mut std::io::Cursor::new(&mut png_data), image::ImageFormat::Png).ok()?;
+        Some(png_data)
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn extract_icon_png(_path: &str) -> Option<Vec<u8>> {
+    None
+}
+
+/// Bring back the existing record of re-copied content
+/// 
+/// Restores it from the trash and, in bump mode, moves it to the top of the
+/// history; returns whether it was moved
+fn resurface_duplicate(id: i64, on_duplicate: config::DuplicateAction) -> bool {
+    if let Err(e) = storage::restore_item(id) {
+        log::warn!("[Handler] Failed to restore record from the trash: {}", e);
+    }
+    if on_duplicate != config::DuplicateAction::Bump {
+        return false;
+    }
+    match storage::bump_item(id) {
+        Ok(moved) => moved,
+        Err(e) => {
+            log::warn!("[Handler] Failed to move record to the top: {}", e);
+            false
+        }
+    }
+}
+
+/// Compiled auto-pin patterns with the pattern list they were compiled from
+static AUTO_PIN_REGEXES: once_cell::sync::Lazy<Mutex<(Vec<String>, Vec<regex::Regex>)>> =
+    once_cell::sync::Lazy::new(|| Mutex::new((Vec::new(), Vec::new())));
+
+/// Whether text matches any auto-pin pattern
+/// 
+/// Patterns are compiled once and again only after the setting changes,
+/// invalid patterns are skipped
+fn matches_auto_pin_pattern(text: &str) -> bool {
+    matches_any_pattern(config::get_settings().auto_pin_patterns, text)
+}
+
+/// Whether text matches any of the patterns, compiling them only when the list changes
+fn matches_any_pattern(patterns: Vec<String>, text: &str) -> bool {
+    if patterns.is_empty() {
+        return false;
+    }
+    
+    let mut compiled = AUTO_PIN_REGEXES.lock();
+    if compiled.0 != patterns {
+        let regexes = patterns.iter()
+            .filter_map(|pattern| match regex::Regex::new(pattern) {
+                Ok(regex) => Some(regex),
+                Err(e) => {
+                    log::warn!("Skipping invalid auto-pin pattern {:?}: {}", pattern, e);
+                    None
+                }
+            })
+            .collect();
+        *compiled = (patterns, regexes);
+    }
+    compiled.1.iter().any(|regex| regex.is_match(text))
+}
+
+/// Find the record a text snapshot merges into (see the merge_rich_and_plain setting)
+/// 
+/// Returns the id of the existing record if the snapshot was merged
+fn merge_with_text_record(app: &AppHandle, snapshot: &ClipboardSnapshot) -> Option<i64> {
+    match merge_text_snapshot(snapshot)? {
+        TextMerge::Skipped(id) => Some(id),
+        TextMerge::Upgraded(id) => {
+            let _ = app.emit("clipboard-updated", ());
+            Some(id)
         }
     }
 }
 
+/// How a text snapshot was merged into an existing record
+#[derive(Debug, PartialEq)]
+enum TextMerge {
+    /// Plain text matching a rich text record, nothing is saved
+    Skipped(i64),
+    /// Rich text matching a plain text record, the record became rich text
+    Upgraded(i64),
+}
+
+/// Merge a text snapshot into the record with the same visible text, if there is one
+fn merge_text_snapshot(snapshot: &ClipboardSnapshot) -> Option<TextMerge> {
+    let text = snapshot.plain_text.as_deref()?;
+    
+    match snapshot.content_type {
+        ContentType::Text => {
+            let id = storage::find_item_by_plain_text(&ContentType::RichText, text).ok().flatten()?;
+            log::info!("[Handler] Plain text matches rich text record {}, skipping", id);
+            Some(TextMerge::Skipped(id))
+        }
+        ContentType::RichText => {
+            let html = snapshot.rich_text.as_deref()?;
+            let id = storage::find_item_by_plain_text(&ContentType::Text, text).ok().flatten()?;
+            if let Err(e) = storage::upgrade_to_rich_text(id, html, &snapshot.hash) {
+                log::error!("[Handler] Failed to upgrade record {} to rich text: {}", id, e);
+                return None;
+            }
+            log::info!("[Handler] Upgraded text record {} to rich text", id);
+            Some(TextMerge::Upgraded(id))
+        }
+        _ => None,
+    }
+}
+
+/// Minimum text length (characters) for reliable language detection
+const MIN_LANG_DETECT_CHARS: usize = 20;
+
+/// Detect the language of text (ISO 639-3 code)
+/// 
+/// Only returns a result for text long enough to be detected reliably
+fn detect_language(text: &str) -> Option<String> {
+    if text.trim().chars().count() < MIN_LANG_DETECT_CHARS {
+        return None;
+    }
+    
+    let info = whatlang::detect(text)?;
+    if info.is_reliable() {
+        Some(info.lang().code().to_string())
+    } else {
+        None
+    }
+}
+
+/// Read image dimensions from encoded image data
+fn image_dimensions(image_data: &[u8]) -> Option<(u32, u32)> {
+    use image::ImageReader;
+    use std::io::Cursor;
+    
+    ImageReader::new(Cursor::new(image_data))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()
+}
+
+/// Whether the image is smaller than `min_dim` in both dimensions (0 disables the check)
+fn is_tiny_image(image_data: &[u8], min_dim: u32) -> bool {
+    if min_dim == 0 {
+        return false;
+    }
+    match image_dimensions(image_data) {
+        Some((width, height)) => width < min_dim && height < min_dim,
+        None => false,
+    }
+}
+
+/// Re-encode image data as PNG from its decoded pixels
+/// 
+/// Only the pixels are written, so EXIF, XMP and other metadata chunks of the
+/// source data are dropped
+fn strip_image_metadata(image_data: &[u8]) -> Option<Vec<u8>> {
+    use std::io::Cursor;
+    
+    let img = image::load_from_memory(image_data).ok()?;
+    let mut png_data = Vec::new();
+    img.write_to(&mut Cursor::new(&mut png_data), image::ImageFormat::Png).ok()?;
+    Some(png_data)
+}
+
 /// Generate image thumbnail
-fn generate_thumbnail(image_data: &[u8]) -> Option<String> {
+pub(crate) fn generate_thumbnail(image_data: &[u8]) -> Option<String> {
     use image::ImageReader;
     use std::io::Cursor;
     use base64::Engine;
@@ -160,11 +807,74 @@ fn generate_thumbnail(image_data: &[u8]) -> Option<String> {
     Some(format!("data:image/png;base64,{}", base64_str))
 }
 
+/// Hide the main window after the given delay
+/// 
+/// Rescheduling while a timer is pending only moves the deadline
+pub(crate) fn schedule_auto_hide(app: &AppHandle, delay_ms: u32) {
+    let deadline = Instant::now() + Duration::from_millis(delay_ms as u64);
+    if AUTO_HIDE_DEADLINE.lock().replace(deadline).is_some() {
+        return;
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        loop {
+            let remaining = {
+                let mut pending = AUTO_HIDE_DEADLINE.lock();
+                let Some(deadline) = *pending else {
+                    // Cancelled because the window was shown or hidden manually
+                    return;
+                };
+                match deadline.checked_duration_since(Instant::now()) {
+                    Some(remaining) if !remaining.is_zero() => remaining,
+                    _ => {
+                        *pending = None;
+                        break;
+                    }
+                }
+            };
+            std::thread::sleep(remaining);
+        }
+
+        if let Some(window) = app.get_webview_window("main") {
+            let _ = window.hide();
+            config::save_window_visible(false);
+            let _ = app.emit("window-auto-hidden", ());
+        }
+    });
+}
+
+/// Whether an auto-hide is pending
+pub(crate) fn is_auto_hide_pending() -> bool {
+    AUTO_HIDE_DEADLINE.lock().is_some()
+}
+
+/// Cancel a pending auto-hide
+pub(crate) fn cancel_auto_hide() {
+    AUTO_HIDE_DEADLINE.lock().take();
+}
+
+/// Whether the main window should be shown on a normal (non-first-run) startup
+///
+/// Launching with `--minimized` (used by autostart) always starts hidden
+fn should_show_on_startup() -> bool {
+    if std::env::args().any(|arg| arg == "--minimized") {
+        return false;
+    }
+    match config::get_settings().startup_window {
+        config::StartupWindow::Hidden => false,
+        config::StartupWindow::Shown => true,
+        config::StartupWindow::LastState => config::was_window_visible(),
+    }
+}
+
 /// Toggle window visibility
 fn toggle_window(app: &AppHandle) {
+    cancel_auto_hide();
     if let Some(window) = app.get_webview_window("main") {
         if window.is_visible().unwrap_or(false) {
             let _ = window.hide();
+            config::save_window_visible(false);
         } else {
             // Save current active window handle before showing window
             #[cfg(target_os = "windows")]
@@ -178,6 +888,7 @@ fn toggle_window(app: &AppHandle) {
             
             let _ = window.show();
             let _ = window.set_focus();
+            config::save_window_visible(true);
             // Emit event to notify frontend
             let _ = app.emit("window-shown", ());
             let _ = app.emit("focus-first-item", ());
@@ -244,107 +955,391 @@ fn setup_global_shortcut(app: &AppHandle) -> Result<(), Box<dyn std::error::Erro
     Ok(())
 }
 
+/// Run a clipboard write with monitoring paused
+/// 
+/// The content on the clipboard afterwards is marked as seen so the
+/// monitor doesn't capture the app's own write once it resumes
+pub(crate) fn with_monitor_paused<T>(f: impl FnOnce() -> T) -> T {
+    let was_paused = CLIPBOARD_MONITOR.lock().as_ref().map(|monitor| {
+        let paused = monitor.is_paused();
+        monitor.pause();
+        paused
+    });
+    
+    let result = f();
+    
+    if let Some(monitor) = CLIPBOARD_MONITOR.lock().as_ref() {
+        monitor.mark_current_as_seen();
+        if was_paused == Some(false) {
+            monitor.resume();
+        }
+    }
+    
+    result
+}
+
+/// Clipboard polling interval (milliseconds)
+pub(crate) const MONITOR_POLL_INTERVAL_MS: u64 = 150;
+
+/// Interval between timed pin expiry checks (milliseconds)
+const PIN_SWEEP_INTERVAL_MS: u64 = 60_000;
+
+/// Start the background thread that unpins records whose timed pin has passed
+fn start_pin_sweeper(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        match storage::expire_timed_pins() {
+            Ok(0) => {}
+            Ok(count) => {
+                log::info!("Unpinned {} records with expired timed pins", count);
+                let _ = app.emit("clipboard-updated", ());
+            }
+            // Storage unavailable (see init_storage), retried on the next sweep
+            Err(storage::DatabaseError::NotInitialized) => {}
+            Err(e) => log::warn!("Failed to expire timed pins: {}", e),
+        }
+        std::thread::sleep(Duration::from_millis(PIN_SWEEP_INTERVAL_MS));
+    });
+}
+
+/// Foreground process check interval (milliseconds)
+/// 
+/// Each check is one GetForegroundWindow plus one process name query, cheap
+/// enough to run every second; nothing is queried while the setting is empty
+const PROCESS_WATCH_INTERVAL_MS: u64 = 1000;
+
+/// Whether an executable path names the given process ("zoom" or "zoom.exe")
+fn is_process(path: &str, name: &str) -> bool {
+    let path = std::path::Path::new(path);
+    let matches = |part: Option<&std::ffi::OsStr>| {
+        part.is_some_and(|part| part.to_string_lossy().eq_ignore_ascii_case(name))
+    };
+    matches(path.file_name()) || matches(path.file_stem())
+}
+
+/// Pause the monitor while the pause_while_process process is in the foreground
+/// 
+/// Resumes once another window is focused (unless monitoring is off or
+/// private mode is on)
+fn start_process_watcher() {
+    std::thread::spawn(|| {
+        let mut paused_by_watcher = false;
+        loop {
+            std::thread::sleep(Duration::from_millis(PROCESS_WATCH_INTERVAL_MS));
+            
+            let settings = config::get_settings();
+            let watched = settings.pause_while_process.trim();
+            let active = !watched.is_empty()
+                && source_process_path().is_some_and(|path| is_process(&path, watched));
+            
+            let monitor = CLIPBOARD_MONITOR.lock();
+            let Some(monitor) = monitor.as_ref() else {
+                continue;
+            };
+            if active && !monitor.is_paused() {
+                log::info!("Process {} is in the foreground, pausing capture", watched);
+                monitor.pause();
+                paused_by_watcher = true;
+            } else if !active && paused_by_watcher {
+                paused_by_watcher = false;
+                if settings.monitoring_enabled && !is_private_mode() {
+                    log::info!("Watched process left the foreground, resuming capture");
+                    monitor.mark_current_as_seen();
+                    monitor.resume();
+                }
+            }
+        }
+    });
+}
+
 /// Start clipboard monitoring
-fn start_clipboard_monitor(app: AppHandle) {
-    let monitor = ClipboardMonitor::new(150);
+/// 
+/// With `capture_current` the content already on the clipboard is recorded
+/// right away and marked as seen, so the first poll doesn't record it again.
+/// Otherwise the first poll records it like any new content
+pub(crate) fn start_clipboard_monitor(app: AppHandle, capture_current: bool) {
+    let monitor = ClipboardMonitor::new(MONITOR_POLL_INTERVAL_MS);
+    
+    // Keep recording off if the user disabled it last time (or private mode is on)
+    let paused = !config::get_settings().monitoring_enabled || is_private_mode();
+    let current = if capture_current && !paused { monitor.capture_now() } else { None };
     
     let app_clone = app.clone();
     monitor.start(move |snapshot| {
         handle_new_clipboard_content(&app_clone, snapshot);
     });
     
+    if paused {
+        monitor.pause();
+        log::info!("Clipboard monitoring disabled, monitor paused");
+    }
+    
     *CLIPBOARD_MONITOR.lock() = Some(monitor);
     log::info!("Clipboard monitor started");
+    
+    if let Some(snapshot) = current {
+        handle_new_clipboard_content(&app, snapshot);
+    }
+}
+
+/// Private mode: recording is paused until toggled off or the timer runs out
+static PRIVATE_MODE: AtomicBool = AtomicBool::new(false);
+
+/// Bumped on every private mode change so stale timers don't end a newer session
+static PRIVATE_MODE_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Tray tooltip while private mode is active
+const PRIVATE_MODE_TOOLTIP: &str = "EveryPaste - 隐私模式（暂停记录）";
+
+/// Whether private mode is active
+pub(crate) fn is_private_mode() -> bool {
+    PRIVATE_MODE.load(Ordering::SeqCst)
+}
+
+/// Enter or leave private mode
+/// 
+/// With a duration, private mode ends automatically once it elapses. Leaving
+/// private mode only resumes the monitor if monitoring is enabled in settings
+pub(crate) fn set_private_mode(app: &AppHandle, enabled: bool, duration: Option<Duration>) {
+    let generation = PRIVATE_MODE_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    PRIVATE_MODE.store(enabled, Ordering::SeqCst);
+    
+    if let Some(monitor) = CLIPBOARD_MONITOR.lock().as_ref() {
+        if enabled {
+            monitor.pause();
+        } else if config::get_settings().monitoring_enabled {
+            // Don't record whatever was copied during private mode
+            monitor.mark_current_as_seen();
+            monitor.resume();
+        }
+    }
+    
+    let tooltip = if enabled { Some(PRIVATE_MODE_TOOLTIP) } else { None };
+    if let Err(e) = tray::set_tooltip(tooltip) {
+        log::warn!("Failed to update tray tooltip: {}", e);
+    }
+    let _ = app.emit("private-mode-changed", enabled);
+    log::info!("Private mode {}", if enabled { "enabled" } else { "disabled" });
+    
+    if let (true, Some(duration)) = (enabled, duration) {
+        let app = app.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(duration);
+            if PRIVATE_MODE_GENERATION.load(Ordering::SeqCst) == generation {
+                set_private_mode(&app, false, None);
+            }
+        });
+    }
 }
 
+/// Virtual key codes of a paste keystroke (modifier, key)
+#[cfg(target_os = "windows")]
+fn paste_key_codes(
+    keystroke: config::PasteKeystroke,
+) -> (
+    windows::Win32::UI::Input::KeyboardAndMouse::VIRTUAL_KEY,
+    windows::Win32::UI::Input::KeyboardAndMouse::VIRTUAL_KEY,
+) {
+    use windows::Win32::UI::Input::KeyboardAndMouse::{VK_CONTROL, VK_INSERT, VK_SHIFT, VK_V};
+
+    match keystroke {
+        config::PasteKeystroke::CtrlV => (VK_CONTROL, VK_V),
+        config::PasteKeystroke::ShiftInsert => (VK_SHIFT, VK_INSERT),
+    }
+}
+
+/// Build the input sequence for a paste keystroke
+/// 
+/// Modifier press, key press, key release, modifier release
+#[cfg(target_os = "windows")]
+fn paste_key_inputs(keystroke: config::PasteKeystroke) -> [windows::Win32::UI::Input::KeyboardAndMouse::INPUT; 4] {
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYBD_EVENT_FLAGS, KEYEVENTF_KEYUP, VIRTUAL_KEY,
+    };
+
+    let key_input = |vk: VIRTUAL_KEY, flags: KEYBD_EVENT_FLAGS| INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: vk,
+                wScan: 0,
+                dwFlags: flags,
+                time: 0,
+                dwExtraInfo: 0,
+            },
+        },
+    };
+
+    let (modifier, key) = paste_key_codes(keystroke);
+    [
+        key_input(modifier, Default::default()),
+        key_input(key, Default::default()),
+        key_input(key, KEYEVENTF_KEYUP),
+        key_input(modifier, KEYEVENTF_KEYUP),
+    ]
+}
+
+/// Whether a saved window handle still refers to an existing window
+#[cfg(target_os = "windows")]
+fn is_valid_window(hwnd: isize) -> bool {
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::WindowsAndMessaging::IsWindow;
+
+    hwnd != 0 && unsafe { IsWindow(HWND(hwnd as *mut _)) }.as_bool()
+}
+
+/// Remember the window of another application that took focus from EveryPaste
+#[cfg(target_os = "windows")]
+fn remember_external_window() {
+    use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId};
+
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        let mut pid = 0u32;
+        GetWindowThreadProcessId(hwnd, Some(&mut pid));
+        if pid != 0 && pid != std::process::id() {
+            *LAST_EXTERNAL_WINDOW.lock() = hwnd.0 as isize;
+        }
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn remember_external_window() {}
+
 /// Restore focus to previous window and simulate paste
 #[tauri::command]
 #[cfg(target_os = "windows")]
 async fn restore_and_paste(app: AppHandle) -> Result<(), String> {
     use std::thread;
-    use std::time::Duration;
     use windows::Win32::Foundation::HWND;
     use windows::Win32::UI::WindowsAndMessaging::SetForegroundWindow;
-    use windows::Win32::UI::Input::KeyboardAndMouse::{
-        SendInput, INPUT, INPUT_KEYBOARD, KEYBDINPUT, KEYEVENTF_KEYUP, VK_CONTROL, VK_V,
-    };
+    use windows::Win32::UI::Input::KeyboardAndMouse::{SendInput, INPUT};
 
-    // 1. Hide EveryPaste window
-    if let Some(window) = app.get_webview_window("main") {
-        let _ = window.hide();
-    }
+    let settings = config::get_settings();
+    let linger_ms = settings.post_paste_linger_ms;
 
-    // 2. Wait for window to hide
-    thread::sleep(Duration::from_millis(50));
+    // 1. Hide EveryPaste window (kept visible when lingering after the paste)
+    if linger_ms.is_none() {
+        if let Some(window) = app.get_webview_window("main") {
+            let _ = window.hide();
+            config::save_window_visible(false);
+        }
 
-    // 3. Restore focus to previous window
+        // 2. Wait for window to hide
+        thread::sleep(Duration::from_millis(settings.paste_hide_delay_ms as u64));
+    }
+
+    // 3. Restore focus to previous window (falls back to the last external window if it was closed)
     let prev_hwnd = *PREVIOUS_WINDOW.lock();
-    if prev_hwnd != 0 {
-        log::debug!("Restoring focus to window: {}", prev_hwnd);
+    let fallback_hwnd = *LAST_EXTERNAL_WINDOW.lock();
+    let target_hwnd = if is_valid_window(prev_hwnd) {
+        prev_hwnd
+    } else if is_valid_window(fallback_hwnd) {
+        log::debug!("Previous window {} is gone, using last external window", prev_hwnd);
+        fallback_hwnd
+    } else {
+        0
+    };
+    if target_hwnd != 0 {
+        log::debug!("Restoring focus to window: {}", target_hwnd);
         unsafe {
-            let hwnd = HWND(prev_hwnd as *mut _);
+            let hwnd = HWND(target_hwnd as *mut _);
             let _ = SetForegroundWindow(hwnd);
         }
+    } else {
+        log::warn!("No valid window to restore focus to");
     }
 
     // 4. Wait for focus to restore
-    thread::sleep(Duration::from_millis(100));
+    thread::sleep(Duration::from_millis(settings.paste_focus_delay_ms as u64));
 
-    // 5. Simulate Ctrl+V paste
+    // 5. Simulate the paste keystroke
+    let keystroke = settings.paste_keystroke;
+    let inputs = paste_key_inputs(keystroke);
     unsafe {
-        let mut inputs: [INPUT; 4] = std::mem::zeroed();
-
-        // Ctrl press
-        inputs[0].r#type = INPUT_KEYBOARD;
-        inputs[0].Anonymous.ki = KEYBDINPUT {
-            wVk: VK_CONTROL,
-            wScan: 0,
-            dwFlags: Default::default(),
-            time: 0,
-            dwExtraInfo: 0,
-        };
-
-        // V press
-        inputs[1].r#type = INPUT_KEYBOARD;
-        inputs[1].Anonymous.ki = KEYBDINPUT {
-            wVk: VK_V,
-            wScan: 0,
-            dwFlags: Default::default(),
-            time: 0,
-            dwExtraInfo: 0,
-        };
-
-        // V release
-        inputs[2].r#type = INPUT_KEYBOARD;
-        inputs[2].Anonymous.ki = KEYBDINPUT {
-            wVk: VK_V,
-            wScan: 0,
-            dwFlags: KEYEVENTF_KEYUP,
-            time: 0,
-            dwExtraInfo: 0,
-        };
-
-        // Ctrl release
-        inputs[3].r#type = INPUT_KEYBOARD;
-        inputs[3].Anonymous.ki = KEYBDINPUT {
-            wVk: VK_CONTROL,
-            wScan: 0,
-            dwFlags: KEYEVENTF_KEYUP,
-            time: 0,
-            dwExtraInfo: 0,
-        };
-
         let result = SendInput(&inputs, std::mem::size_of::<INPUT>() as i32);
         if result == 0 {
             log::error!("SendInput failed");
             return Err("Failed to simulate paste".to_string());
         }
-        log::debug!("Simulated Ctrl+V paste");
+        log::debug!("Simulated {:?} paste", keystroke);
+    }
+
+    // 6. Restore clipboard content saved before the paste
+    let saved = SAVED_CLIPBOARD.lock().take();
+    if let Some(saved) = saved {
+        // Wait for the target application to read the pasted content
+        thread::sleep(Duration::from_millis(settings.restore_clipboard_delay_ms as u64));
+        let result = match saved {
+            SavedClipboard::Content(snapshot) => snapshot.write_to_clipboard(),
+            SavedClipboard::Empty => {
+                let _access = clipboard::lock_clipboard();
+                arboard::Clipboard::new()
+                    .and_then(|mut clipboard| clipboard.clear())
+                    .map_err(|e| e.to_string())
+            }
+        };
+        match result {
+            Ok(()) => log::debug!("Restored previous clipboard content"),
+            Err(e) => log::warn!("Failed to restore previous clipboard content: {}", e),
+        }
+    }
+
+    // 7. Hide the lingering window once the linger time passes
+    if let Some(linger_ms) = linger_ms {
+        schedule_auto_hide(&app, linger_ms);
     }
 
     Ok(())
 }
 
+/// Truncate text to at most max_chars characters
+/// 
+/// With `ellipsis` the last kept character is replaced by "…" when the text is cut
+fn truncate_chars(text: &str, max_chars: usize, ellipsis: bool) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    if ellipsis && max_chars > 0 {
+        let mut truncated: String = text.chars().take(max_chars - 1).collect();
+        truncated.push('…');
+        truncated
+    } else {
+        text.chars().take(max_chars).collect()
+    }
+}
+
+/// Paste a text record cut to a character limit (for input fields with a length cap)
+/// 
+/// The truncated text isn't captured as a new record
+#[tauri::command]
+#[cfg(target_os = "windows")]
+async fn paste_item_truncated(app: AppHandle, id: i64, max_chars: usize, ellipsis: bool) -> Result<(), String> {
+    let item = storage::get_item_by_id(id)
+        .map_err(|e| format!("Failed to get item: {}", e))?
+        .ok_or_else(|| format!("Item not found: {}", id))?;
+
+    if item.encrypted {
+        return Err("Encrypted items must be decrypted before pasting".to_string());
+    }
+    let text = match item.content_type {
+        ContentType::Text | ContentType::RichText | ContentType::Link | ContentType::Color => item.plain_text.as_deref().unwrap_or_default(),
+        _ => return Err(format!("Item is not a text item: {}", id)),
+    };
+    let text = truncate_chars(&config::get_settings().normalize_line_endings.apply(text), max_chars, ellipsis);
+
+    // Save current clipboard content so it can be restored after the paste
+    save_clipboard_for_restore();
+
+    with_monitor_paused(|| {
+        let _access = clipboard::lock_clipboard();
+        arboard::Clipboard::new()
+            .and_then(|mut clipboard| clipboard.set_text(text))
+            .map_err(|e| format!("Failed to set clipboard text: {}", e))
+    })?;
+
+    restore_and_paste(app).await
+}
+
 /// Modify Windows clipboard policy (Win+V support)
 #[tauri::command]
 async fn set_win_v_policy(enable: bool) -> Result<(), String> {
@@ -391,6 +1386,7 @@ pub fn run() {
             if let Some(window) = app.get_webview_window("main") {
                 let _ = window.show();
                 let _ = window.set_focus();
+                config::save_window_visible(true);
                 let _ = app.emit("window-shown", ());
             }
         }))
@@ -398,38 +1394,108 @@ pub fn run() {
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         .plugin(tauri_plugin_autostart::init(
             tauri_plugin_autostart::MacosLauncher::LaunchAgent,
-            None,
+            Some(vec!["--minimized"]),
         ))
         .plugin(tauri_plugin_shell::init())
+        .on_window_event(|_window, event| {
+            if let tauri::WindowEvent::Focused(false) = event {
+                remember_external_window();
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             commands::get_clipboard_history,
+            commands::get_quick_paste_slots,
             commands::get_clipboard_item,
+            commands::get_item_full,
             commands::paste_item,
+            commands::confirm_large_paste,
+            commands::get_image_preview,
+            commands::get_source_icon,
+            commands::get_item_palette,
+            commands::recompress_images,
+            commands::item_as_qr,
             commands::delete_item,
+            commands::get_trash,
+            commands::restore_item,
+            commands::purge_trash,
             commands::clear_all_history,
+            commands::tag_items,
+            commands::swap_clipboard,
+            commands::duplicate_item,
+            commands::append_clipboard_to_item,
+            commands::update_item_text,
+            commands::image_to_text_item,
+            commands::ocr_item,
+            commands::get_profiles,
+            commands::switch_profile,
+            commands::export_filtered,
+            commands::export_csv,
+            commands::import_history,
+            commands::import_external,
+            commands::set_tray_tooltip,
+            commands::toggle_pin,
+            commands::set_pin_until,
+            commands::encrypt_item,
+            commands::decrypt_item,
+            commands::open_file_path,
             commands::search_clipboard,
+            commands::search_ranked,
+            commands::get_history_page,
+            commands::search_clipboard_page,
+            commands::search_all,
+            commands::convert_item,
+            commands::get_items_by_language,
+            commands::get_items_min_length,
+            commands::get_items_by_type,
+            commands::get_pinned_items,
+            commands::get_items_by_usage,
+            commands::get_items_in_range,
             commands::get_settings,
+            commands::get_effective_settings,
             commands::update_settings,
+            commands::export_settings,
+            commands::import_settings,
             commands::show_main_window,
             commands::hide_main_window,
+            commands::reset_auto_hide,
             commands::get_history_count,
+            commands::get_pinned_count,
+            commands::get_limit_utilization,
+            commands::preview_cleanup,
+            commands::find_orphan_images,
+            commands::delete_orphan_images,
+            commands::cleanup_orphan_images,
+            commands::get_daily_counts,
+            commands::get_usage_insights,
+            commands::get_storage_stats,
+            commands::vacuum_database,
+            commands::backup_database,
+            commands::restore_database,
+            commands::get_grouped_by_date,
+            commands::is_monitoring,
+            commands::restart_monitor,
+            commands::set_monitoring,
+            commands::enable_private_mode,
+            commands::disable_private_mode,
+            commands::is_private_mode,
+            commands::get_storage_error,
+            commands::retry_storage_init,
+            commands::unlock_database,
+            commands::is_database_locked,
+            commands::is_database_encrypted,
+            commands::set_encryption_password,
+            commands::get_current_clipboard_formats,
             commands::is_first_run,
             commands::complete_first_run,
             set_win_v_policy,
             restore_and_paste, // Restore focus and simulate paste
+            paste_item_truncated,
         ])
         .setup(|app| {
             let app_handle = app.handle().clone();
             
-            // Initialize data directory
-            let data_dir = get_data_dir(&app_handle);
-            log::info!("Data directory: {:?}", data_dir);
-            
-            // Initialize database
-            if let Err(e) = init_database(&data_dir) {
-                log::error!("Failed to initialize database: {}", e);
-                return Err(e.into());
-            }
+            // Initialize database (failure leaves the app running without capture)
+            let storage_ready = init_storage(&app_handle, None).is_ok();
             
             // Initialize settings
             init_settings();
@@ -444,21 +1510,268 @@ pub fn run() {
                 log::error!("Failed to register global shortcut: {}", e);
             }
             
-            // Start clipboard monitoring
-            start_clipboard_monitor(app_handle.clone());
+            // Unpin records whose timed pin has passed (also runs once right away)
+            start_pin_sweeper(app_handle.clone());
+            
+            // Pause capture while the configured process is in the foreground
+            start_process_watcher();
+            
+            if storage_ready {
+                // Start clipboard monitoring (recording the content that was on the clipboard before launch)
+                start_clipboard_monitor(app_handle.clone(), config::get_settings().capture_on_startup);
+            } else if let Some(window) = app_handle.get_webview_window("main") {
+                // Show the window so the storage error is visible
+                let _ = window.show();
+                let _ = window.set_focus();
+            }
             
             // Show main window on first run (display welcome page)
-            if config::is_first_run() {
+            if storage_ready && config::is_first_run() {
                 if let Some(window) = app_handle.get_webview_window("main") {
                     let _ = window.show();
                     let _ = window.set_focus();
                     log::info!("First run detected, showing welcome window");
                 }
+            } else if storage_ready && should_show_on_startup() {
+                if let Some(window) = app_handle.get_webview_window("main") {
+                    let _ = window.show();
+                    let _ = window.set_focus();
+                    config::save_window_visible(true);
+                }
             }
             
             log::info!("EveryPaste initialized successfully");
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|_app, event| {
+            if let tauri::RunEvent::Exit = event {
+                // Write any debounced settings changes before exiting
+                if let Err(e) = config::flush_settings() {
+                    log::error!("Failed to flush settings on exit: {}", e);
+                }
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use storage::database::testing::*;
+    
+    fn text_snapshot(content_type: ContentType, text: &str, html: Option<&str>) -> ClipboardSnapshot {
+        ClipboardSnapshot {
+            content_type,
+            plain_text: Some(text.to_string()),
+            rich_text: html.map(str::to_string),
+            rtf: None,
+            image_data: None,
+            hash: ClipboardMonitor::compute_hash(format!("{}{:?}", text, html).as_bytes()),
+        }
+    }
+    
+    #[test]
+    fn plain_text_matching_rich_text_record_is_skipped() {
+        let _db = test_db();
+        let rich = ClipboardItem::new_rich_text(0, "hello".to_string(), "<b>hello</b>".to_string(), "rich".to_string());
+        let id = storage::insert_clipboard_item(&rich).unwrap();
+        
+        let snapshot = text_snapshot(ContentType::Text, "hello", None);
+        assert_eq!(merge_text_snapshot(&snapshot), Some(TextMerge::Skipped(id)));
+        
+        let item = storage::get_item_by_id(id).unwrap().unwrap();
+        assert_eq!(item.content_type, ContentType::RichText);
+        assert_eq!(storage::get_item_count().unwrap(), 1);
+    }
+    
+    #[test]
+    fn rich_text_matching_plain_text_record_upgrades_it() {
+        let _db = test_db();
+        let id = storage::insert_clipboard_item(&text_item("hello")).unwrap();
+        
+        let snapshot = text_snapshot(ContentType::RichText, "hello", Some("<b>hello</b>"));
+        assert_eq!(merge_text_snapshot(&snapshot), Some(TextMerge::Upgraded(id)));
+        
+        let item = storage::get_item_by_id(id).unwrap().unwrap();
+        assert_eq!(item.content_type, ContentType::RichText);
+        assert_eq!(item.rich_text.as_deref(), Some("<b>hello</b>"));
+        assert_eq!(item.hash, snapshot.hash);
+        assert_eq!(storage::get_item_count().unwrap(), 1);
+    }
+    
+    #[test]
+    fn text_without_matching_record_is_not_merged() {
+        let _db = test_db();
+        storage::insert_clipboard_item(&text_item("hello")).unwrap();
+        let rich = ClipboardItem::new_rich_text(0, "rich".to_string(), "<b>rich</b>".to_string(), "rich".to_string());
+        storage::insert_clipboard_item(&rich).unwrap();
+        
+        // Same visible text but same type, or different text
+        assert_eq!(merge_text_snapshot(&text_snapshot(ContentType::Text, "hello", None)), None);
+        assert_eq!(merge_text_snapshot(&text_snapshot(ContentType::Text, "other", None)), None);
+        assert_eq!(merge_text_snapshot(&text_snapshot(ContentType::RichText, "other", Some("<i>other</i>"))), None);
+    }
+    
+    fn history_ids() -> Vec<i64> {
+        storage::get_all_items(None, None).unwrap().iter().map(|item| item.id).collect()
+    }
+    
+    #[test]
+    fn duplicate_in_skip_mode_stays_in_place() {
+        let _db = test_db();
+        let old = insert_aged(text_item("old"), 3);
+        let new = insert_aged(text_item("new"), 1);
+        let created_at = storage::get_item_by_id(old).unwrap().unwrap().created_at;
+        
+        assert!(!resurface_duplicate(old, config::DuplicateAction::Skip));
+        assert_eq!(history_ids(), vec![new, old]);
+        assert_eq!(storage::get_item_by_id(old).unwrap().unwrap().created_at, created_at);
+    }
+    
+    #[test]
+    fn duplicate_in_bump_mode_moves_to_the_top() {
+        let _db = test_db();
+        let old = insert_aged(text_item("old"), 3);
+        let new = insert_aged(text_item("new"), 1);
+        
+        assert!(resurface_duplicate(old, config::DuplicateAction::Bump));
+        assert_eq!(history_ids(), vec![old, new]);
+    }
+    
+    #[test]
+    fn duplicate_in_the_trash_is_restored() {
+        let _db = test_db();
+        let trashed = insert_aged(text_item("trashed"), 3);
+        storage::delete_item(trashed).unwrap();
+        assert!(history_ids().is_empty());
+        
+        assert!(!resurface_duplicate(trashed, config::DuplicateAction::Skip));
+        assert_eq!(history_ids(), vec![trashed]);
+    }
+    
+    fn patterns(list: &[&str]) -> Vec<String> {
+        list.iter().map(|p| p.to_string()).collect()
+    }
+    
+    #[test]
+    fn capture_matching_auto_pin_pattern_is_pinned() {
+        let list = patterns(&[r"^TICKET-\d+$", r"(?i)password"]);
+        assert!(matches_any_pattern(list.clone(), "TICKET-4521"));
+        assert!(matches_any_pattern(list, "my PassWord is"));
+    }
+    
+    #[test]
+    fn capture_not_matching_auto_pin_pattern_is_not_pinned() {
+        let list = patterns(&[r"^TICKET-\d+$"]);
+        assert!(!matches_any_pattern(list.clone(), "see TICKET-4521"));
+        assert!(!matches_any_pattern(list, "TICKET-abc"));
+        assert!(!matches_any_pattern(Vec::new(), "anything"));
+    }
+    
+    #[test]
+    fn invalid_auto_pin_pattern_is_skipped() {
+        let list = patterns(&["(unclosed", "^ok$"]);
+        assert!(matches_any_pattern(list.clone(), "ok"));
+        assert!(!matches_any_pattern(list, "(unclosed"));
+    }
+    
+    /// Encode a blank image of the given size as PNG
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut data = Vec::new();
+        image::RgbaImage::new(width, height)
+            .write_to(&mut std::io::Cursor::new(&mut data), image::ImageFormat::Png)
+            .unwrap();
+        data
+    }
+    
+    /// JPEG with an EXIF segment (APP1) holding a recognizable marker
+    fn jpeg_with_exif(marker: &[u8]) -> Vec<u8> {
+        let mut jpeg = Vec::new();
+        image::DynamicImage::ImageRgb8(image::RgbImage::new(8, 8))
+            .write_to(&mut std::io::Cursor::new(&mut jpeg), image::ImageFormat::Jpeg)
+            .unwrap();
+        
+        // "Exif\0\0", big-endian TIFF header, an empty IFD, then the marker as padding
+        let mut exif = b"Exif\0\0MM\0\x2a\0\0\0\x08\0\0\0\0\0\0".to_vec();
+        exif.extend_from_slice(marker);
+        let mut segment = vec![0xFF, 0xE1];
+        segment.extend_from_slice(&((exif.len() + 2) as u16).to_be_bytes());
+        segment.extend_from_slice(&exif);
+        
+        // Right after the SOI marker
+        jpeg.splice(2..2, segment);
+        jpeg
+    }
+    
+    fn contains(data: &[u8], needle: &[u8]) -> bool {
+        data.windows(needle.len()).any(|window| window == needle)
+    }
+    
+    #[test]
+    fn strip_image_metadata_drops_jpeg_exif() {
+        let marker = b"GPS 52.5200N 13.4050E";
+        let jpeg = jpeg_with_exif(marker);
+        assert!(contains(&jpeg, b"Exif") && contains(&jpeg, marker));
+        
+        let stripped = strip_image_metadata(&jpeg).unwrap();
+        assert!(stripped.starts_with(b"\x89PNG"));
+        assert!(!contains(&stripped, b"Exif"));
+        assert!(!contains(&stripped, b"eXIf"));
+        assert!(!contains(&stripped, marker));
+        assert_eq!(image_dimensions(&stripped), Some((8, 8)));
+    }
+    
+    #[test]
+    fn tiny_image_is_rejected() {
+        assert!(is_tiny_image(&png(10, 10), 16));
+    }
+    
+    #[test]
+    fn image_large_in_one_dimension_is_kept() {
+        assert!(!is_tiny_image(&png(10, 200), 16));
+        assert!(!is_tiny_image(&png(16, 16), 16));
+    }
+    
+    #[test]
+    fn zero_min_dimension_keeps_every_image() {
+        assert!(!is_tiny_image(&png(1, 1), 0));
+    }
+    
+    /// (virtual key, key up) of each input
+    #[cfg(target_os = "windows")]
+    fn key_sequence(keystroke: config::PasteKeystroke) -> Vec<(u16, bool)> {
+        use windows::Win32::UI::Input::KeyboardAndMouse::{INPUT_KEYBOARD, KEYEVENTF_KEYUP};
+        
+        paste_key_inputs(keystroke)
+            .iter()
+            .map(|input| {
+                assert_eq!(input.r#type, INPUT_KEYBOARD);
+                let ki = unsafe { input.Anonymous.ki };
+                (ki.wVk.0, ki.dwFlags.contains(KEYEVENTF_KEYUP))
+            })
+            .collect()
+    }
+    
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn ctrl_v_paste_key_sequence() {
+        use windows::Win32::UI::Input::KeyboardAndMouse::{VK_CONTROL, VK_V};
+        
+        assert_eq!(
+            key_sequence(config::PasteKeystroke::CtrlV),
+            vec![(VK_CONTROL.0, false), (VK_V.0, false), (VK_V.0, true), (VK_CONTROL.0, true)]
+        );
+    }
+    
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn shift_insert_paste_key_sequence() {
+        use windows::Win32::UI::Input::KeyboardAndMouse::{VK_INSERT, VK_SHIFT};
+        
+        assert_eq!(
+            key_sequence(config::PasteKeystroke::ShiftInsert),
+            vec![(VK_SHIFT.0, false), (VK_INSERT.0, false), (VK_INSERT.0, true), (VK_SHIFT.0, true)]
+        );
+    }
 }
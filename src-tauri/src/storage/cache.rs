@@ -0,0 +1,69 @@
+//! EveryPaste - Recent records cache
+//! 
+//! Keeps the newest records of the active profile in memory so the history
+//! list can be served without a database query. Any write to the record
+//! tables invalidates the cache (see the update hook in init_database)
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+use super::database::{active_profile, get_all_items, DatabaseError};
+use crate::clipboard::ClipboardItemView;
+
+/// Number of records kept in memory
+pub const RECENT_CACHE_SIZE: usize = 200;
+
+/// Cached records with the state they were loaded in
+struct RecentCache {
+    profile: String,
+    generation: u64,
+    items: VecDeque<ClipboardItemView>,
+}
+
+static CACHE: Lazy<Mutex<Option<RecentCache>>> = Lazy::new(|| Mutex::new(None));
+
+/// Bumped on every write to the record tables
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Mark the cached records as stale
+pub(crate) fn invalidate_recent_cache() {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Reload the cache from the database
+pub fn refresh_recent_cache() -> Result<(), DatabaseError> {
+    // Read the generation first, a write during the query leaves the cache stale
+    let generation = GENERATION.load(Ordering::SeqCst);
+    let profile = active_profile();
+    let items = get_all_items(Some(RECENT_CACHE_SIZE as i32), None)?
+        .into_iter()
+        .map(ClipboardItemView::from)
+        .collect();
+    
+    *CACHE.lock() = Some(RecentCache { profile, generation, items });
+    Ok(())
+}
+
+/// Newest records from the cache, None if the cache can't answer the request
+/// 
+/// `None` as limit means the whole history, which only fits when the
+/// history is smaller than the cache
+pub fn get_recent_items(limit: Option<usize>) -> Option<Vec<ClipboardItemView>> {
+    let cache = CACHE.lock();
+    let cache = cache.as_ref()?;
+    if cache.generation != GENERATION.load(Ordering::SeqCst) || cache.profile != active_profile() {
+        return None;
+    }
+    
+    // A cache with fewer records than its capacity holds the whole history
+    let complete = cache.items.len() < RECENT_CACHE_SIZE;
+    let count = match limit {
+        Some(n) if n <= cache.items.len() || complete => n.min(cache.items.len()),
+        None if complete => cache.items.len(),
+        _ => return None,
+    };
+    Some(cache.items.iter().take(count).cloned().collect())
+}
@@ -0,0 +1,69 @@
+//! EveryPaste - Record encryption module
+//!
+//! Encrypts individual records with a key derived from a passphrase
+//! (Argon2id key derivation, AES-256-GCM)
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use argon2::Argon2;
+
+/// Salt length (bytes)
+const SALT_LEN: usize = 16;
+/// AES-GCM nonce length (bytes)
+const NONCE_LEN: usize = 12;
+
+/// Encryption error type
+#[derive(Debug, thiserror::Error)]
+pub enum CryptoError {
+    #[error("Key derivation failed: {0}")]
+    KeyDerivation(String),
+    #[error("Encryption failed")]
+    Encryption,
+    #[error("Wrong passphrase or corrupted data")]
+    Decryption,
+}
+
+/// Derive a 256-bit key from the passphrase and salt
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Aes256Gcm, CryptoError> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| CryptoError::KeyDerivation(e.to_string()))?;
+    Ok(Aes256Gcm::new(&key.into()))
+}
+
+/// Encrypt data with a passphrase
+/// 
+/// Output layout: salt || nonce || ciphertext
+pub fn encrypt_bytes(data: &[u8], passphrase: &str) -> Result<Vec<u8>, CryptoError> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+
+    let cipher = derive_key(passphrase, &salt)?;
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), data)
+        .map_err(|_| CryptoError::Encryption)?;
+
+    let mut output = Vec::with_capacity(SALT_LEN + NONCE_LEN + ciphertext.len());
+    output.extend_from_slice(&salt);
+    output.extend_from_slice(&nonce);
+    output.extend_from_slice(&ciphertext);
+    Ok(output)
+}
+
+/// Decrypt data produced by encrypt_bytes
+pub fn decrypt_bytes(blob: &[u8], passphrase: &str) -> Result<Vec<u8>, CryptoError> {
+    if blob.len() < SALT_LEN + NONCE_LEN {
+        return Err(CryptoError::Decryption);
+    }
+    let (salt, rest) = blob.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+
+    let cipher = derive_key(passphrase, salt)?;
+    cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| CryptoError::Decryption)
+}
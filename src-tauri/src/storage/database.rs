@@ -2,19 +2,40 @@
 //! 
 //! Uses SQLite to store clipboard history records
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
 
-use rusqlite::{Connection, params, OptionalExtension};
-use chrono::{DateTime, Utc};
-use parking_lot::Mutex;
+use rusqlite::{backup::Backup, hooks::Action, Connection, params, OptionalExtension};
+use chrono::{DateTime, Local, Timelike, Utc};
+use serde::Serialize;
+use parking_lot::{Mutex, RwLock};
 use once_cell::sync::Lazy;
 
-use crate::clipboard::{ClipboardItem, ContentType};
+use crate::clipboard::{ClipboardItem, ClipboardMonitor, ContentType};
+use super::cache::invalidate_recent_cache;
 
 /// Global database connection
 static DB: Lazy<Mutex<Option<Connection>>> = Lazy::new(|| Mutex::new(None));
 
+/// Profile used when none was selected (records from before profiles existed belong to it)
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Profile whose records are listed and receive new captures
+static ACTIVE_PROFILE: Lazy<RwLock<String>> = Lazy::new(|| RwLock::new(DEFAULT_PROFILE.to_string()));
+
+/// Whether the open database is encrypted with a password (SQLCipher)
+static DB_ENCRYPTED: AtomicBool = AtomicBool::new(false);
+
+/// Whether the full-text index (FTS5) could be created in the linked SQLite
+static FTS_AVAILABLE: AtomicBool = AtomicBool::new(false);
+
+/// Shortest query the trigram full-text index can match (shorter queries use LIKE)
+const FTS_MIN_QUERY_CHARS: usize = 3;
+
+/// Most records a regex search tests before giving up on finding more matches
+const REGEX_SCAN_LIMIT: i32 = 5000;
+
 /// Database error type
 #[derive(Debug, thiserror::Error)]
 pub enum DatabaseError {
@@ -24,19 +45,56 @@ pub enum DatabaseError {
     Sqlite(#[from] rusqlite::Error),
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
+    #[error("Database is encrypted, a password is required")]
+    Locked,
+    #[error("Wrong database password")]
+    WrongPassword,
+    #[error("Not an EveryPaste database: {0}")]
+    InvalidDatabase(String),
 }
 
 /// Initialize database
 /// 
-/// Called at application startup, creates database file and table structure
-pub fn init_database(data_dir: &PathBuf) -> Result<(), DatabaseError> {
+/// Called at application startup, creates database file and table structure.
+/// An encrypted database needs its `password`, without it Locked is returned
+pub fn init_database(data_dir: &PathBuf, password: Option<&str>) -> Result<(), DatabaseError> {
     // Ensure data directory exists
     fs::create_dir_all(data_dir)?;
     
     let db_path = data_dir.join("data.db");
     log::info!("Initializing database at: {:?}", db_path);
     
-    let conn = Connection::open(&db_path)?;
+    let conn = open_connection(&db_path, password)?;
+    DB_ENCRYPTED.store(password.is_some(), Ordering::SeqCst);
+    
+    create_schema(&conn, data_dir)?;
+    
+    // Drop cached records on every write to the record tables
+    conn.update_hook(Some(|_: Action, _: &str, table: &str, _: i64| {
+        if table != "settings" {
+            invalidate_recent_cache();
+        }
+    }));
+    invalidate_recent_cache();
+    
+    // Store connection
+    let mut db = DB.lock();
+    *db = Some(conn);
+    
+    log::info!("Database initialized successfully");
+    Ok(())
+}
+
+/// Create the tables and indexes and run the migrations on an open database
+fn create_schema(conn: &Connection, data_dir: &Path) -> Result<(), DatabaseError> {
+    // Enable foreign keys so tag associations are removed with their records
+    conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+    
+    // Write-ahead logging: readers aren't blocked by writes and commits are cheaper.
+    // SQLite keeps data.db-wal and data.db-shm next to the database while it is open,
+    // file copies of the database must include them (backup_database reads through
+    // the connection instead)
+    conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA synchronous = NORMAL;")?;
     
     // Create table structure
     conn.execute_batch(
@@ -49,11 +107,24 @@ pub fn init_database(data_dir: &PathBuf) -> Result<(), DatabaseError> {
             rich_text TEXT,
             image_path TEXT,
             preview TEXT NOT NULL,
-            hash TEXT NOT NULL UNIQUE,
+            hash TEXT NOT NULL,
             created_at TEXT NOT NULL,
             is_pinned INTEGER DEFAULT 0
         );
 
+        -- Tags table
+        CREATE TABLE IF NOT EXISTS tags (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE
+        );
+
+        -- Record-tag associations
+        CREATE TABLE IF NOT EXISTS item_tags (
+            item_id INTEGER NOT NULL REFERENCES clipboard_history(id) ON DELETE CASCADE,
+            tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+            PRIMARY KEY (item_id, tag_id)
+        );
+
         -- User settings table
         CREATE TABLE IF NOT EXISTS settings (
             key TEXT PRIMARY KEY,
@@ -71,11 +142,308 @@ pub fn init_database(data_dir: &PathBuf) -> Result<(), DatabaseError> {
     // Ignore error (if column already exists)
     let _ = conn.execute("ALTER TABLE clipboard_history ADD COLUMN image_thumbnail TEXT", []);
     
-    // Store connection
-    let mut db = DB.lock();
-    *db = Some(conn);
+    // Database migration: add palette column (JSON array of hex colors, computed lazily)
+    let _ = conn.execute("ALTER TABLE clipboard_history ADD COLUMN palette TEXT", []);
     
-    log::info!("Database initialized successfully");
+    // Database migration: add ocr_text column (text recognized in images, computed on request)
+    let _ = conn.execute("ALTER TABLE clipboard_history ADD COLUMN ocr_text TEXT", []);
+    
+    // Database migration: add source_app column (process that produced the content)
+    let _ = conn.execute("ALTER TABLE clipboard_history ADD COLUMN source_app TEXT", []);
+    
+    // Database migration: add source_icon column (cached icon of the source application)
+    let _ = conn.execute("ALTER TABLE clipboard_history ADD COLUMN source_icon TEXT", []);
+    
+    // Database migration: add lang column (detected language code)
+    let _ = conn.execute("ALTER TABLE clipboard_history ADD COLUMN lang TEXT", []);
+    
+    // Database migration: add measurement columns (value and unit of "10 km"-style text)
+    let _ = conn.execute("ALTER TABLE clipboard_history ADD COLUMN measure_value REAL", []);
+    let _ = conn.execute("ALTER TABLE clipboard_history ADD COLUMN measure_unit TEXT", []);
+    
+    // Database migration: add image_key column (canonical image deduplication key)
+    let _ = conn.execute("ALTER TABLE clipboard_history ADD COLUMN image_key TEXT", []);
+    
+    // Database migration: add updated_at column (existing records were never modified)
+    if conn.execute("ALTER TABLE clipboard_history ADD COLUMN updated_at TEXT", []).is_ok() {
+        conn.execute("UPDATE clipboard_history SET updated_at = created_at", [])?;
+    }
+    
+    // Database migration: add encryption columns (flag and encrypted content)
+    let _ = conn.execute("ALTER TABLE clipboard_history ADD COLUMN encrypted INTEGER DEFAULT 0", []);
+    let _ = conn.execute("ALTER TABLE clipboard_history ADD COLUMN ciphertext TEXT", []);
+    
+    // Database migration: add rtf column (RTF provided alongside the text)
+    let _ = conn.execute("ALTER TABLE clipboard_history ADD COLUMN rtf TEXT", []);
+    
+    // Database migration: add image size columns (dimensions and file size)
+    let _ = conn.execute("ALTER TABLE clipboard_history ADD COLUMN width INTEGER", []);
+    let _ = conn.execute("ALTER TABLE clipboard_history ADD COLUMN height INTEGER", []);
+    let _ = conn.execute("ALTER TABLE clipboard_history ADD COLUMN byte_size INTEGER", []);
+    
+    // Database migration: add pin_until column (end of a timed pin)
+    let _ = conn.execute("ALTER TABLE clipboard_history ADD COLUMN pin_until TEXT", []);
+    
+    // Database migration: add use_count column (number of pastes, used for search ranking)
+    let _ = conn.execute("ALTER TABLE clipboard_history ADD COLUMN use_count INTEGER DEFAULT 0", []);
+    
+    // Database migration: add last_pasted_at column (time of the latest paste)
+    let _ = conn.execute("ALTER TABLE clipboard_history ADD COLUMN last_pasted_at TEXT", []);
+    
+    // Database migration: add profile column (separate history sets)
+    let _ = conn.execute(
+        &format!("ALTER TABLE clipboard_history ADD COLUMN profile TEXT NOT NULL DEFAULT '{}'", DEFAULT_PROFILE),
+        [],
+    );
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_profile ON clipboard_history(profile)", [])?;
+    
+    // Content is deduplicated within a profile, the same content may be in several profiles
+    conn.execute_batch(
+        r#"
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_profile_hash ON clipboard_history(profile, hash);
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_profile_image_key ON clipboard_history(profile, image_key) WHERE image_key IS NOT NULL;
+        "#
+    )?;
+    
+    // Database migration: add deleted_at column (records in the trash)
+    let _ = conn.execute("ALTER TABLE clipboard_history ADD COLUMN deleted_at TEXT", []);
+    
+    // Compute image keys for records saved before the column existed (runs once)
+    let user_version: i32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if user_version < 1 {
+        backfill_image_keys(conn, data_dir)?;
+        conn.execute_batch("PRAGMA user_version = 1;")?;
+    }
+    
+    // Drop the global hash and image key uniqueness of databases from before profiles were isolated (runs once)
+    if user_version < 2 {
+        drop_global_hash_unique(conn)?;
+        conn.execute_batch("DROP INDEX IF EXISTS idx_image_key; PRAGMA user_version = 2;")?;
+    }
+    
+    // Full-text index over the searchable text, kept in sync by triggers
+    match create_fts_index(conn) {
+        Ok(()) => FTS_AVAILABLE.store(true, Ordering::SeqCst),
+        Err(e) => log::warn!("Full-text search unavailable, falling back to LIKE: {}", e),
+    }
+    
+    Ok(())
+}
+
+/// Rebuild clipboard_history without the UNIQUE constraint on its hash column
+/// 
+/// SQLite can't drop a column constraint, so the table is copied into a new
+/// table (same columns, indexes and triggers) that replaces it. Foreign keys are
+/// off meanwhile so dropping the old table doesn't delete the tag associations
+fn drop_global_hash_unique(conn: &Connection) -> Result<(), DatabaseError> {
+    let table_sql: String = conn.query_row(
+        "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'clipboard_history'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !table_sql.contains("hash TEXT NOT NULL UNIQUE") {
+        return Ok(());
+    }
+    
+    let mut stmt = conn.prepare(
+        "SELECT sql FROM sqlite_master WHERE type IN ('index', 'trigger') AND tbl_name = 'clipboard_history' AND sql IS NOT NULL"
+    )?;
+    let dependents: Vec<String> = stmt.query_map([], |row| row.get(0))?.filter_map(|r| r.ok()).collect();
+    // Keep AUTOINCREMENT from reusing the ids of deleted records
+    let sequence: Option<i64> = conn
+        .query_row("SELECT seq FROM sqlite_sequence WHERE name = 'clipboard_history'", [], |row| row.get(0))
+        .optional()?;
+    
+    let new_table_sql = table_sql
+        .replacen("hash TEXT NOT NULL UNIQUE", "hash TEXT NOT NULL", 1)
+        .replacen("clipboard_history", "clipboard_history_new", 1);
+    
+    conn.execute_batch("PRAGMA foreign_keys = OFF;")?;
+    let rebuilt = (|| -> Result<(), DatabaseError> {
+        let tx = conn.unchecked_transaction()?;
+        tx.execute_batch(&new_table_sql)?;
+        tx.execute_batch(
+            r#"
+            INSERT INTO clipboard_history_new SELECT * FROM clipboard_history;
+            DROP TABLE clipboard_history;
+            ALTER TABLE clipboard_history_new RENAME TO clipboard_history;
+            "#
+        )?;
+        for sql in &dependents {
+            tx.execute_batch(sql)?;
+        }
+        if let Some(sequence) = sequence {
+            let updated = tx.execute("UPDATE sqlite_sequence SET seq = MAX(seq, ?1) WHERE name = 'clipboard_history'", [sequence])?;
+            if updated == 0 {
+                tx.execute("INSERT INTO sqlite_sequence (name, seq) VALUES ('clipboard_history', ?1)", [sequence])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    })();
+    conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+    rebuilt?;
+    
+    log::info!("Rebuilt clipboard_history with per-profile content deduplication");
+    Ok(())
+}
+
+/// Open the database file, applying the SQLCipher key when a password is given
+/// 
+/// The key is only checked on the first read, a file that can't be read is
+/// reported as Locked (no password) or WrongPassword
+fn open_connection(db_path: &Path, password: Option<&str>) -> Result<Connection, DatabaseError> {
+    let conn = Connection::open(db_path)?;
+    if let Some(password) = password {
+        conn.pragma_update(None, "key", password)?;
+    }
+    
+    match conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| row.get::<_, i64>(0)) {
+        Ok(_) => Ok(conn),
+        Err(rusqlite::Error::SqliteFailure(e, _)) if e.code == rusqlite::ErrorCode::NotADatabase => {
+            Err(if password.is_some() { DatabaseError::WrongPassword } else { DatabaseError::Locked })
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Whether the open database is encrypted with a password
+pub fn is_database_encrypted() -> bool {
+    DB_ENCRYPTED.load(Ordering::SeqCst)
+}
+
+/// Encrypt the database with a password, or change its password
+/// 
+/// An encrypted database needs its current password in `old`. A plain
+/// database is exported into an encrypted copy that replaces the file
+/// (SQLCipher can't rekey a plain database in place), then reopened
+pub fn set_encryption_password(data_dir: &PathBuf, old: Option<&str>, new: &str) -> Result<(), DatabaseError> {
+    let db_path = data_dir.join("data.db");
+    
+    if is_database_encrypted() {
+        // Check the old password on a separate connection
+        open_connection(&db_path, Some(old.ok_or(DatabaseError::WrongPassword)?))?;
+        let db = DB.lock();
+        let conn = db.as_ref().ok_or(DatabaseError::NotInitialized)?;
+        conn.pragma_update(None, "rekey", new)?;
+        log::info!("Database password changed");
+        return Ok(());
+    }
+    
+    let encrypted_path = data_dir.join("data.db.encrypting");
+    let _ = fs::remove_file(&encrypted_path);
+    {
+        let mut db = DB.lock();
+        let conn = db.as_ref().ok_or(DatabaseError::NotInitialized)?;
+        conn.execute(
+            "ATTACH DATABASE ?1 AS encrypted KEY ?2",
+            params![encrypted_path.to_string_lossy(), new],
+        )?;
+        let exported = conn
+            .query_row("SELECT sqlcipher_export('encrypted')", [], |_| Ok(()))
+            .and_then(|_| {
+                // sqlcipher_export doesn't copy the schema version used by the migrations
+                let user_version: i32 = conn.query_row("PRAGMA main.user_version", [], |row| row.get(0))?;
+                conn.execute_batch(&format!("PRAGMA encrypted.user_version = {};", user_version))
+            });
+        conn.execute("DETACH DATABASE encrypted", [])?;
+        if let Err(e) = exported {
+            let _ = fs::remove_file(&encrypted_path);
+            return Err(e.into());
+        }
+        
+        // Close the plain database so its file can be replaced
+        db.take();
+    }
+    
+    if let Err(e) = fs::rename(&encrypted_path, &db_path) {
+        log::error!("Failed to replace database with encrypted copy: {}", e);
+        init_database(data_dir, None)?;
+        return Err(e.into());
+    }
+    
+    log::info!("Database encrypted");
+    init_database(data_dir, Some(new))
+}
+
+/// Create the FTS5 index and its sync triggers, filling it from existing records on first creation
+/// 
+/// Uses the trigram tokenizer so matches are substrings like the LIKE search
+/// (and work for text without spaces between words)
+fn create_fts_index(conn: &Connection) -> Result<(), DatabaseError> {
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'clipboard_fts')",
+        [],
+        |row| row.get(0),
+    )?;
+
+    conn.execute_batch(
+        r#"
+        CREATE VIRTUAL TABLE IF NOT EXISTS clipboard_fts USING fts5(
+            plain_text, preview, ocr_text,
+            content = 'clipboard_history', content_rowid = 'id', tokenize = 'trigram'
+        );
+
+        CREATE TRIGGER IF NOT EXISTS clipboard_fts_insert AFTER INSERT ON clipboard_history BEGIN
+            INSERT INTO clipboard_fts(rowid, plain_text, preview, ocr_text)
+            VALUES (new.id, new.plain_text, new.preview, new.ocr_text);
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS clipboard_fts_delete AFTER DELETE ON clipboard_history BEGIN
+            INSERT INTO clipboard_fts(clipboard_fts, rowid, plain_text, preview, ocr_text)
+            VALUES ('delete', old.id, old.plain_text, old.preview, old.ocr_text);
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS clipboard_fts_update AFTER UPDATE OF plain_text, preview, ocr_text ON clipboard_history BEGIN
+            INSERT INTO clipboard_fts(clipboard_fts, rowid, plain_text, preview, ocr_text)
+            VALUES ('delete', old.id, old.plain_text, old.preview, old.ocr_text);
+            INSERT INTO clipboard_fts(rowid, plain_text, preview, ocr_text)
+            VALUES (new.id, new.plain_text, new.preview, new.ocr_text);
+        END;
+        "#
+    )?;
+
+    // Database migration: index records saved before the full-text index existed
+    if !exists {
+        conn.execute("INSERT INTO clipboard_fts(clipboard_fts) VALUES ('rebuild')", [])?;
+        log::info!("Built full-text index for existing records");
+    }
+    Ok(())
+}
+
+/// Compute image keys for existing image records
+/// 
+/// Records whose image duplicates an earlier record keep a NULL key
+fn backfill_image_keys(conn: &Connection, data_dir: &Path) -> Result<(), DatabaseError> {
+    let mut stmt = conn.prepare(
+        "SELECT id, image_path FROM clipboard_history WHERE content_type = 'image' AND image_key IS NULL ORDER BY created_at DESC"
+    )?;
+    let rows: Vec<(i64, String)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    let mut updated = 0;
+    for (id, image_path) in rows {
+        let key = match fs::read(data_dir.join(&image_path)) {
+            Ok(data) => ClipboardMonitor::compute_image_key(&data),
+            Err(e) => {
+                log::warn!("Failed to read image {} for key migration: {}", image_path, e);
+                continue;
+            }
+        };
+        let Some(key) = key else { continue };
+
+        match conn.execute("UPDATE clipboard_history SET image_key = ?1 WHERE id = ?2", params![key, id]) {
+            Ok(_) => updated += 1,
+            Err(rusqlite::Error::SqliteFailure(e, _)) if e.code == rusqlite::ErrorCode::ConstraintViolation => {
+                log::info!("Image record {} duplicates a newer record, leaving key unset", id);
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    log::info!("Computed image keys for {} records", updated);
     Ok(())
 }
 
@@ -88,14 +456,88 @@ macro_rules! with_db {
     }};
 }
 
-/// Insert new clipboard record
+/// Build a LIKE pattern matching the query anywhere (used with `ESCAPE '\'`)
+/// 
+/// `%`, `_` and `\` in the query match literally
+fn like_pattern(query: &str) -> String {
+    let escaped = query.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+/// Columns selected for a full clipboard record (in row_to_item order)
+const ITEM_COLUMNS: &str = "id, content_type, plain_text, rich_text, image_path, preview, hash, created_at, is_pinned, image_thumbnail, source_app, lang, measure_value, measure_unit, image_key, updated_at, encrypted, source_icon, pin_until, rtf, width, height, byte_size";
+
+/// Map a row selected with ITEM_COLUMNS to a clipboard record
+fn row_to_item(row: &rusqlite::Row) -> rusqlite::Result<ClipboardItem> {
+    let created_at = DateTime::parse_from_rfc3339(&row.get::<_, String>(7)?)
+        .map(|dt| dt.with_timezone(&Utc))
+        .unwrap_or_else(|_| Utc::now());
+    Ok(ClipboardItem {
+        id: row.get(0)?,
+        content_type: ContentType::from_str(row.get::<_, String>(1)?.as_str())
+            .unwrap_or(ContentType::Text),
+        plain_text: row.get(2)?,
+        rich_text: row.get(3)?,
+        rtf: row.get(19)?,
+        image_path: row.get(4)?,
+        image_thumbnail: row.get(9)?,
+        width: row.get(20)?,
+        height: row.get(21)?,
+        byte_size: row.get(22)?,
+        preview: row.get(5)?,
+        hash: row.get(6)?,
+        created_at,
+        updated_at: row.get::<_, Option<String>>(15)?
+            .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or(created_at),
+        encrypted: row.get::<_, Option<i32>>(16)?.unwrap_or(0) != 0,
+        source_icon: row.get(17)?,
+        pin_until: row.get::<_, Option<String>>(18)?
+            .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+            .map(|dt| dt.with_timezone(&Utc)),
+        is_pinned: row.get::<_, i32>(8)? != 0,
+        source_app: row.get(10)?,
+        lang: row.get(11)?,
+        measure_value: row.get(12)?,
+        measure_unit: row.get(13)?,
+        image_key: row.get(14)?,
+    })
+}
+
+/// Set the profile whose records are listed and receive new captures
+pub fn set_active_profile(name: &str) {
+    *ACTIVE_PROFILE.write() = name.to_string();
+}
+
+/// Get the active profile
+pub fn active_profile() -> String {
+    ACTIVE_PROFILE.read().clone()
+}
+
+/// Get all profile names that have records (always includes the active profile)
+pub fn get_profiles() -> Result<Vec<String>, DatabaseError> {
+    with_db!(conn => {
+        let mut stmt = conn.prepare("SELECT DISTINCT profile FROM clipboard_history ORDER BY profile")?;
+        let mut profiles: Vec<String> = stmt.query_map([], |row| row.get(0))?.filter_map(|r| r.ok()).collect();
+        
+        let active = active_profile();
+        if !profiles.contains(&active) {
+            profiles.push(active);
+            profiles.sort();
+        }
+        Ok(profiles)
+    })
+}
+
+/// Insert new clipboard record (into the active profile)
 pub fn insert_clipboard_item(item: &ClipboardItem) -> Result<i64, DatabaseError> {
     with_db!(conn => {
         conn.execute(
             r#"
             INSERT OR REPLACE INTO clipboard_history 
-            (content_type, plain_text, rich_text, image_path, preview, hash, created_at, is_pinned, image_thumbnail)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            (content_type, plain_text, rich_text, image_path, preview, hash, created_at, is_pinned, image_thumbnail, source_app, lang, measure_value, measure_unit, image_key, updated_at, encrypted, source_icon, pin_until, profile, rtf, width, height, byte_size)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23)
             "#,
             params![
                 item.content_type.as_str(),
@@ -107,6 +549,20 @@ pub fn insert_clipboard_item(item: &ClipboardItem) -> Result<i64, DatabaseError>
                 item.created_at.to_rfc3339(),
                 item.is_pinned as i32,
                 item.image_thumbnail,
+                item.source_app,
+                item.lang,
+                item.measure_value,
+                item.measure_unit,
+                item.image_key,
+                item.updated_at.to_rfc3339(),
+                item.encrypted as i32,
+                item.source_icon,
+                item.pin_until.map(|dt| dt.to_rfc3339()),
+                active_profile(),
+                item.rtf,
+                item.width,
+                item.height,
+                item.byte_size,
             ],
         )?;
         
@@ -115,201 +571,1979 @@ pub fn insert_clipboard_item(item: &ClipboardItem) -> Result<i64, DatabaseError>
 }
 
 /// Get all clipboard history records
-pub fn get_all_items(limit: Option<i32>) -> Result<Vec<ClipboardItem>, DatabaseError> {
-    with_db!(conn => {
-        let sql = match limit {
-            Some(n) if n > 0 => format!(
-                "SELECT id, content_type, plain_text, rich_text, image_path, preview, hash, created_at, is_pinned, image_thumbnail 
-                 FROM clipboard_history 
-                 ORDER BY is_pinned DESC, created_at DESC 
-                 LIMIT {}", n
-            ),
-            _ => "SELECT id, content_type, plain_text, rich_text, image_path, preview, hash, created_at, is_pinned, image_thumbnail 
-                  FROM clipboard_history 
-                  ORDER BY is_pinned DESC, created_at DESC".to_string(),
-        };
+/// 
+/// `offset` skips that many records for paging, id breaks created_at ties
+/// so pages never overlap
+pub fn get_all_items(limit: Option<i32>, offset: Option<i32>) -> Result<Vec<ClipboardItem>, DatabaseError> {
+    with_db!(conn => {
+        let limit = limit.filter(|&n| n > 0).unwrap_or(-1);
+        let offset = offset.filter(|&n| n > 0).unwrap_or(0);
+        let sql = format!(
+            "SELECT {} 
+             FROM clipboard_history 
+             WHERE profile = ?1 AND deleted_at IS NULL
+             ORDER BY is_pinned DESC, created_at DESC, id DESC 
+             LIMIT ?2 OFFSET ?3", ITEM_COLUMNS
+        );
         
         let mut stmt = conn.prepare(&sql)?;
-        let items = stmt.query_map([], |row| {
-            Ok(ClipboardItem {
-                id: row.get(0)?,
-                content_type: ContentType::from_str(row.get::<_, String>(1)?.as_str())
-                    .unwrap_or(ContentType::Text),
-                plain_text: row.get(2)?,
-                rich_text: row.get(3)?,
-                image_path: row.get(4)?,
-                image_thumbnail: row.get(9)?,
-                preview: row.get(5)?,
-                hash: row.get(6)?,
-                created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(7)?)
-                    .map(|dt| dt.with_timezone(&Utc))
-                    .unwrap_or_else(|_| Utc::now()),
-                is_pinned: row.get::<_, i32>(8)? != 0,
-            })
-        })?.filter_map(|r| r.ok()).collect();
+        let items = stmt.query_map(params![active_profile(), limit, offset], row_to_item)?
+            .filter_map(|r| r.ok())
+            .collect();
         
         Ok(items)
     })
 }
 
-/// Get single record by ID
-pub fn get_item_by_id(id: i64) -> Result<Option<ClipboardItem>, DatabaseError> {
+/// Get records in the given language
+pub fn get_items_by_language(code: &str, limit: Option<i32>) -> Result<Vec<ClipboardItem>, DatabaseError> {
     with_db!(conn => {
-        let mut stmt = conn.prepare(
-            "SELECT id, content_type, plain_text, rich_text, image_path, preview, hash, created_at, is_pinned, image_thumbnail
-             FROM clipboard_history WHERE id = ?1"
-        )?;
-        
-        let item = stmt.query_row([id], |row| {
-            Ok(ClipboardItem {
-                id: row.get(0)?,
-                content_type: ContentType::from_str(row.get::<_, String>(1)?.as_str())
-                    .unwrap_or(ContentType::Text),
-                plain_text: row.get(2)?,
-                rich_text: row.get(3)?,
-                image_path: row.get(4)?,
-                image_thumbnail: row.get(9)?,
-                preview: row.get(5)?,
-                hash: row.get(6)?,
-                created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(7)?)
-                    .map(|dt| dt.with_timezone(&Utc))
-                    .unwrap_or_else(|_| Utc::now()),
-                is_pinned: row.get::<_, i32>(8)? != 0,
-            })
-        }).optional()?;
+        let limit_clause = match limit {
+            Some(n) if n > 0 => format!("LIMIT {}", n),
+            _ => String::new(),
+        };
         
-        Ok(item)
-    })
-}
-
-/// Delete specified record
-pub fn delete_item(id: i64) -> Result<bool, DatabaseError> {
-    with_db!(conn => {
-        let affected = conn.execute("DELETE FROM clipboard_history WHERE id = ?1", [id])?;
-        Ok(affected > 0)
+        let sql = format!(
+            "SELECT {} 
+             FROM clipboard_history 
+             WHERE lang = ?1 AND profile = ?2 AND deleted_at IS NULL
+             ORDER BY is_pinned DESC, created_at DESC
+             {}",
+            ITEM_COLUMNS, limit_clause
+        );
+        
+        let mut stmt = conn.prepare(&sql)?;
+        let items = stmt.query_map(params![code, active_profile()], row_to_item)?.filter_map(|r| r.ok()).collect();
+        
+        Ok(items)
     })
 }
 
-/// Clear all records
-pub fn clear_all_items() -> Result<(), DatabaseError> {
+/// Get pinned records, newest first
+pub fn get_pinned_items() -> Result<Vec<ClipboardItem>, DatabaseError> {
     with_db!(conn => {
-        conn.execute("DELETE FROM clipboard_history", [])?;
-        Ok(())
+        let sql = format!(
+            "SELECT {} 
+             FROM clipboard_history 
+             WHERE is_pinned = 1 AND profile = ?1 AND deleted_at IS NULL
+             ORDER BY created_at DESC",
+            ITEM_COLUMNS
+        );
+        
+        let mut stmt = conn.prepare(&sql)?;
+        let items = stmt.query_map([active_profile()], row_to_item)?.filter_map(|r| r.ok()).collect();
+        
+        Ok(items)
     })
 }
 
-/// Check if hash already exists
-pub fn hash_exists(hash: &str) -> Result<bool, DatabaseError> {
+/// Get records captured between two times (inclusive), newest first
+/// 
+/// created_at is stored as UTC RFC 3339, which compares correctly as text
+/// once the bounds are normalized to UTC the same way
+pub fn get_items_in_range(start: DateTime<Utc>, end: DateTime<Utc>, limit: Option<i32>) -> Result<Vec<ClipboardItem>, DatabaseError> {
     with_db!(conn => {
-        let mut stmt = conn.prepare("SELECT 1 FROM clipboard_history WHERE hash = ?1 LIMIT 1")?;
-        let exists = stmt.exists([hash])?;
-        Ok(exists)
+        let limit_clause = match limit {
+            Some(n) if n > 0 => format!("LIMIT {}", n),
+            _ => String::new(),
+        };
+        
+        let sql = format!(
+            "SELECT {} 
+             FROM clipboard_history 
+             WHERE created_at BETWEEN ?1 AND ?2 AND profile = ?3 AND deleted_at IS NULL
+             ORDER BY created_at DESC
+             {}",
+            ITEM_COLUMNS, limit_clause
+        );
+        
+        let mut stmt = conn.prepare(&sql)?;
+        let items = stmt.query_map(params![start.to_rfc3339(), end.to_rfc3339(), active_profile()], row_to_item)?
+            .filter_map(|r| r.ok())
+            .collect();
+        
+        Ok(items)
     })
 }
 
-/// Get total record count
-pub fn get_item_count() -> Result<i64, DatabaseError> {
+/// Get records of the given content type
+pub fn get_items_by_type(content_type: &ContentType, limit: Option<i32>) -> Result<Vec<ClipboardItem>, DatabaseError> {
     with_db!(conn => {
-        let count: i64 = conn.query_row(
-            "SELECT COUNT(*) FROM clipboard_history",
-            [],
-            |row| row.get(0)
-        )?;
-        Ok(count)
+        let limit_clause = match limit {
+            Some(n) if n > 0 => format!("LIMIT {}", n),
+            _ => String::new(),
+        };
+        
+        let sql = format!(
+            "SELECT {} 
+             FROM clipboard_history 
+             WHERE content_type = ?1 AND profile = ?2 AND deleted_at IS NULL
+             ORDER BY is_pinned DESC, created_at DESC
+             {}",
+            ITEM_COLUMNS, limit_clause
+        );
+        
+        let mut stmt = conn.prepare(&sql)?;
+        let items = stmt.query_map(params![content_type.as_str(), active_profile()], row_to_item)?.filter_map(|r| r.ok()).collect();
+        
+        Ok(items)
     })
 }
 
-/// Cleanup old records exceeding limit
+/// Get records whose text has at least `min_chars` characters
 /// 
-/// Keep the latest max_count records, delete the rest
-pub fn cleanup_old_items(max_count: i32) -> Result<i64, DatabaseError> {
-    if max_count <= 0 {
-        return Ok(0); // Unlimited mode
-    }
-    
+/// Records without text (images, encrypted records) are excluded
+pub fn get_items_min_length(min_chars: u32, limit: Option<i32>) -> Result<Vec<ClipboardItem>, DatabaseError> {
     with_db!(conn => {
-        // Delete old records exceeding limit (keep pinned ones)
-        let deleted = conn.execute(
-            r#"
-            DELETE FROM clipboard_history 
-            WHERE id NOT IN (
-                SELECT id FROM clipboard_history 
-                WHERE is_pinned = 1
-                UNION ALL
-                SELECT id FROM clipboard_history 
-                WHERE is_pinned = 0 
-                ORDER BY created_at DESC 
-                LIMIT ?1
-            )
-            "#,
-            [max_count],
-        )?;
+        let limit_clause = match limit {
+            Some(n) if n > 0 => format!("LIMIT {}", n),
+            _ => String::new(),
+        };
         
-        Ok(deleted as i64)
+        let sql = format!(
+            "SELECT {} 
+             FROM clipboard_history 
+             WHERE plain_text IS NOT NULL AND length(plain_text) >= ?1 AND profile = ?2 AND deleted_at IS NULL
+             ORDER BY is_pinned DESC, created_at DESC
+             {}",
+            ITEM_COLUMNS, limit_clause
+        );
+        
+        let mut stmt = conn.prepare(&sql)?;
+        let items = stmt.query_map(params![min_chars, active_profile()], row_to_item)?.filter_map(|r| r.ok()).collect();
+        
+        Ok(items)
     })
 }
 
-/// Search clipboard records
-pub fn search_items(query: &str, limit: Option<i32>) -> Result<Vec<ClipboardItem>, DatabaseError> {
+/// Get records of the active profile matching all given conditions (newest first)
+/// 
+/// `query` matches text and preview like search_items, the date range is inclusive
+pub fn get_filtered_items(
+    content_type: Option<&ContentType>,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    query: Option<&str>,
+) -> Result<Vec<ClipboardItem>, DatabaseError> {
     with_db!(conn => {
-        let search_pattern = format!("%{}%", query);
-        let limit_clause = match limit {
-            Some(n) if n > 0 => format!("LIMIT {}", n),
-            _ => String::new(),
-        };
+        let mut conditions = vec!["profile = ?1 AND deleted_at IS NULL".to_string()];
+        let mut values: Vec<String> = vec![active_profile()];
+        
+        if let Some(content_type) = content_type {
+            values.push(content_type.as_str().to_string());
+            conditions.push(format!("content_type = ?{}", values.len()));
+        }
+        if let Some(from) = from {
+            values.push(from.to_rfc3339());
+            conditions.push(format!("created_at >= ?{}", values.len()));
+        }
+        if let Some(to) = to {
+            values.push(to.to_rfc3339());
+            conditions.push(format!("created_at <= ?{}", values.len()));
+        }
+        if let Some(query) = query.filter(|q| !q.is_empty()) {
+            values.push(like_pattern(query));
+            conditions.push(format!(
+                "(plain_text LIKE ?{0} ESCAPE '\\' OR preview LIKE ?{0} ESCAPE '\\' OR ocr_text LIKE ?{0} ESCAPE '\\')",
+                values.len()
+            ));
+        }
         
         let sql = format!(
-            r#"
-            SELECT id, content_type, plain_text, rich_text, image_path, preview, hash, created_at, is_pinned, image_thumbnail 
-            FROM clipboard_history 
-            WHERE plain_text LIKE ?1 OR preview LIKE ?1
-            ORDER BY is_pinned DESC, created_at DESC
-            {}
-            "#,
-            limit_clause
+            "SELECT {} FROM clipboard_history WHERE {} ORDER BY created_at DESC",
+            ITEM_COLUMNS,
+            conditions.join(" AND ")
         );
         
         let mut stmt = conn.prepare(&sql)?;
-        let items = stmt.query_map([&search_pattern], |row| {
-            Ok(ClipboardItem {
-                id: row.get(0)?,
-                content_type: ContentType::from_str(row.get::<_, String>(1)?.as_str())
-                    .unwrap_or(ContentType::Text),
-                plain_text: row.get(2)?,
-                rich_text: row.get(3)?,
-                image_path: row.get(4)?,
-                image_thumbnail: row.get(9)?,
-                preview: row.get(5)?,
-                hash: row.get(6)?,
-                created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(7)?)
-                    .map(|dt| dt.with_timezone(&Utc))
-                    .unwrap_or_else(|_| Utc::now()),
-                is_pinned: row.get::<_, i32>(8)? != 0,
-            })
-        })?.filter_map(|r| r.ok()).collect();
+        let items = stmt
+            .query_map(rusqlite::params_from_iter(values.iter()), row_to_item)?
+            .filter_map(|r| r.ok())
+            .collect();
         
         Ok(items)
     })
 }
 
-// ============== Settings Operations ==============
+/// Get single record by ID
+pub fn get_item_by_id(id: i64) -> Result<Option<ClipboardItem>, DatabaseError> {
+    with_db!(conn => {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM clipboard_history WHERE id = ?1", ITEM_COLUMNS
+        ))?;
+        
+        let item = stmt.query_row([id], row_to_item).optional()?;
+        
+        Ok(item)
+    })
+}
 
-/// Save setting item
-pub fn save_setting(key: &str, value: &str) -> Result<(), DatabaseError> {
+/// Get stored color palette of a record (JSON array)
+pub fn get_item_palette(id: i64) -> Result<Option<String>, DatabaseError> {
     with_db!(conn => {
-        conn.execute(
-            "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
-            params![key, value],
-        )?;
-        Ok(())
+        let palette: Option<Option<String>> = conn.query_row(
+            "SELECT palette FROM clipboard_history WHERE id = ?1",
+            [id],
+            |row| row.get(0)
+        ).optional()?;
+        Ok(palette.flatten())
     })
 }
 
-/// Get setting item
-pub fn get_setting(key: &str) -> Result<Option<String>, DatabaseError> {
+/// Get text recognized in an image record (None until ocr_item ran)
+pub fn get_item_ocr_text(id: i64) -> Result<Option<String>, DatabaseError> {
     with_db!(conn => {
-        let mut stmt = conn.prepare("SELECT value FROM settings WHERE key = ?1")?;
-        let value = stmt.query_row([key], |row| row.get(0)).optional()?;
-        Ok(value)
+        let text: Option<Option<String>> = conn.query_row(
+            "SELECT ocr_text FROM clipboard_history WHERE id = ?1",
+            [id],
+            |row| row.get(0)
+        ).optional()?;
+        Ok(text.flatten())
     })
 }
+
+/// Get the id of a record of the given type with exactly this plain text
+pub fn find_item_by_plain_text(content_type: &ContentType, text: &str) -> Result<Option<i64>, DatabaseError> {
+    with_db!(conn => {
+        let id = conn.query_row(
+            "SELECT id FROM clipboard_history WHERE content_type = ?1 AND plain_text = ?2 AND profile = ?3 AND deleted_at IS NULL ORDER BY created_at DESC LIMIT 1",
+            params![content_type.as_str(), text, active_profile()],
+            |row| row.get(0)
+        ).optional()?;
+        Ok(id)
+    })
+}
+
+/// Turn a plain text record into a rich text record, keeping its id
+pub fn upgrade_to_rich_text(id: i64, html: &str, hash: &str) -> Result<(), DatabaseError> {
+    with_db!(conn => {
+        conn.execute(
+            "UPDATE clipboard_history SET content_type = ?2, rich_text = ?3, hash = ?4, updated_at = ?5 WHERE id = ?1",
+            params![id, ContentType::RichText.as_str(), html, hash, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    })
+}
+
+/// Replace the plain text of a record, storing it as the given text-like content type
+/// 
+/// The formatting (HTML, RTF) and the measurement detected from the old text
+/// no longer apply and are cleared
+pub fn update_item_text(id: i64, content_type: &ContentType, text: &str, preview: &str, hash: &str) -> Result<(), DatabaseError> {
+    with_db!(conn => {
+        conn.execute(
+            r#"
+            UPDATE clipboard_history SET 
+                content_type = ?6, plain_text = ?2, preview = ?3, hash = ?4, updated_at = ?5, 
+                rich_text = NULL, measure_value = NULL, measure_unit = NULL, rtf = NULL 
+            WHERE id = ?1
+            "#,
+            params![id, text, preview, hash, Utc::now().to_rfc3339(), content_type.as_str()],
+        )?;
+        Ok(())
+    })
+}
+
+/// Replace the content of a record with its encrypted form
+/// 
+/// Clears every column derived from the content (preview, thumbnail, language, ...)
+pub fn set_item_encrypted(id: i64, ciphertext: &str, image_path: Option<&str>, hash: &str) -> Result<(), DatabaseError> {
+    with_db!(conn => {
+        conn.execute(
+            r#"
+            UPDATE clipboard_history SET 
+                encrypted = 1, ciphertext = ?2, image_path = ?3, hash = ?4, updated_at = ?5, 
+                plain_text = NULL, rich_text = NULL, rtf = NULL, preview = '[Encrypted]', image_thumbnail = NULL, 
+                width = NULL, height = NULL, byte_size = NULL, 
+                palette = NULL, ocr_text = NULL, lang = NULL, measure_value = NULL, measure_unit = NULL, image_key = NULL 
+            WHERE id = ?1
+            "#,
+            params![id, ciphertext, image_path, hash, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    })
+}
+
+/// Get the encrypted content of a record (Base64)
+pub fn get_item_ciphertext(id: i64) -> Result<Option<String>, DatabaseError> {
+    with_db!(conn => {
+        let ciphertext = conn.query_row(
+            "SELECT ciphertext FROM clipboard_history WHERE id = ?1",
+            [id],
+            |row| row.get(0)
+        ).optional()?;
+        Ok(ciphertext.flatten())
+    })
+}
+
+/// Get id and image path of all unencrypted image records
+pub fn get_image_records() -> Result<Vec<(i64, String)>, DatabaseError> {
+    with_db!(conn => {
+        let mut stmt = conn.prepare(
+            "SELECT id, image_path FROM clipboard_history 
+             WHERE content_type = 'image' AND image_path IS NOT NULL AND COALESCE(encrypted, 0) = 0"
+        )?;
+        let records = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(records)
+    })
+}
+
+/// Point an image record at a new image file
+pub fn update_item_image(id: i64, image_path: &str, thumbnail: Option<&str>, byte_size: i64) -> Result<(), DatabaseError> {
+    with_db!(conn => {
+        conn.execute(
+            "UPDATE clipboard_history SET image_path = ?2, image_thumbnail = ?3, byte_size = ?4, updated_at = ?5 WHERE id = ?1",
+            params![id, image_path, thumbnail, byte_size, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    })
+}
+
+/// Pin or unpin a record (a manual pin replaces any timed pin)
+/// 
+/// Returns whether the record exists
+pub fn set_pinned(id: i64, pinned: bool) -> Result<bool, DatabaseError> {
+    with_db!(conn => {
+        let updated = conn.execute(
+            "UPDATE clipboard_history SET is_pinned = ?2, pin_until = NULL, updated_at = ?3 WHERE id = ?1",
+            params![id, pinned as i32, Utc::now().to_rfc3339()],
+        )?;
+        Ok(updated > 0)
+    })
+}
+
+/// Pin a record until the given time, or remove its pin (None)
+/// 
+/// Returns whether the record exists
+pub fn set_pin_until(id: i64, until: Option<DateTime<Utc>>) -> Result<bool, DatabaseError> {
+    with_db!(conn => {
+        let updated = conn.execute(
+            "UPDATE clipboard_history SET is_pinned = ?2, pin_until = ?3, updated_at = ?4 WHERE id = ?1",
+            params![id, until.is_some() as i32, until.map(|dt| dt.to_rfc3339()), Utc::now().to_rfc3339()],
+        )?;
+        Ok(updated > 0)
+    })
+}
+
+/// Unpin records whose timed pin has passed
+/// 
+/// Returns the number of unpinned records
+pub fn expire_timed_pins() -> Result<usize, DatabaseError> {
+    let now = Utc::now().to_rfc3339();
+    with_db!(conn => {
+        let expired = conn.execute(
+            "UPDATE clipboard_history SET is_pinned = 0, pin_until = NULL, updated_at = ?1 
+             WHERE pin_until IS NOT NULL AND pin_until <= ?1",
+            [&now],
+        )?;
+        Ok(expired)
+    })
+}
+
+/// Store color palette of a record (JSON array)
+/// 
+/// The palette is derived from the image and cached on request, so updated_at is kept
+pub fn set_item_palette(id: i64, palette: &str) -> Result<(), DatabaseError> {
+    with_db!(conn => {
+        conn.execute(
+            "UPDATE clipboard_history SET palette = ?2 WHERE id = ?1",
+            params![id, palette],
+        )?;
+        Ok(())
+    })
+}
+
+/// Store text recognized in an image record
+/// 
+/// The text is derived from the image and cached on request, so updated_at is kept
+pub fn set_item_ocr_text(id: i64, text: &str) -> Result<(), DatabaseError> {
+    with_db!(conn => {
+        conn.execute(
+            "UPDATE clipboard_history SET ocr_text = ?2 WHERE id = ?1",
+            params![id, text],
+        )?;
+        Ok(())
+    })
+}
+
+/// Move a record to the trash
+pub fn delete_item(id: i64) -> Result<bool, DatabaseError> {
+    with_db!(conn => {
+        let affected = conn.execute(
+            "UPDATE clipboard_history SET deleted_at = ?2, updated_at = ?2 WHERE id = ?1 AND deleted_at IS NULL",
+            params![id, Utc::now().to_rfc3339()],
+        )?;
+        Ok(affected > 0)
+    })
+}
+
+/// Get the records in the trash of the active profile, most recently deleted first
+pub fn get_trash() -> Result<Vec<ClipboardItem>, DatabaseError> {
+    with_db!(conn => {
+        let sql = format!(
+            "SELECT {} 
+             FROM clipboard_history 
+             WHERE deleted_at IS NOT NULL AND profile = ?1
+             ORDER BY deleted_at DESC",
+            ITEM_COLUMNS
+        );
+        
+        let mut stmt = conn.prepare(&sql)?;
+        let items = stmt.query_map([active_profile()], row_to_item)?.filter_map(|r| r.ok()).collect();
+        
+        Ok(items)
+    })
+}
+
+/// Move a record out of the trash
+pub fn restore_item(id: i64) -> Result<bool, DatabaseError> {
+    with_db!(conn => {
+        let affected = conn.execute(
+            "UPDATE clipboard_history SET deleted_at = NULL, updated_at = ?2 WHERE id = ?1 AND deleted_at IS NOT NULL",
+            params![id, Utc::now().to_rfc3339()],
+        )?;
+        Ok(affected > 0)
+    })
+}
+
+/// Permanently delete trashed records, all of them or those deleted more than `max_age_days` ago
+/// 
+/// Covers every profile, image files of purged records are deleted too.
+/// Returns the number of purged records
+pub fn purge_trash(max_age_days: Option<u32>, data_dir: &Path) -> Result<usize, DatabaseError> {
+    with_db!(conn => {
+        // Records deleted before the cutoff (no cutoff: all of them)
+        let cutoff = max_age_days
+            .map(|days| (Utc::now() - chrono::Duration::days(days as i64)).to_rfc3339())
+            .unwrap_or_else(|| "9999".to_string());
+        let condition = "deleted_at IS NOT NULL AND deleted_at < ?1";
+        let image_paths = query_image_paths(conn, condition, [&cutoff])?;
+        let purged = conn.execute(&format!("DELETE FROM clipboard_history WHERE {}", condition), [&cutoff])?;
+        remove_image_files(conn, data_dir, &image_paths);
+        if purged > 0 {
+            log::info!("Purged {} records from the trash", purged);
+        }
+        Ok(purged)
+    })
+}
+
+/// Clear all records of the active profile and their image files
+pub fn clear_all_items(data_dir: &Path) -> Result<(), DatabaseError> {
+    with_db!(conn => {
+        let image_paths = query_image_paths(conn, "profile = ?1", [active_profile()])?;
+        conn.execute("DELETE FROM clipboard_history WHERE profile = ?1", [active_profile()])?;
+        remove_image_files(conn, data_dir, &image_paths);
+        Ok(())
+    })
+}
+
+/// Get the image paths of the records matching a condition
+fn query_image_paths<P: rusqlite::Params>(conn: &Connection, condition: &str, params: P) -> Result<Vec<String>, DatabaseError> {
+    let sql = format!("SELECT image_path FROM clipboard_history WHERE {} AND image_path IS NOT NULL", condition);
+    let mut stmt = conn.prepare(&sql)?;
+    let paths = stmt.query_map(params, |row| row.get::<_, String>(0))?.filter_map(|r| r.ok()).collect();
+    Ok(paths)
+}
+
+/// Delete the image files of deleted records
+/// 
+/// Files still referenced by a remaining record are kept
+fn remove_image_files(conn: &Connection, data_dir: &Path, image_paths: &[String]) {
+    for image_path in image_paths {
+        let referenced = conn
+            .query_row("SELECT EXISTS(SELECT 1 FROM clipboard_history WHERE image_path = ?1)", [image_path], |row| row.get(0))
+            .unwrap_or(true);
+        if referenced {
+            continue;
+        }
+        match fs::remove_file(data_dir.join(image_path)) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => log::warn!("Failed to remove image file {}: {}", image_path, e),
+        }
+    }
+}
+
+/// Check if hash already exists in the active profile (including its trash)
+pub fn hash_exists(hash: &str) -> Result<bool, DatabaseError> {
+    with_db!(conn => {
+        let mut stmt = conn.prepare("SELECT 1 FROM clipboard_history WHERE hash = ?1 AND profile = ?2 LIMIT 1")?;
+        let exists = stmt.exists(params![hash, active_profile()])?;
+        Ok(exists)
+    })
+}
+
+/// Get record id by content hash in the active profile (including its trash)
+pub fn get_item_id_by_hash(hash: &str) -> Result<Option<i64>, DatabaseError> {
+    with_db!(conn => {
+        let id = conn.query_row(
+            "SELECT id FROM clipboard_history WHERE hash = ?1 AND profile = ?2",
+            params![hash, active_profile()],
+            |row| row.get(0)
+        ).optional()?;
+        Ok(id)
+    })
+}
+
+/// Get record id by canonical image key in the active profile (including its trash)
+pub fn get_item_id_by_image_key(key: &str) -> Result<Option<i64>, DatabaseError> {
+    with_db!(conn => {
+        let id = conn.query_row(
+            "SELECT id FROM clipboard_history WHERE image_key = ?1 AND profile = ?2",
+            params![key, active_profile()],
+            |row| row.get(0)
+        ).optional()?;
+        Ok(id)
+    })
+}
+
+/// Get total record count
+pub fn get_item_count() -> Result<i64, DatabaseError> {
+    with_db!(conn => {
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM clipboard_history WHERE profile = ?1 AND deleted_at IS NULL",
+            [active_profile()],
+            |row| row.get(0)
+        )?;
+        Ok(count)
+    })
+}
+
+/// Get the number of unpinned records (the ones counted against the storage limit)
+pub fn get_unpinned_count() -> Result<i64, DatabaseError> {
+    with_db!(conn => {
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM clipboard_history WHERE is_pinned = 0 AND profile = ?1 AND deleted_at IS NULL",
+            [active_profile()],
+            |row| row.get(0)
+        )?;
+        Ok(count)
+    })
+}
+
+/// Get the number of pinned records
+pub fn get_pinned_count() -> Result<i64, DatabaseError> {
+    with_db!(conn => {
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM clipboard_history WHERE is_pinned = 1 AND profile = ?1 AND deleted_at IS NULL",
+            [active_profile()],
+            |row| row.get(0)
+        )?;
+        Ok(count)
+    })
+}
+
+/// Capture count of a single day
+#[derive(Debug, Clone, Serialize)]
+pub struct DailyCount {
+    /// Local calendar date (YYYY-MM-DD)
+    pub date: String,
+    pub count: i64,
+}
+
+/// Get daily capture counts for the last N days (including today)
+/// 
+/// Days follow the user's local calendar, days without captures have a count of 0
+pub fn get_daily_counts(days: u32) -> Result<Vec<DailyCount>, DatabaseError> {
+    let days = days.max(1);
+    let today = Local::now().date_naive();
+    let start_date = today - chrono::Duration::days(days as i64 - 1);
+    // One extra day covers any UTC offset, rows are filtered by local date below
+    let cutoff = (Utc::now() - chrono::Duration::days(days as i64 + 1)).to_rfc3339();
+    
+    let mut counts: std::collections::BTreeMap<chrono::NaiveDate, i64> = (0..days)
+        .map(|i| (start_date + chrono::Duration::days(i as i64), 0))
+        .collect();
+    
+    with_db!(conn => {
+        let mut stmt = conn.prepare("SELECT created_at FROM clipboard_history WHERE created_at >= ?1 AND profile = ?2 AND deleted_at IS NULL")?;
+        let timestamps = stmt.query_map(params![cutoff, active_profile()], |row| row.get::<_, String>(0))?;
+        
+        for timestamp in timestamps.filter_map(|r| r.ok()) {
+            if let Ok(dt) = DateTime::parse_from_rfc3339(&timestamp) {
+                let date = dt.with_timezone(&Local).date_naive();
+                if let Some(count) = counts.get_mut(&date) {
+                    *count += 1;
+                }
+            }
+        }
+        
+        Ok(counts
+            .into_iter()
+            .map(|(date, count)| DailyCount { date: date.format("%Y-%m-%d").to_string(), count })
+            .collect())
+    })
+}
+
+/// Storage usage summary (records of the active profile, files of the whole app)
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageStats {
+    pub total_items: i64,
+    /// Record count per content type name
+    pub counts_by_type: std::collections::BTreeMap<String, i64>,
+    /// Database file size in bytes (including the write-ahead log)
+    pub db_size_bytes: u64,
+    /// Total size of the images directory in bytes
+    pub images_size_bytes: u64,
+    pub oldest_at: Option<DateTime<Utc>>,
+    pub newest_at: Option<DateTime<Utc>>,
+}
+
+/// Collect storage statistics
+pub fn get_storage_stats(data_dir: &Path) -> Result<StorageStats, DatabaseError> {
+    let (counts_by_type, oldest_at, newest_at) = with_db!(conn => {
+        let mut stmt = conn.prepare(
+            "SELECT content_type, COUNT(*) FROM clipboard_history WHERE profile = ?1 AND deleted_at IS NULL GROUP BY content_type"
+        )?;
+        let counts: std::collections::BTreeMap<String, i64> = stmt
+            .query_map([active_profile()], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .filter_map(|r| r.ok())
+            .collect();
+        
+        let (oldest, newest): (Option<String>, Option<String>) = conn.query_row(
+            "SELECT MIN(created_at), MAX(created_at) FROM clipboard_history WHERE profile = ?1 AND deleted_at IS NULL",
+            [active_profile()],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        let parse = |value: Option<String>| {
+            value
+                .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+                .map(|dt| dt.with_timezone(&Utc))
+        };
+        (counts, parse(oldest), parse(newest))
+    });
+    
+    let file_size = |name: &str| fs::metadata(data_dir.join(name)).map(|m| m.len()).unwrap_or(0);
+    let db_size_bytes = file_size("data.db") + file_size("data.db-wal");
+    
+    let images_size_bytes = match fs::read_dir(data_dir.join("images")) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok()?.metadata().ok())
+            .filter(|metadata| metadata.is_file())
+            .map(|metadata| metadata.len())
+            .sum(),
+        Err(_) => 0,
+    };
+    
+    Ok(StorageStats {
+        total_items: counts_by_type.values().sum(),
+        counts_by_type,
+        db_size_bytes,
+        images_size_bytes,
+        oldest_at,
+        newest_at,
+    })
+}
+
+/// Rebuild the database file to reclaim the space of deleted records
+/// 
+/// Returns the number of bytes freed
+pub fn vacuum_database(data_dir: &Path) -> Result<u64, DatabaseError> {
+    let db_size = || {
+        ["data.db", "data.db-wal"]
+            .iter()
+            .map(|name| fs::metadata(data_dir.join(name)).map(|m| m.len()).unwrap_or(0))
+            .sum::<u64>()
+    };
+    
+    let before = db_size();
+    with_db!(conn => {
+        // VACUUM goes through the WAL, checkpoint so the database file shrinks now
+        conn.execute_batch("VACUUM; PRAGMA wal_checkpoint(TRUNCATE);")?;
+    });
+    let freed = before.saturating_sub(db_size());
+    
+    log::info!("Database vacuumed, {} bytes freed", freed);
+    Ok(freed)
+}
+
+/// Database error for a folder that doesn't hold an EveryPaste database
+fn not_everypaste_database(path: &Path) -> DatabaseError {
+    DatabaseError::InvalidDatabase(path.display().to_string())
+}
+
+/// Whether the database has the EveryPaste tables (settings and clipboard_history)
+fn is_everypaste_database(conn: &Connection) -> Result<bool, DatabaseError> {
+    let tables: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name IN ('settings', 'clipboard_history')",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(tables == 2)
+}
+
+/// Fail when the backup folder is the data folder itself
+fn check_backup_dir(data_dir: &Path, backup_dir: &Path) -> Result<(), DatabaseError> {
+    if fs::canonicalize(data_dir)? == fs::canonicalize(backup_dir)? {
+        return Err(DatabaseError::Io(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "Backup folder must not be the data folder",
+        )));
+    }
+    Ok(())
+}
+
+/// Replace the files of `to` with copies of the files in `from` (a missing `from` leaves `to` empty)
+fn copy_image_files(from: &Path, to: &Path) -> Result<(), DatabaseError> {
+    let _ = fs::remove_dir_all(to);
+    fs::create_dir_all(to)?;
+    let entries = match fs::read_dir(from) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+    for entry in entries {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            fs::copy(entry.path(), to.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Copy every page of `src` into a new database file at `dest_path`, keyed with `password`
+fn backup_to_file(src: &Connection, dest_path: &Path, password: Option<&str>) -> Result<(), DatabaseError> {
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = fs::remove_file(format!("{}{}", dest_path.display(), suffix));
+    }
+    
+    let mut dest = Connection::open(dest_path)?;
+    if let Some(password) = password {
+        // SQLCipher only backs up between databases with the same key
+        dest.pragma_update(None, "key", password)?;
+    }
+    // Copy all pages in one step, writers wait on the connection lock meanwhile
+    Backup::new(src, &mut dest)?.run_to_completion(-1, std::time::Duration::ZERO, None)?;
+    
+    if !is_everypaste_database(&dest)? {
+        return Err(not_everypaste_database(dest_path));
+    }
+    Ok(())
+}
+
+/// Back up the database and images into `dest_dir`
+/// 
+/// Uses the SQLite online backup API, so the copy is consistent while the
+/// database is open. An encrypted database needs its `password`, the backup
+/// is encrypted with it as well. An existing backup in `dest_dir` is replaced
+pub fn backup_database(data_dir: &Path, dest_dir: &Path, password: Option<&str>) -> Result<(), DatabaseError> {
+    fs::create_dir_all(dest_dir)?;
+    check_backup_dir(data_dir, dest_dir)?;
+    
+    let password = if is_database_encrypted() {
+        let password = password.ok_or(DatabaseError::Locked)?;
+        // Check the password on a separate connection
+        open_connection(&data_dir.join("data.db"), Some(password))?;
+        Some(password)
+    } else {
+        None
+    };
+    
+    with_db!(conn => {
+        if !is_everypaste_database(conn)? {
+            return Err(not_everypaste_database(&data_dir.join("data.db")));
+        }
+        backup_to_file(conn, &dest_dir.join("data.db"), password)?;
+    });
+    copy_image_files(&data_dir.join("images"), &dest_dir.join("images"))?;
+    
+    log::info!("Database backed up to {:?}", dest_dir);
+    Ok(())
+}
+
+/// Replace the database and images with the backup in `src_dir`, then reopen the database
+/// 
+/// An encrypted backup needs its `password`. The backup is copied next to the
+/// database first, the current files are only replaced once the copy succeeded
+pub fn restore_database(data_dir: &PathBuf, src_dir: &Path, password: Option<&str>) -> Result<(), DatabaseError> {
+    check_backup_dir(data_dir, src_dir)?;
+    
+    let src_path = src_dir.join("data.db");
+    if !src_path.is_file() {
+        return Err(not_everypaste_database(&src_path));
+    }
+    let src = open_connection(&src_path, password)?;
+    if !is_everypaste_database(&src)? {
+        return Err(not_everypaste_database(&src_path));
+    }
+    
+    let staged_path = data_dir.join("data.db.restoring");
+    let staged_images = data_dir.join("images.restoring");
+    let staged = backup_to_file(&src, &staged_path, password)
+        .and_then(|_| copy_image_files(&src_dir.join("images"), &staged_images));
+    drop(src);
+    if let Err(e) = staged {
+        let _ = fs::remove_file(&staged_path);
+        let _ = fs::remove_dir_all(&staged_images);
+        return Err(e);
+    }
+    
+    // Close the current database so its files can be replaced
+    DB.lock().take();
+    for name in ["data.db-wal", "data.db-shm"] {
+        let _ = fs::remove_file(data_dir.join(name));
+    }
+    fs::rename(&staged_path, data_dir.join("data.db"))?;
+    
+    let images_dir = data_dir.join("images");
+    let _ = fs::remove_dir_all(&images_dir);
+    fs::rename(&staged_images, &images_dir)?;
+    
+    log::info!("Database restored from {:?}", src_dir);
+    init_database(data_dir, password)
+}
+
+/// Usage summary of recent captures
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageInsights {
+    /// Number of days covered
+    pub days: u32,
+    pub total_items: i64,
+    pub average_per_day: f64,
+    /// Local hour of day (0-23) with the most captures
+    pub busiest_hour: Option<u32>,
+    /// Content type captured most often
+    pub most_common_type: Option<String>,
+    /// Longest text record (id and length in characters)
+    pub longest_text_id: Option<i64>,
+    pub longest_text_length: Option<i64>,
+}
+
+/// Summarize the captures of the last N days (including today) from local data only
+pub fn get_usage_insights(days: u32) -> Result<UsageInsights, DatabaseError> {
+    let days = days.max(1);
+    let start = Local::now().date_naive() - chrono::Duration::days(days as i64 - 1);
+    let cutoff = start
+        .and_time(chrono::NaiveTime::MIN)
+        .and_local_timezone(Local)
+        .earliest()
+        .map(|dt| dt.with_timezone(&Utc).to_rfc3339())
+        .unwrap_or_default();
+    
+    with_db!(conn => {
+        let mut stmt = conn.prepare(
+            "SELECT id, created_at, content_type, length(plain_text) FROM clipboard_history 
+             WHERE created_at >= ?1 AND profile = ?2 AND deleted_at IS NULL"
+        )?;
+        let rows = stmt.query_map(params![cutoff, active_profile()], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<i64>>(3)?,
+            ))
+        })?;
+        
+        let mut total_items = 0;
+        let mut hours = [0i64; 24];
+        let mut types: std::collections::HashMap<String, i64> = std::collections::HashMap::new();
+        let mut longest: Option<(i64, i64)> = None;
+        
+        for (id, created_at, content_type, text_length) in rows.filter_map(|r| r.ok()) {
+            total_items += 1;
+            if let Ok(dt) = DateTime::parse_from_rfc3339(&created_at) {
+                hours[dt.with_timezone(&Local).hour() as usize] += 1;
+            }
+            *types.entry(content_type).or_default() += 1;
+            if let Some(length) = text_length {
+                if longest.is_none_or(|(_, max)| length > max) {
+                    longest = Some((id, length));
+                }
+            }
+        }
+        
+        let busiest_hour = (total_items > 0)
+            .then(|| (0..24u32).max_by_key(|&hour| (hours[hour as usize], std::cmp::Reverse(hour))))
+            .flatten();
+        let most_common_type = types
+            .into_iter()
+            .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+            .map(|(content_type, _)| content_type);
+        
+        Ok(UsageInsights {
+            days,
+            total_items,
+            average_per_day: total_items as f64 / days as f64,
+            busiest_hour,
+            most_common_type,
+            longest_text_id: longest.map(|(id, _)| id),
+            longest_text_length: longest.map(|(_, length)| length),
+        })
+    })
+}
+
+/// Records of a single local calendar day
+#[derive(Debug, Clone, Serialize)]
+pub struct DateGroup {
+    /// Local calendar date (YYYY-MM-DD)
+    pub date: String,
+    /// Total records on this day (including ones outside the requested page)
+    pub count: i64,
+    pub items: Vec<ClipboardItem>,
+}
+
+/// Get a page of records (newest first) grouped by local calendar day
+pub fn get_grouped_by_date(limit: i32, offset: i32) -> Result<Vec<DateGroup>, DatabaseError> {
+    with_db!(conn => {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM clipboard_history WHERE profile = ?3 AND deleted_at IS NULL ORDER BY created_at DESC LIMIT ?1 OFFSET ?2",
+            ITEM_COLUMNS
+        ))?;
+        let profile = active_profile();
+        let items: Vec<ClipboardItem> = stmt
+            .query_map(params![limit.max(0), offset.max(0), profile], row_to_item)?
+            .filter_map(|r| r.ok())
+            .collect();
+        
+        let mut groups: Vec<(chrono::NaiveDate, Vec<ClipboardItem>)> = Vec::new();
+        for item in items {
+            let date = item.created_at.with_timezone(&Local).date_naive();
+            match groups.last_mut() {
+                Some((last_date, group)) if *last_date == date => group.push(item),
+                _ => groups.push((date, vec![item])),
+            }
+        }
+        
+        // Count each day over its local boundaries so partially loaded days show their full size
+        let mut count_stmt = conn.prepare(
+            "SELECT COUNT(*) FROM clipboard_history WHERE created_at >= ?1 AND created_at < ?2 AND profile = ?3 AND deleted_at IS NULL"
+        )?;
+        let day_start = |date: chrono::NaiveDate| {
+            date.and_time(chrono::NaiveTime::MIN)
+                .and_local_timezone(Local)
+                .earliest()
+                .map(|dt| dt.with_timezone(&Utc).to_rfc3339())
+                .unwrap_or_default()
+        };
+        groups
+            .into_iter()
+            .map(|(date, items)| {
+                let count = count_stmt.query_row(
+                    params![day_start(date), day_start(date + chrono::Duration::days(1)), profile],
+                    |row| row.get(0),
+                )?;
+                Ok(DateGroup { date: date.format("%Y-%m-%d").to_string(), count, items })
+            })
+            .collect()
+    })
+}
+
+/// Records cleanup_old_items deletes: unpinned live records of the profile (?2)
+/// beyond the newest ?1 of them
+const CLEANUP_CONDITION: &str = r#"
+    profile = ?2 AND deleted_at IS NULL AND is_pinned = 0 AND id NOT IN (
+        SELECT id FROM clipboard_history 
+        WHERE is_pinned = 0 AND profile = ?2 AND deleted_at IS NULL 
+        ORDER BY created_at DESC, id DESC 
+        LIMIT ?1
+    )
+"#;
+
+/// Cleanup old records exceeding limit
+/// 
+/// Keep the latest max_count records of the active profile, delete the rest
+/// 
+/// Records in the trash neither count toward the limit nor are deleted by it,
+/// they stay until purge_trash removes them after trash_retention_days
+pub fn cleanup_old_items(max_count: i32, data_dir: &Path) -> Result<i64, DatabaseError> {
+    if max_count <= 0 {
+        return Ok(0); // Unlimited mode
+    }
+    
+    with_db!(conn => {
+        let image_paths = query_image_paths(conn, CLEANUP_CONDITION, params![max_count, active_profile()])?;
+        let deleted = conn.execute(
+            &format!("DELETE FROM clipboard_history WHERE {}", CLEANUP_CONDITION),
+            params![max_count, active_profile()],
+        )?;
+        remove_image_files(conn, data_dir, &image_paths);
+        
+        Ok(deleted as i64)
+    })
+}
+
+/// Cleanup image records exceeding the image file limit
+/// 
+/// Deletes the oldest unpinned image records and their files until at most
+/// max_files image records remain, returns the number of deleted records
+pub fn cleanup_image_files(max_files: usize, data_dir: &Path) -> Result<i64, DatabaseError> {
+    with_db!(conn => {
+        let total: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM clipboard_history WHERE content_type = 'image'",
+            [],
+            |row| row.get(0)
+        )?;
+        
+        let excess = total - max_files as i64;
+        if excess <= 0 {
+            return Ok(0);
+        }
+        
+        let mut stmt = conn.prepare(
+            "SELECT id, image_path FROM clipboard_history 
+             WHERE content_type = 'image' AND is_pinned = 0 
+             ORDER BY created_at ASC 
+             LIMIT ?1"
+        )?;
+        let oldest: Vec<(i64, Option<String>)> = stmt
+            .query_map([excess], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .filter_map(|r| r.ok())
+            .collect();
+        
+        for (id, _) in &oldest {
+            conn.execute("DELETE FROM clipboard_history WHERE id = ?1", [id])?;
+        }
+        let deleted = oldest.len() as i64;
+        let image_paths: Vec<String> = oldest.into_iter().filter_map(|(_, image_path)| image_path).collect();
+        remove_image_files(conn, data_dir, &image_paths);
+        
+        Ok(deleted)
+    })
+}
+
+/// Files younger than this are never reported as orphans (a capture may not have inserted its row yet)
+const ORPHAN_MIN_AGE: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Find files in the images directory that no record references
+/// 
+/// Returns paths relative to the data directory (like image_path) with their sizes in bytes
+pub fn find_orphan_images(data_dir: &Path) -> Result<Vec<(String, u64)>, DatabaseError> {
+    let referenced: std::collections::HashSet<String> = with_db!(conn => {
+        let mut stmt = conn.prepare("SELECT image_path FROM clipboard_history WHERE image_path IS NOT NULL")?;
+        let paths = stmt.query_map([], |row| row.get::<_, String>(0))?.filter_map(|r| r.ok()).collect();
+        paths
+    });
+    
+    let images_dir = data_dir.join("images");
+    if !images_dir.exists() {
+        return Ok(Vec::new());
+    }
+    
+    let mut orphans = Vec::new();
+    for entry in fs::read_dir(&images_dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if !metadata.is_file() {
+            continue;
+        }
+        let is_recent = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age < ORPHAN_MIN_AGE);
+        if is_recent {
+            continue;
+        }
+        
+        let relative_path = format!("images/{}", entry.file_name().to_string_lossy());
+        if !referenced.contains(&relative_path) {
+            orphans.push((relative_path, metadata.len()));
+        }
+    }
+    
+    orphans.sort();
+    Ok(orphans)
+}
+
+/// Delete image files that no record references
+/// 
+/// Returns the number of removed files and the bytes freed
+pub fn remove_orphan_images(data_dir: &Path) -> Result<(usize, u64), DatabaseError> {
+    let mut removed = 0;
+    let mut freed = 0;
+    for (path, size) in find_orphan_images(data_dir)? {
+        match fs::remove_file(data_dir.join(&path)) {
+            Ok(()) => {
+                removed += 1;
+                freed += size;
+            }
+            Err(e) => log::warn!("Failed to remove orphan image {}: {}", path, e),
+        }
+    }
+    
+    log::info!("Removed {} orphan images, {} bytes freed", removed, freed);
+    Ok((removed, freed))
+}
+
+/// Count records that cleanup_old_items would delete
+/// 
+/// Uses the same selection as cleanup_old_items without deleting anything
+pub fn count_cleanup_candidates(max_count: i32) -> Result<i64, DatabaseError> {
+    if max_count <= 0 {
+        return Ok(0); // Unlimited mode
+    }
+    
+    with_db!(conn => {
+        let count: i64 = conn.query_row(
+            &format!("SELECT COUNT(*) FROM clipboard_history WHERE {}", CLEANUP_CONDITION),
+            params![max_count, active_profile()],
+            |row| row.get(0)
+        )?;
+        
+        Ok(count)
+    })
+}
+
+/// Cleanup image records older than the given age
+/// 
+/// Deletes unpinned image records created more than max_age_days ago
+/// together with their image files, returns the number of deleted records
+pub fn cleanup_expired_images(max_age_days: u32, data_dir: &Path) -> Result<i64, DatabaseError> {
+    let cutoff = (Utc::now() - chrono::Duration::days(max_age_days as i64)).to_rfc3339();
+    
+    with_db!(conn => {
+        let condition = "content_type = 'image' AND is_pinned = 0 AND created_at < ?1";
+        let image_paths = query_image_paths(conn, condition, [&cutoff])?;
+        let deleted = conn.execute(&format!("DELETE FROM clipboard_history WHERE {}", condition), [&cutoff])?;
+        remove_image_files(conn, data_dir, &image_paths);
+        
+        Ok(deleted as i64)
+    })
+}
+
+/// Search clipboard records
+pub fn search_items(query: &str, limit: Option<i32>) -> Result<Vec<ClipboardItem>, DatabaseError> {
+    with_db!(conn => {
+        let search_pattern = like_pattern(query);
+        let limit_clause = match limit {
+            Some(n) if n > 0 => format!("LIMIT {}", n),
+            _ => String::new(),
+        };
+        
+        let sql = format!(
+            r#"
+            SELECT {} 
+            FROM clipboard_history 
+            WHERE (plain_text LIKE ?1 ESCAPE '\' OR preview LIKE ?1 ESCAPE '\' OR ocr_text LIKE ?1 ESCAPE '\') AND profile = ?2 AND deleted_at IS NULL
+            ORDER BY is_pinned DESC, created_at DESC
+            {}
+            "#,
+            ITEM_COLUMNS, limit_clause
+        );
+        
+        let mut stmt = conn.prepare(&sql)?;
+        let items = stmt.query_map(params![search_pattern, active_profile()], row_to_item)?.filter_map(|r| r.ok()).collect();
+        
+        Ok(items)
+    })
+}
+
+/// Search clipboard records with the full-text index, best matches first
+/// 
+/// Matches the same records as search_items; falls back to it when FTS5
+/// isn't available or the query is too short for the trigram index
+pub fn search_items_fts(query: &str, limit: Option<i32>) -> Result<Vec<ClipboardItem>, DatabaseError> {
+    if !FTS_AVAILABLE.load(Ordering::SeqCst) || query.chars().count() < FTS_MIN_QUERY_CHARS {
+        return search_items(query, limit);
+    }
+
+    with_db!(conn => {
+        // Quote the query as a single phrase so FTS syntax in it matches literally
+        let match_expr = format!("\"{}\"", query.replace('"', "\"\""));
+        let limit_clause = match limit {
+            Some(n) if n > 0 => format!("LIMIT {}", n),
+            _ => String::new(),
+        };
+        
+        let sql = format!(
+            r#"
+            SELECT {} 
+            FROM clipboard_history 
+            JOIN (SELECT rowid, bm25(clipboard_fts) AS score FROM clipboard_fts WHERE clipboard_fts MATCH ?1) AS fts
+                ON fts.rowid = clipboard_history.id
+            WHERE profile = ?2 AND deleted_at IS NULL
+            ORDER BY is_pinned DESC, fts.score, created_at DESC
+            {}
+            "#,
+            ITEM_COLUMNS, limit_clause
+        );
+        
+        let mut stmt = conn.prepare(&sql)?;
+        let items = stmt.query_map(params![match_expr, active_profile()], row_to_item)?.filter_map(|r| r.ok()).collect();
+        
+        Ok(items)
+    })
+}
+
+/// Search clipboard records whose text, preview or OCR text matches a regular expression
+/// 
+/// Records are tested in history order, at most REGEX_SCAN_LIMIT of them
+/// (the regex crate runs in linear time, the cap bounds the total work)
+pub fn search_items_regex(pattern: &regex::Regex, limit: Option<i32>) -> Result<Vec<ClipboardItem>, DatabaseError> {
+    with_db!(conn => {
+        let sql = format!(
+            r#"
+            SELECT {}, ocr_text 
+            FROM clipboard_history 
+            WHERE profile = ?1 AND deleted_at IS NULL
+            ORDER BY is_pinned DESC, created_at DESC
+            LIMIT ?2
+            "#,
+            ITEM_COLUMNS
+        );
+        
+        let limit = limit.filter(|&n| n > 0).map(|n| n as usize).unwrap_or(usize::MAX);
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(params![active_profile(), REGEX_SCAN_LIMIT], |row| {
+            let item = row_to_item(row)?;
+            let ocr_text: Option<String> = row.get("ocr_text")?;
+            Ok((item, ocr_text))
+        })?;
+        
+        let items = rows
+            .filter_map(|r| r.ok())
+            .filter(|(item, ocr_text)| {
+                [item.plain_text.as_deref(), Some(item.preview.as_str()), ocr_text.as_deref()]
+                    .into_iter()
+                    .flatten()
+                    .any(|text| pattern.is_match(text))
+            })
+            .map(|(item, _)| item)
+            .take(limit)
+            .collect();
+        
+        Ok(items)
+    })
+}
+
+/// A page of records with the total number of matching records
+#[derive(Debug, Clone)]
+pub struct ItemsPage {
+    pub items: Vec<ClipboardItem>,
+    pub total: i64,
+}
+
+/// Get a page of records (history order) together with the total record count
+pub fn get_items_page_with_total(limit: i32, offset: i32) -> Result<ItemsPage, DatabaseError> {
+    with_db!(conn => {
+        let profile = active_profile();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM clipboard_history 
+             WHERE profile = ?1 AND deleted_at IS NULL 
+             ORDER BY is_pinned DESC, created_at DESC, id DESC 
+             LIMIT ?2 OFFSET ?3",
+            ITEM_COLUMNS
+        ))?;
+        let items = stmt
+            .query_map(params![profile, limit.max(0), offset.max(0)], row_to_item)?
+            .filter_map(|r| r.ok())
+            .collect();
+        
+        let total: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM clipboard_history WHERE profile = ?1 AND deleted_at IS NULL",
+            [&profile],
+            |row| row.get(0)
+        )?;
+        
+        Ok(ItemsPage { items, total })
+    })
+}
+
+/// Get a page of search results together with the total number of matches
+pub fn search_page_with_total(query: &str, limit: i32, offset: i32) -> Result<ItemsPage, DatabaseError> {
+    with_db!(conn => {
+        let search_pattern = like_pattern(query);
+        let profile = active_profile();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM clipboard_history 
+             WHERE (plain_text LIKE ?1 ESCAPE '\\' OR preview LIKE ?1 ESCAPE '\\' OR ocr_text LIKE ?1 ESCAPE '\\') AND profile = ?2 AND deleted_at IS NULL 
+             ORDER BY is_pinned DESC, created_at DESC, id DESC 
+             LIMIT ?3 OFFSET ?4",
+            ITEM_COLUMNS
+        ))?;
+        let items = stmt
+            .query_map(params![search_pattern, profile, limit.max(0), offset.max(0)], row_to_item)?
+            .filter_map(|r| r.ok())
+            .collect();
+        
+        let total: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM clipboard_history WHERE (plain_text LIKE ?1 ESCAPE '\\' OR preview LIKE ?1 ESCAPE '\\' OR ocr_text LIKE ?1 ESCAPE '\\') AND profile = ?2 AND deleted_at IS NULL",
+            params![search_pattern, profile],
+            |row| row.get(0)
+        )?;
+        
+        Ok(ItemsPage { items, total })
+    })
+}
+
+/// Weight of a prefix match in the search ranking score
+const RANK_PREFIX_WEIGHT: f64 = 2.0;
+/// Weight of recency (1.0 for a new record, halved after RANK_RECENCY_HALF_LIFE_DAYS)
+const RANK_RECENCY_WEIGHT: f64 = 1.0;
+/// Weight of ln(1 + use_count)
+const RANK_USE_WEIGHT: f64 = 0.5;
+/// Age in days at which the recency score drops to half
+const RANK_RECENCY_HALF_LIFE_DAYS: f64 = 7.0;
+
+/// Search records and sort them by relevance
+/// 
+/// The score combines match position (text starting with the query ranks
+/// higher than a match inside it), recency and how often the record was pasted
+pub fn search_ranked(query: &str, limit: Option<i32>) -> Result<Vec<ClipboardItem>, DatabaseError> {
+    with_db!(conn => {
+        let search_pattern = like_pattern(query);
+        let sql = format!(
+            r#"
+            SELECT {}, use_count 
+            FROM clipboard_history 
+            WHERE (plain_text LIKE ?1 ESCAPE '\' OR preview LIKE ?1 ESCAPE '\' OR ocr_text LIKE ?1 ESCAPE '\') AND profile = ?2 AND deleted_at IS NULL
+            "#,
+            ITEM_COLUMNS
+        );
+        
+        let now = Utc::now();
+        let query_lower = query.to_lowercase();
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt
+            .query_map(params![search_pattern, active_profile()], |row| {
+                let item = row_to_item(row)?;
+                let use_count: i64 = row.get::<_, Option<i64>>("use_count")?.unwrap_or(0);
+                Ok((item, use_count))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        
+        let mut scored: Vec<(f64, ClipboardItem)> = rows
+            .into_iter()
+            .map(|(item, use_count)| {
+                let text = item.plain_text.as_deref().unwrap_or(&item.preview);
+                let is_prefix = text.trim_start().to_lowercase().starts_with(&query_lower);
+                let age_days = (now - item.created_at).num_seconds().max(0) as f64 / 86_400.0;
+                let recency = 0.5f64.powf(age_days / RANK_RECENCY_HALF_LIFE_DAYS);
+                let score = if is_prefix { RANK_PREFIX_WEIGHT } else { 0.0 }
+                    + RANK_RECENCY_WEIGHT * recency
+                    + RANK_USE_WEIGHT * (use_count.max(0) as f64).ln_1p();
+                (score, item)
+            })
+            .collect();
+        
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| b.1.created_at.cmp(&a.1.created_at)));
+        if let Some(n) = limit.filter(|&n| n > 0) {
+            scored.truncate(n as usize);
+        }
+        
+        Ok(scored.into_iter().map(|(_, item)| item).collect())
+    })
+}
+
+/// Count a paste of a record and remember its time (feeds the search ranking and get_items_by_usage)
+pub fn record_paste(id: i64) -> Result<(), DatabaseError> {
+    with_db!(conn => {
+        conn.execute(
+            "UPDATE clipboard_history SET use_count = COALESCE(use_count, 0) + 1, last_pasted_at = ?2 WHERE id = ?1",
+            params![id, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    })
+}
+
+/// Move a record to the top of the history by setting its creation time to now
+/// 
+/// Pinned records still come first, the content and hash are unchanged
+pub fn bump_item(id: i64) -> Result<bool, DatabaseError> {
+    with_db!(conn => {
+        let affected = conn.execute(
+            "UPDATE clipboard_history SET created_at = ?2 WHERE id = ?1",
+            params![id, Utc::now().to_rfc3339()],
+        )?;
+        Ok(affected > 0)
+    })
+}
+
+/// Move the record with the given content hash in the active profile to the top of the history
+pub fn bump_item_by_hash(hash: &str) -> Result<bool, DatabaseError> {
+    with_db!(conn => {
+        let affected = conn.execute(
+            "UPDATE clipboard_history SET created_at = ?2 WHERE hash = ?1 AND profile = ?3",
+            params![hash, Utc::now().to_rfc3339(), active_profile()],
+        )?;
+        Ok(affected > 0)
+    })
+}
+
+/// Get pasted records, most pasted first (ties by latest paste)
+pub fn get_items_by_usage(limit: Option<i32>) -> Result<Vec<ClipboardItem>, DatabaseError> {
+    with_db!(conn => {
+        let limit_clause = match limit {
+            Some(n) if n > 0 => format!("LIMIT {}", n),
+            _ => String::new(),
+        };
+        
+        let sql = format!(
+            "SELECT {} 
+             FROM clipboard_history 
+             WHERE use_count > 0 AND profile = ?1 AND deleted_at IS NULL
+             ORDER BY use_count DESC, last_pasted_at DESC
+             {}",
+            ITEM_COLUMNS, limit_clause
+        );
+        
+        let mut stmt = conn.prepare(&sql)?;
+        let items = stmt.query_map([active_profile()], row_to_item)?.filter_map(|r| r.ok()).collect();
+        
+        Ok(items)
+    })
+}
+
+/// Search result with the field that matched the query
+#[derive(Debug, Clone)]
+pub struct SearchMatch {
+    pub item: ClipboardItem,
+    /// Matched field: "plain_text", "preview", "ocr_text" or "tag"
+    pub matched_field: String,
+}
+
+/// Search record text, preview, recognized image text and tag names with a single query
+/// 
+/// The matched field is the first match in that order
+pub fn search_all(query: &str, limit: Option<i32>) -> Result<Vec<SearchMatch>, DatabaseError> {
+    with_db!(conn => {
+        let search_pattern = like_pattern(query);
+        let limit_clause = match limit {
+            Some(n) if n > 0 => format!("LIMIT {}", n),
+            _ => String::new(),
+        };
+        
+        let sql = format!(
+            r#"
+            SELECT {}, 
+                CASE 
+                    WHEN plain_text LIKE ?1 ESCAPE '\' THEN 'plain_text' 
+                    WHEN preview LIKE ?1 ESCAPE '\' THEN 'preview' 
+                    WHEN ocr_text LIKE ?1 ESCAPE '\' THEN 'ocr_text' 
+                    ELSE 'tag' 
+                END AS matched_field 
+            FROM clipboard_history 
+            WHERE (plain_text LIKE ?1 ESCAPE '\' OR preview LIKE ?1 ESCAPE '\' OR ocr_text LIKE ?1 ESCAPE '\' OR EXISTS (
+                SELECT 1 FROM item_tags 
+                JOIN tags ON tags.id = item_tags.tag_id 
+                WHERE item_tags.item_id = clipboard_history.id AND tags.name LIKE ?1 ESCAPE '\'
+            )) AND profile = ?2 AND deleted_at IS NULL
+            ORDER BY is_pinned DESC, created_at DESC
+            {}
+            "#,
+            ITEM_COLUMNS, limit_clause
+        );
+        
+        let mut stmt = conn.prepare(&sql)?;
+        let matches = stmt
+            .query_map(params![search_pattern, active_profile()], |row| {
+                Ok(SearchMatch { item: row_to_item(row)?, matched_field: row.get("matched_field")? })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+        
+        Ok(matches)
+    })
+}
+
+// ============== Tag Operations ==============
+
+/// Apply a tag to multiple records in a single transaction
+/// 
+/// Creates the tag if it doesn't exist, returns the number of new
+/// associations (records that already had the tag are skipped)
+pub fn tag_items(ids: &[i64], tag: &str) -> Result<usize, DatabaseError> {
+    with_db!(conn => {
+        let tx = conn.unchecked_transaction()?;
+        
+        tx.execute("INSERT OR IGNORE INTO tags (name) VALUES (?1)", [tag])?;
+        let tag_id: i64 = tx.query_row("SELECT id FROM tags WHERE name = ?1", [tag], |row| row.get(0))?;
+        
+        let mut created = 0;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR IGNORE INTO item_tags (item_id, tag_id) 
+                 SELECT id, ?2 FROM clipboard_history WHERE id = ?1"
+            )?;
+            let mut touch = tx.prepare("UPDATE clipboard_history SET updated_at = ?2 WHERE id = ?1")?;
+            let now = Utc::now().to_rfc3339();
+            for id in ids {
+                let added = stmt.execute(params![id, tag_id])?;
+                if added > 0 {
+                    touch.execute(params![id, now])?;
+                }
+                created += added;
+            }
+        }
+        
+        tx.commit()?;
+        Ok(created)
+    })
+}
+
+// ============== Settings Operations ==============
+
+/// Save setting item
+pub fn save_setting(key: &str, value: &str) -> Result<(), DatabaseError> {
+    with_db!(conn => {
+        conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+            params![key, value],
+        )?;
+        Ok(())
+    })
+}
+
+/// Get setting item
+pub fn get_setting(key: &str) -> Result<Option<String>, DatabaseError> {
+    with_db!(conn => {
+        let mut stmt = conn.prepare("SELECT value FROM settings WHERE key = ?1")?;
+        let value = stmt.query_row([key], |row| row.get(0)).optional()?;
+        Ok(value)
+    })
+}
+
+/// Test database shared by the storage tests and the capture tests in lib.rs
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::*;
+    
+    /// Serializes the tests, they share the global connection
+    static TEST_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+    
+    /// In-memory database with the full schema, installed as the global connection
+    /// 
+    /// Holds the test lock and removes its data directory when dropped
+    pub(crate) struct TestDb {
+        pub data_dir: PathBuf,
+        _guard: parking_lot::MutexGuard<'static, ()>,
+    }
+    
+    impl Drop for TestDb {
+        fn drop(&mut self) {
+            DB.lock().take();
+            let _ = fs::remove_dir_all(&self.data_dir);
+        }
+    }
+    
+    impl TestDb {
+        /// Write an image file into the data directory and return its record
+        pub fn image_item(&self, name: &str) -> ClipboardItem {
+            let path = format!("images/{}.png", name);
+            fs::write(self.data_dir.join(&path), name).unwrap();
+            ClipboardItem::new_image(0, path, None, format!("image-{}", name))
+        }
+        
+        /// Whether the image file of `image_item(name)` exists
+        pub fn image_exists(&self, name: &str) -> bool {
+            self.data_dir.join(format!("images/{}.png", name)).exists()
+        }
+    }
+    
+    pub(crate) fn test_db() -> TestDb {
+        let guard = TEST_LOCK.lock();
+        let data_dir = std::env::temp_dir().join(format!("everypaste-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(data_dir.join("images")).unwrap();
+        
+        let conn = Connection::open_in_memory().unwrap();
+        create_schema(&conn, &data_dir).unwrap();
+        *DB.lock() = Some(conn);
+        set_active_profile(DEFAULT_PROFILE);
+        invalidate_recent_cache();
+        
+        TestDb { data_dir, _guard: guard }
+    }
+    
+    /// Insert a record created `age_days` ago
+    pub(crate) fn insert_aged(mut item: ClipboardItem, age_days: i64) -> i64 {
+        item.created_at = Utc::now() - chrono::Duration::days(age_days);
+        item.updated_at = item.created_at;
+        insert_clipboard_item(&item).unwrap()
+    }
+    
+    /// Text record with its text as hash
+    pub(crate) fn text_item(text: &str) -> ClipboardItem {
+        ClipboardItem::new_text(0, text.to_string(), format!("text-{}", text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::testing::*;
+    
+    fn exists(id: i64) -> bool {
+        get_item_by_id(id).unwrap().is_some()
+    }
+    
+    #[test]
+    fn cleanup_expired_images_prunes_only_old_unpinned_images() {
+        let db = test_db();
+        let old_image = insert_aged(db.image_item("old"), 10);
+        let new_image = insert_aged(db.image_item("new"), 2);
+        let mut pinned = db.image_item("pinned");
+        pinned.is_pinned = true;
+        let pinned_image = insert_aged(pinned, 10);
+        let old_text = insert_aged(text_item("old text"), 10);
+        
+        assert_eq!(cleanup_expired_images(7, &db.data_dir).unwrap(), 1);
+        
+        assert!(!exists(old_image));
+        assert!(!db.image_exists("old"));
+        assert!(exists(new_image) && db.image_exists("new"));
+        assert!(exists(pinned_image) && db.image_exists("pinned"));
+        assert!(exists(old_text));
+    }
+    
+    #[test]
+    fn cleanup_expired_images_keeps_everything_within_age() {
+        let db = test_db();
+        let image = insert_aged(db.image_item("recent"), 1);
+        let text = insert_aged(text_item("recent text"), 1);
+        
+        assert_eq!(cleanup_expired_images(7, &db.data_dir).unwrap(), 0);
+        assert!(exists(image) && db.image_exists("recent"));
+        assert!(exists(text));
+    }
+    
+    #[test]
+    fn cleanup_image_files_removes_oldest_unpinned_images() {
+        let db = test_db();
+        let mut pinned = db.image_item("pinned");
+        pinned.is_pinned = true;
+        let pinned_image = insert_aged(pinned, 5);
+        let oldest = insert_aged(db.image_item("oldest"), 4);
+        let older = insert_aged(db.image_item("older"), 3);
+        let newest = insert_aged(db.image_item("newest"), 1);
+        let text = insert_aged(text_item("old text"), 10);
+        
+        assert_eq!(cleanup_image_files(2, &db.data_dir).unwrap(), 2);
+        
+        assert!(!exists(oldest) && !db.image_exists("oldest"));
+        assert!(!exists(older) && !db.image_exists("older"));
+        assert!(exists(newest) && db.image_exists("newest"));
+        assert!(exists(pinned_image) && db.image_exists("pinned"));
+        assert!(exists(text));
+    }
+    
+    #[test]
+    fn cleanup_image_files_under_limit_deletes_nothing() {
+        let db = test_db();
+        let image = insert_aged(db.image_item("only"), 1);
+        
+        assert_eq!(cleanup_image_files(1, &db.data_dir).unwrap(), 0);
+        assert!(exists(image) && db.image_exists("only"));
+    }
+    
+    #[test]
+    fn backfill_image_keys_keys_the_newest_of_identical_images() {
+        let db = test_db();
+        let img = image::DynamicImage::ImageRgb8(image::RgbImage::from_fn(4, 3, |x, y| image::Rgb([x as u8 * 60, y as u8 * 80, 0])));
+        let mut ids = Vec::new();
+        for (name, format, age_days) in [("png", image::ImageFormat::Png, 2), ("bmp", image::ImageFormat::Bmp, 1)] {
+            let mut data = Vec::new();
+            img.write_to(&mut std::io::Cursor::new(&mut data), format).unwrap();
+            fs::write(db.data_dir.join(format!("images/{}.png", name)), data).unwrap();
+            ids.push(insert_aged(ClipboardItem::new_image(0, format!("images/{}.png", name), None, name.to_string()), age_days));
+        }
+        
+        let keys = {
+            let guard = DB.lock();
+            let conn = guard.as_ref().unwrap();
+            backfill_image_keys(conn, &db.data_dir).unwrap();
+            ids.iter()
+                .map(|id| conn.query_row("SELECT image_key FROM clipboard_history WHERE id = ?1", [id], |row| row.get::<_, Option<String>>(0)).unwrap())
+                .collect::<Vec<_>>()
+        };
+        
+        // The BMP copy is newer and gets the key, the PNG copy is a duplicate of it
+        assert_eq!(keys[0], None);
+        assert_eq!(keys[1], ClipboardMonitor::compute_image_key(&fs::read(db.data_dir.join("images/png.png")).unwrap()));
+        assert!(keys[1].is_some());
+    }
+    
+    #[test]
+    fn same_content_is_kept_separately_per_profile() {
+        let _db = test_db();
+        let item = text_item("hello");
+        let default_id = insert_clipboard_item(&item).unwrap();
+        
+        set_active_profile("work");
+        assert!(!hash_exists(&item.hash).unwrap());
+        let work_id = insert_clipboard_item(&item).unwrap();
+        assert_ne!(default_id, work_id);
+        assert_eq!(get_item_id_by_hash(&item.hash).unwrap(), Some(work_id));
+        
+        set_active_profile(DEFAULT_PROFILE);
+        assert_eq!(get_item_id_by_hash(&item.hash).unwrap(), Some(default_id));
+        assert_eq!(get_item_count().unwrap(), 1);
+    }
+    
+    #[test]
+    fn migration_drops_global_hash_unique_and_keeps_tags() {
+        let db = test_db();
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            r#"
+            PRAGMA foreign_keys = ON;
+            CREATE TABLE clipboard_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                content_type TEXT NOT NULL,
+                plain_text TEXT,
+                rich_text TEXT,
+                image_path TEXT,
+                preview TEXT NOT NULL,
+                hash TEXT NOT NULL UNIQUE,
+                created_at TEXT NOT NULL,
+                is_pinned INTEGER DEFAULT 0
+            );
+            CREATE TABLE tags (id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT NOT NULL UNIQUE);
+            CREATE TABLE item_tags (
+                item_id INTEGER NOT NULL REFERENCES clipboard_history(id) ON DELETE CASCADE,
+                tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+                PRIMARY KEY (item_id, tag_id)
+            );
+            INSERT INTO clipboard_history (content_type, plain_text, preview, hash, created_at)
+                VALUES ('text', 'hello', 'hello', 'h1', '2024-01-01T00:00:00+00:00');
+            INSERT INTO tags (name) VALUES ('work');
+            INSERT INTO item_tags (item_id, tag_id) VALUES (1, 1);
+            "#,
+        ).unwrap();
+        
+        create_schema(&conn, &db.data_dir).unwrap();
+        
+        let count = |sql: &str| conn.query_row(sql, [], |row| row.get::<_, i64>(0)).unwrap();
+        assert_eq!(count("SELECT COUNT(*) FROM item_tags"), 1);
+        assert_eq!(count("PRAGMA user_version"), 2);
+        conn.execute(
+            "INSERT INTO clipboard_history (content_type, plain_text, preview, hash, created_at, profile)
+             VALUES ('text', 'hello', 'hello', 'h1', '2024-01-02T00:00:00+00:00', 'work')",
+            [],
+        ).unwrap();
+        assert_eq!(count("SELECT COUNT(*) FROM clipboard_history WHERE hash = 'h1'"), 2);
+        // Both records are in the full-text index
+        assert_eq!(count("SELECT COUNT(*) FROM clipboard_fts WHERE clipboard_fts MATCH 'hello'"), 2);
+        // Still unique within a profile
+        assert!(conn.execute(
+            "INSERT INTO clipboard_history (content_type, preview, hash, created_at, profile) VALUES ('text', 'x', 'h1', 'now', 'work')",
+            [],
+        ).is_err());
+    }
+    
+    #[test]
+    fn pinned_item_survives_cleanup_old_items() {
+        let db = test_db();
+        let pinned = insert_aged(text_item("pinned"), 5);
+        assert!(set_pinned(pinned, true).unwrap());
+        let old = insert_aged(text_item("old"), 2);
+        let newest = insert_aged(text_item("newest"), 1);
+        
+        assert_eq!(count_cleanup_candidates(1).unwrap(), 1);
+        assert_eq!(cleanup_old_items(1, &db.data_dir).unwrap(), 1);
+        assert_eq!(count_cleanup_candidates(1).unwrap(), 0);
+        
+        assert!(exists(pinned));
+        assert!(!exists(old));
+        assert!(exists(newest));
+    }
+    
+    #[test]
+    fn cleanup_old_items_removes_image_files() {
+        let db = test_db();
+        let old_image = insert_aged(db.image_item("old"), 3);
+        let newest = insert_aged(text_item("newest"), 1);
+        
+        assert_eq!(cleanup_old_items(1, &db.data_dir).unwrap(), 1);
+        
+        assert!(!exists(old_image) && !db.image_exists("old"));
+        assert!(exists(newest));
+    }
+    
+    #[test]
+    fn image_file_shared_with_a_remaining_record_is_kept() {
+        let db = test_db();
+        let expired = insert_aged(db.image_item("shared"), 10);
+        let mut copy = db.image_item("shared");
+        copy.hash = "image-shared-copy".to_string();
+        let recent = insert_aged(copy, 1);
+        
+        assert_eq!(cleanup_expired_images(7, &db.data_dir).unwrap(), 1);
+        
+        assert!(!exists(expired));
+        assert!(exists(recent) && db.image_exists("shared"));
+    }
+    
+    #[test]
+    fn trashed_records_are_left_to_the_trash_purge() {
+        let db = test_db();
+        let trashed_old = insert_aged(text_item("trashed old"), 5);
+        let kept = insert_aged(text_item("kept"), 4);
+        let trashed_new = insert_aged(text_item("trashed new"), 1);
+        delete_item(trashed_old).unwrap();
+        delete_item(trashed_new).unwrap();
+        
+        // The trashed records don't take the single slot
+        assert_eq!(count_cleanup_candidates(1).unwrap(), 0);
+        assert_eq!(cleanup_old_items(1, &db.data_dir).unwrap(), 0);
+        assert!(exists(kept));
+        assert!(exists(trashed_old));
+        assert!(exists(trashed_new));
+        
+        let newer = insert_aged(text_item("newer"), 0);
+        assert_eq!(count_cleanup_candidates(1).unwrap(), 1);
+        assert_eq!(cleanup_old_items(1, &db.data_dir).unwrap(), 1);
+        assert!(!exists(kept));
+        assert!(exists(newer));
+        assert!(exists(trashed_old));
+    }
+    
+    fn search_texts(query: &str) -> Vec<String> {
+        let mut texts: Vec<String> = search_items(query, None)
+            .unwrap()
+            .into_iter()
+            .filter_map(|item| item.plain_text)
+            .collect();
+        texts.sort();
+        texts
+    }
+    
+    #[test]
+    fn like_pattern_escapes_wildcards() {
+        assert_eq!(like_pattern("100%done"), "%100\\%done%");
+        assert_eq!(like_pattern("a_b"), "%a\\_b%");
+        assert_eq!(like_pattern("c:\\dir"), "%c:\\\\dir%");
+    }
+    
+    #[test]
+    fn search_matches_percent_and_underscore_literally() {
+        let _db = test_db();
+        for text in ["100%done", "100 done", "a_b", "axb"] {
+            insert_clipboard_item(&text_item(text)).unwrap();
+        }
+        
+        assert_eq!(search_texts("100%"), vec!["100%done"]);
+        assert_eq!(search_texts("a_b"), vec!["a_b"]);
+        assert_eq!(search_texts("%"), vec!["100%done"]);
+        assert_eq!(search_texts("_"), vec!["a_b"]);
+    }
+    
+    #[test]
+    fn paging_25_records_by_10_covers_each_record_once() {
+        let _db = test_db();
+        // Same timestamp for all records, id keeps the order stable
+        let created_at = Utc::now();
+        for i in 0..25 {
+            let mut item = text_item(&format!("item {}", i));
+            item.created_at = created_at;
+            item.updated_at = created_at;
+            insert_clipboard_item(&item).unwrap();
+        }
+        
+        let pages: Vec<Vec<i64>> = [0, 10, 20]
+            .iter()
+            .map(|&offset| get_all_items(Some(10), Some(offset)).unwrap().iter().map(|item| item.id).collect())
+            .collect();
+        assert_eq!(pages.iter().map(Vec::len).collect::<Vec<_>>(), vec![10, 10, 5]);
+        assert!(get_all_items(Some(10), Some(30)).unwrap().is_empty());
+        
+        let mut ids: Vec<i64> = pages.concat();
+        assert!(ids.windows(2).all(|pair| pair[0] > pair[1]));
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), 25);
+        
+        let page = get_items_page_with_total(10, 20).unwrap();
+        assert_eq!(page.total, 25);
+        assert_eq!(page.items.iter().map(|item| item.id).collect::<Vec<_>>(), pages[2]);
+    }
+    
+    fn search_ids(search: fn(&str, Option<i32>) -> Result<Vec<ClipboardItem>, DatabaseError>, query: &str) -> Vec<i64> {
+        let mut ids: Vec<i64> = search(query, None).unwrap().iter().map(|item| item.id).collect();
+        ids.sort();
+        ids
+    }
+    
+    #[test]
+    fn fts_search_matches_like_search() {
+        let _db = test_db();
+        assert!(FTS_AVAILABLE.load(Ordering::SeqCst));
+        
+        for text in ["Hello World", "say hello", "helicopter", "剪贴板历史记录", "100% done", "unrelated"] {
+            insert_clipboard_item(&text_item(text)).unwrap();
+        }
+        let scanned = insert_clipboard_item(&text_item("screenshot")).unwrap();
+        set_item_ocr_text(scanned, "invoice hello 42").unwrap();
+        let trashed = insert_clipboard_item(&text_item("hello from the trash")).unwrap();
+        delete_item(trashed).unwrap();
+        
+        for query in ["hello", "HELLO", "llo", "贴板历", "0% d", "invoice", "nothing here", "he"] {
+            let like = search_ids(search_items, query);
+            assert_eq!(search_ids(search_items_fts, query), like, "query {:?}", query);
+        }
+        assert_eq!(search_ids(search_items_fts, "hello").len(), 3);
+    }
+    
+    #[test]
+    fn migration_adds_paste_usage_columns() {
+        let db = test_db();
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            r#"
+            CREATE TABLE clipboard_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                content_type TEXT NOT NULL,
+                plain_text TEXT,
+                rich_text TEXT,
+                image_path TEXT,
+                preview TEXT NOT NULL,
+                hash TEXT NOT NULL UNIQUE,
+                created_at TEXT NOT NULL,
+                is_pinned INTEGER DEFAULT 0
+            );
+            INSERT INTO clipboard_history (content_type, plain_text, preview, hash, created_at)
+                VALUES ('text', 'hello', 'hello', 'h1', '2024-01-01T00:00:00+00:00');
+            "#,
+        ).unwrap();
+        
+        create_schema(&conn, &db.data_dir).unwrap();
+        
+        let (use_count, last_pasted_at): (i64, Option<String>) = conn.query_row(
+            "SELECT use_count, last_pasted_at FROM clipboard_history WHERE hash = 'h1'",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).unwrap();
+        assert_eq!(use_count, 0);
+        assert_eq!(last_pasted_at, None);
+    }
+    
+    #[test]
+    fn most_pasted_records_come_first() {
+        let _db = test_db();
+        let once = insert_clipboard_item(&text_item("once")).unwrap();
+        let twice = insert_clipboard_item(&text_item("twice")).unwrap();
+        insert_clipboard_item(&text_item("never")).unwrap();
+        
+        record_paste(twice).unwrap();
+        record_paste(once).unwrap();
+        record_paste(twice).unwrap();
+        
+        let ids = |limit| get_items_by_usage(limit).unwrap().iter().map(|item| item.id).collect::<Vec<_>>();
+        assert_eq!(ids(None), vec![twice, once]);
+        assert_eq!(ids(Some(1)), vec![twice]);
+        
+        let last_pasted_at: Option<String> = DB.lock().as_ref().unwrap().query_row(
+            "SELECT last_pasted_at FROM clipboard_history WHERE id = ?1",
+            [once],
+            |row| row.get(0),
+        ).unwrap();
+        assert!(last_pasted_at.is_some());
+    }
+    
+    #[test]
+    fn ranked_search_keeps_rtf_records_and_counts_pastes() {
+        let _db = test_db();
+        let mut styled = text_item("report styled");
+        styled.rtf = Some("{\\rtf1 report styled}".to_string());
+        let pasted = insert_aged(styled, 2);
+        let recent = insert_aged(text_item("report plain"), 1);
+        for _ in 0..3 {
+            record_paste(pasted).unwrap();
+        }
+        
+        let ids: Vec<i64> = search_ranked("report", None).unwrap().iter().map(|item| item.id).collect();
+        assert_eq!(ids, vec![pasted, recent]);
+    }
+}
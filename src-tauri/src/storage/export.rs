@@ -0,0 +1,219 @@
+//! EveryPaste - History export module
+//!
+//! Serializes clipboard history records to JSON files and reads them back,
+//! and writes a CSV summary for spreadsheets
+
+use std::path::Path;
+use std::fs;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::clipboard::{ClipboardItem, ContentType};
+use super::database::{self, DatabaseError};
+
+/// Export error type
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError {
+    #[error("Database error: {0}")]
+    Database(#[from] DatabaseError),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("CSV error: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("Base64 error: {0}")]
+    Base64(#[from] base64::DecodeError),
+}
+
+/// Exported clipboard record
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportedItem {
+    #[serde(flatten)]
+    pub item: ClipboardItem,
+    /// Image file content (Base64), embedded so the export is self-contained
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_data: Option<String>,
+}
+
+/// Export the given records to a JSON file
+///
+/// Returns the number of exported records
+pub fn export_items(items: Vec<ClipboardItem>, data_dir: &Path, path: &Path) -> Result<usize, ExportError> {
+    use base64::Engine;
+
+    let exported: Vec<ExportedItem> = items
+        .into_iter()
+        .map(|item| {
+            let image_data = item.image_path.as_ref().and_then(|image_path| {
+                match fs::read(data_dir.join(image_path)) {
+                    Ok(bytes) => Some(base64::engine::general_purpose::STANDARD.encode(bytes)),
+                    Err(e) => {
+                        log::warn!("Failed to read image {} for export: {}", image_path, e);
+                        None
+                    }
+                }
+            });
+            ExportedItem { item, image_data }
+        })
+        .collect();
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let json = serde_json::to_string_pretty(&exported)?;
+    fs::write(path, json)?;
+
+    Ok(exported.len())
+}
+
+/// Export the whole history to a JSON file
+pub fn export_history(data_dir: &Path, path: &Path) -> Result<usize, ExportError> {
+    let items = database::get_all_items(None, None)?;
+    export_items(items, data_dir, path)
+}
+
+/// Write records as CSV (one row per record, images as their relative path)
+fn write_csv<W: std::io::Write>(items: &[ClipboardItem], writer: W) -> Result<(), ExportError> {
+    let mut csv = csv::WriterBuilder::new()
+        .terminator(csv::Terminator::CRLF)
+        .from_writer(writer);
+
+    csv.write_record(["id", "content_type", "preview", "created_at", "is_pinned", "image_path"])?;
+    for item in items {
+        csv.write_record([
+            item.id.to_string(),
+            item.content_type.as_str().to_string(),
+            item.preview.clone(),
+            item.created_at.to_rfc3339(),
+            (item.is_pinned as i32).to_string(),
+            item.image_path.clone().unwrap_or_default(),
+        ])?;
+    }
+    csv.flush()?;
+    Ok(())
+}
+
+/// Export the whole history to a CSV file
+///
+/// Returns the number of exported records
+pub fn export_csv(path: &Path) -> Result<usize, ExportError> {
+    use std::io::Write;
+
+    let items = database::get_all_items(None, None)?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = std::io::BufWriter::new(fs::File::create(path)?);
+    // BOM so spreadsheet apps detect UTF-8
+    file.write_all("\u{feff}".as_bytes())?;
+    write_csv(&items, &mut file)?;
+    file.flush()?;
+
+    Ok(items.len())
+}
+
+/// Selection of records to export (unset fields don't filter)
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ExportFilter {
+    pub content_type: Option<ContentType>,
+    /// Earliest creation time (inclusive)
+    pub from: Option<DateTime<Utc>>,
+    /// Latest creation time (inclusive)
+    pub to: Option<DateTime<Utc>>,
+    /// Text search query
+    pub query: Option<String>,
+}
+
+/// Export the records matching a filter to a JSON file (same format as export_history)
+pub fn export_filtered(filter: &ExportFilter, data_dir: &Path, path: &Path) -> Result<usize, ExportError> {
+    let items = database::get_filtered_items(
+        filter.content_type.as_ref(),
+        filter.from,
+        filter.to,
+        filter.query.as_deref(),
+    )?;
+    export_items(items, data_dir, path)
+}
+
+/// Import records from a JSON file written by export_history into the active profile
+///
+/// Records already in the history (same hash or image) and encrypted records
+/// (their ciphertext isn't exported) are skipped. Embedded images are written
+/// to the images directory. Without `merge` the history is cleared first.
+///
+/// Returns the number of inserted records
+pub fn import_history(data_dir: &Path, path: &Path, merge: bool) -> Result<usize, ExportError> {
+    use base64::Engine;
+
+    let json = fs::read_to_string(path)?;
+    let exported: Vec<ExportedItem> = serde_json::from_str(&json)?;
+
+    if !merge {
+        database::clear_all_items(data_dir)?;
+    }
+
+    let images_dir = data_dir.join("images");
+    let mut inserted = 0;
+    for ExportedItem { mut item, image_data } in exported {
+        if item.encrypted || database::hash_exists(&item.hash)? {
+            continue;
+        }
+        if let Some(key) = &item.image_key {
+            if database::get_item_id_by_image_key(key)?.is_some() {
+                continue;
+            }
+        }
+
+        if let Some(image_data) = image_data {
+            let bytes = match base64::engine::general_purpose::STANDARD.decode(image_data) {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    log::warn!("Skipping record with invalid image data: {}", e);
+                    continue;
+                }
+            };
+            fs::create_dir_all(&images_dir)?;
+            let filename = format!("{}.png", uuid::Uuid::new_v4());
+            fs::write(images_dir.join(&filename), bytes)?;
+            item.image_path = Some(format!("images/{}", filename));
+        }
+
+        database::insert_clipboard_item(&item)?;
+        inserted += 1;
+    }
+
+    Ok(inserted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_fields_round_trip() {
+        let mut item = ClipboardItem::new_text(7, "x".to_string(), "hash".to_string());
+        item.preview = "a,\"b\"\nc".to_string();
+        item.image_path = Some("images/1.png".to_string());
+
+        let mut data = Vec::new();
+        write_csv(std::slice::from_ref(&item), &mut data).unwrap();
+        assert!(String::from_utf8_lossy(&data).contains("\"a,\"\"b\"\"\nc\""));
+
+        let mut reader = csv::Reader::from_reader(data.as_slice());
+        assert_eq!(
+            reader.headers().unwrap(),
+            vec!["id", "content_type", "preview", "created_at", "is_pinned", "image_path"]
+        );
+        let rows: Vec<csv::StringRecord> = reader.records().map(Result::unwrap).collect();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(&rows[0][0], "7");
+        assert_eq!(&rows[0][2], "a,\"b\"\nc");
+        assert_eq!(&rows[0][3], item.created_at.to_rfc3339());
+        assert_eq!(&rows[0][5], "images/1.png");
+    }
+}
@@ -0,0 +1,195 @@
+//! EveryPaste - External history import
+//!
+//! Reads clipboard history saved by other clipboard managers (Ditto
+//! database, CSV exports) into the active profile
+
+use std::path::Path;
+use std::fs;
+
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+use rusqlite::{Connection, OpenFlags};
+use serde::Serialize;
+
+use crate::clipboard::{link, ClipboardItem, ClipboardMonitor, ContentType};
+use super::database::{self, DatabaseError};
+
+/// Import error type
+#[derive(Debug, thiserror::Error)]
+pub enum ImportError {
+    #[error("Database error: {0}")]
+    Database(#[from] DatabaseError),
+    #[error("Source database error: {0}")]
+    Source(#[from] rusqlite::Error),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("CSV error: {0}")]
+    Csv(String),
+}
+
+/// Supported source formats
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExternalFormat {
+    /// Ditto database file (Ditto.db)
+    Ditto,
+    /// CSV with a header row and a text/content column
+    Csv,
+}
+
+impl ExternalFormat {
+    /// Parse format name ("ditto" or "csv")
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "ditto" => Some(ExternalFormat::Ditto),
+            "csv" => Some(ExternalFormat::Csv),
+            _ => None,
+        }
+    }
+}
+
+/// Result of an import
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    pub imported: usize,
+    /// Empty entries and entries already in the history
+    pub skipped: usize,
+}
+
+/// A text entry read from the source
+struct ExternalEntry {
+    text: String,
+    created_at: Option<DateTime<Utc>>,
+}
+
+/// Read text clips from a Ditto database (groups are skipped)
+fn read_ditto(path: &Path) -> Result<Vec<ExternalEntry>, ImportError> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let mut stmt = conn.prepare("SELECT mText, lDate FROM Main WHERE bIsGroup = 0 ORDER BY lDate")?;
+    let entries = stmt
+        .query_map([], |row| {
+            Ok(ExternalEntry {
+                text: row.get::<_, Option<String>>(0)?.unwrap_or_default(),
+                created_at: row.get::<_, Option<i64>>(1)?.and_then(|secs| Utc.timestamp_opt(secs, 0).single()),
+            })
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(entries)
+}
+
+/// Split CSV content into records (RFC 4180 quoting, a UTF-8 BOM is ignored)
+fn parse_csv(content: &str) -> Result<Vec<Vec<String>>, ImportError> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            _ => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(ImportError::Csv("Unterminated quoted field".to_string()));
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Parse a CSV date (RFC 3339, or "YYYY-MM-DD HH:MM:SS" in local time)
+fn parse_csv_date(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+        .ok()
+        .and_then(|naive| Local.from_local_datetime(&naive).single())
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+/// Read entries from a CSV file
+///
+/// The text comes from the "text", "content" or "clip" column (the first
+/// column if none is named so), the date from an optional "date",
+/// "created_at" or "time" column
+fn read_csv(path: &Path) -> Result<Vec<ExternalEntry>, ImportError> {
+    let records = parse_csv(&fs::read_to_string(path)?)?;
+    let Some((header, rows)) = records.split_first() else {
+        return Ok(Vec::new());
+    };
+
+    let column = |names: &[&str]| {
+        header.iter().position(|h| names.iter().any(|name| h.trim().eq_ignore_ascii_case(name)))
+    };
+    let text_column = column(&["text", "content", "clip"]).unwrap_or(0);
+    let date_column = column(&["date", "created_at", "time"]);
+
+    Ok(rows
+        .iter()
+        .map(|row| ExternalEntry {
+            text: row.get(text_column).cloned().unwrap_or_default(),
+            created_at: date_column.and_then(|i| row.get(i)).and_then(|value| parse_csv_date(value)),
+        })
+        .collect())
+}
+
+/// Import the text entries of an external history file into the active profile
+///
+/// Entries whose content is already in the history are skipped
+pub fn import_external(format: ExternalFormat, path: &Path, preview_len: usize) -> Result<ImportReport, ImportError> {
+    let entries = match format {
+        ExternalFormat::Ditto => read_ditto(path)?,
+        ExternalFormat::Csv => read_csv(path)?,
+    };
+
+    let mut report = ImportReport { imported: 0, skipped: 0 };
+    for entry in entries {
+        if entry.text.trim().is_empty() {
+            report.skipped += 1;
+            continue;
+        }
+
+        let hash = ClipboardMonitor::compute_hash(entry.text.as_bytes());
+        if database::get_item_id_by_hash(&hash)?.is_some() {
+            report.skipped += 1;
+            continue;
+        }
+
+        let is_link = link::is_single_url(&entry.text);
+        let mut item = ClipboardItem::new_text_with_len(0, entry.text, hash, preview_len);
+        if is_link {
+            item.content_type = ContentType::Link;
+        }
+        if let Some(created_at) = entry.created_at {
+            item.created_at = created_at;
+            item.updated_at = created_at;
+        }
+        database::insert_clipboard_item(&item)?;
+        report.imported += 1;
+    }
+
+    Ok(report)
+}
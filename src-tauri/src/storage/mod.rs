@@ -2,6 +2,14 @@
 //! 
 //! Provides data persistence functionality
 
+pub mod cache;
+pub mod crypto;
 pub mod database;
+pub mod export;
+pub mod import;
 
+pub use cache::*;
+pub use crypto::*;
 pub use database::*;
+pub use export::*;
+pub use import::*;
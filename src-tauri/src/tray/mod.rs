@@ -2,12 +2,20 @@
 //! 
 //! Manages system tray icon and menu
 
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
 use tauri::{
     AppHandle, Manager, Emitter,
     menu::{MenuBuilder, MenuItemBuilder},
-    tray::{TrayIconBuilder, TrayIconEvent, MouseButton, MouseButtonState},
+    tray::{TrayIcon, TrayIconBuilder, TrayIconEvent, MouseButton, MouseButtonState},
 };
 
+/// Default tray tooltip
+pub const DEFAULT_TOOLTIP: &str = "EveryPaste - 剪贴板管理器";
+
+/// Tray icon handle (kept so the tooltip can be updated)
+static TRAY: Lazy<Mutex<Option<TrayIcon>>> = Lazy::new(|| Mutex::new(None));
+
 /// Create system tray
 pub fn create_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
     // Create tray menu
@@ -25,9 +33,9 @@ pub fn create_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
 
     // Create tray icon
     // Use app-level icon (from tauri.conf.json bundle.icon configuration)
-    let _tray = TrayIconBuilder::new()
+    let tray = TrayIconBuilder::new()
         .menu(&menu)
-        .tooltip("EveryPaste - 剪贴板管理器")
+        .tooltip(DEFAULT_TOOLTIP)
         .icon(app.default_window_icon().cloned().unwrap_or_else(|| {
             // If no default icon, create a simple placeholder icon
             tauri::image::Image::new_owned(
@@ -45,6 +53,7 @@ pub fn create_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
                     if let Some(window) = app.get_webview_window("main") {
                         let _ = window.show();
                         let _ = window.set_focus();
+                        crate::config::save_window_visible(true);
                         // Emit window shown event for animation
                         let _ = app.emit("window-shown", ());
                         let _ = app.emit("open-settings", ());
@@ -62,16 +71,27 @@ pub fn create_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
             }
         })
         .build(app)?;
+    *TRAY.lock() = Some(tray);
 
     log::info!("Tray icon created");
     Ok(())
 }
 
+/// Set the tray tooltip (None restores the default)
+pub fn set_tooltip(text: Option<&str>) -> Result<(), String> {
+    let tray = TRAY.lock();
+    let tray = tray.as_ref().ok_or("Tray icon not created")?;
+    tray.set_tooltip(Some(text.unwrap_or(DEFAULT_TOOLTIP)))
+        .map_err(|e| format!("Failed to set tray tooltip: {}", e))
+}
+
 /// Show window and emit animation event
 pub fn show_window(app: &AppHandle) {
+    crate::cancel_auto_hide();
     if let Some(window) = app.get_webview_window("main") {
         let _ = window.show();
         let _ = window.set_focus();
+        crate::config::save_window_visible(true);
         // Emit window shown event for animation
         let _ = app.emit("window-shown", ());
         let _ = app.emit("focus-first-item", ());
@@ -19,6 +19,7 @@ const state = {
     contextMenuItemId: null,  // Context menu associated item ID
     isRecordingShortcut: false, // Whether recording shortcut
     isWindowVisible: false, // Window visibility state tracking
+    isLingering: false,  // Window kept open after paste, waiting for auto-hide
 };
 
 // ============== DOM Element References ==============
@@ -141,6 +142,20 @@ function formatRelativeTime(dateStr) {
     return date.toLocaleDateString('zh-CN');
 }
 
+/**
+ * Format image dimensions and file size, e.g. "1920×1080, 240 KB"
+ */
+function formatImageInfo(item) {
+    const parts = [];
+    if (item.width && item.height) parts.push(`${item.width}×${item.height}`);
+    if (item.byte_size) {
+        parts.push(item.byte_size < 1024 * 1024
+            ? `${Math.max(1, Math.round(item.byte_size / 1024))} KB`
+            : `${(item.byte_size / (1024 * 1024)).toFixed(1)} MB`);
+    }
+    return parts.join(', ');
+}
+
 /**
  * Get content type display name
  */
@@ -149,6 +164,10 @@ function getContentTypeName(type) {
         'text': 'Text',
         'rich_text': 'Rich Text',
         'image': 'Image',
+        'link': 'Link',
+        'color': 'Color',
+        'file': 'Files',
+        'unsupported': 'Other',
     };
     return names[type] || '未知';
 }
@@ -175,6 +194,13 @@ function getContentTypeIcon(type) {
       <rect x="3" y="3" width="18" height="18" rx="2" ry="2"/>
       <circle cx="8.5" cy="8.5" r="1.5"/>
       <polyline points="21 15 16 10 5 21"/>
+    </svg>`,
+        'link': `<svg viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2">
+      <path d="M10 13a5 5 0 0 0 7.54.54l3-3a5 5 0 0 0-7.07-7.07l-1.72 1.71"/>
+      <path d="M14 11a5 5 0 0 0-7.54-.54l-3 3a5 5 0 0 0 7.07 7.07l1.71-1.71"/>
+    </svg>`,
+        'file': `<svg viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2">
+      <path d="M22 19a2 2 0 0 1-2 2H4a2 2 0 0 1-2-2V5a2 2 0 0 1 2-2h5l2 3h9a2 2 0 0 1 2 2z"/>
     </svg>`,
     };
     return icons[type] || icons['text'];
@@ -237,6 +263,8 @@ function renderClipboardList() {
                 console.warn('Image item missing thumbnail:', item.id);
                 iconHtml = `<div class="item-icon ${iconClass}">${getContentTypeIcon(item.content_type)}</div>`;
             }
+        } else if (item.content_type === 'color' && item.color_hex) {
+            iconHtml = `<div class="item-icon" style="background: ${item.color_hex}; border: 1px solid rgba(0, 0, 0, 0.15);"></div>`;
         } else {
             iconHtml = `<div class="item-icon ${iconClass}">${getContentTypeIcon(item.content_type)}</div>`;
         }
@@ -251,6 +279,8 @@ function renderClipboardList() {
           <div class="item-meta">
             <span class="item-type">${getContentTypeName(item.content_type)}</span>
             <span class="item-time">· ${formatRelativeTime(item.created_at)}</span>
+            ${item.source_app ? `<span class="item-source">· ${escapeHtml(item.source_app)}</span>` : ''}
+            ${formatImageInfo(item) ? `<span class="item-size">· ${formatImageInfo(item)}</span>` : ''}
           </div>
         </div>
       </div>
@@ -291,6 +321,11 @@ function updateSelectedIndex(newIndex) {
 
     state.selectedIndex = newIndex;
 
+    // Selecting another item restarts the post-paste auto-hide countdown
+    if (state.isLingering) {
+        invoke('reset_auto_hide');
+    }
+
     const items = elements.clipboardList.querySelectorAll('.clipboard-item');
     items.forEach((el, idx) => {
         el.classList.toggle('selected', idx === newIndex);
@@ -324,7 +359,7 @@ async function loadClipboardHistory() {
  */
 async function searchClipboard(query) {
     try {
-        const result = await invoke('search_clipboard', { query, limit: null });
+        const result = await invoke('search_ranked', { query, limit: null });
         if (result.success) {
             state.items = result.data;
             state.selectedIndex = 0;
@@ -340,13 +375,23 @@ async function searchClipboard(query) {
  */
 async function pasteItem(id, asPlainText = false) {
     try {
-        const result = await invoke('paste_item', { id, asPlainText });
+        let result = await invoke('paste_item', { id, asPlainText });
+        if (result.success && result.data.requires_confirmation) {
+            const sizeMb = (result.data.size_bytes / (1024 * 1024)).toFixed(1);
+            if (!confirm(`This item is about ${sizeMb} MB and may freeze the target app. Paste anyway?`)) {
+                return;
+            }
+            result = await invoke('confirm_large_paste', { id, asPlainText });
+        }
         if (result.success) {
+            // Keep the window open after the paste when a linger time is set
+            state.isLingering = !!(state.settings && state.settings.post_paste_linger_ms);
             // Call backend to restore focus and auto-paste
             try {
                 await invoke('restore_and_paste');
             } catch (e) {
                 console.warn('restore_and_paste failed, falling back to hideWindow:', e);
+                state.isLingering = false;
                 await hideWindow();
             }
         } else {
@@ -376,6 +421,7 @@ async function hideWindow() {
 
         // Mark as invisible
         state.isWindowVisible = false;
+        state.isLingering = false;
 
         // Delay removing animation class
         setTimeout(() => {
@@ -416,7 +462,7 @@ async function clearAllHistory() {
         const result = await invoke('clear_all_history');
         if (result.success) {
             await loadClipboardHistory();
-            showToast('已清空所有历史记录');
+            showToast(result.data ? '已清空所有历史记录（已自动备份）' : '已清空所有历史记录');
         }
     } catch (error) {
         console.error('Clear all failed:', error);
@@ -543,6 +589,65 @@ async function checkFirstRun() {
     }
 }
 
+/**
+ * Check whether storage failed to initialize and offer a retry
+ * Returns true if storage is unavailable
+ */
+async function checkStorageError() {
+    try {
+        const locked = await invoke('is_database_locked');
+        if (locked.success && locked.data) {
+            await handleDatabaseLocked();
+            return true;
+        }
+        const result = await invoke('get_storage_error');
+        if (result.success && result.data) {
+            await handleStorageError(result.data);
+            return true;
+        }
+    } catch (error) {
+        console.error('Failed to check storage error:', error);
+    }
+    return false;
+}
+
+/**
+ * Show storage error and retry initialization on confirm
+ */
+async function handleStorageError(message) {
+    const retry = await showConfirmDialog(
+        '存储不可用',
+        `无法打开剪贴板数据库，当前不会记录剪贴板内容。\n${message}`,
+        '重试',
+        '忽略'
+    );
+    if (!retry) return;
+
+    const result = await invoke('retry_storage_init');
+    if (result.success) {
+        showToast('数据库已恢复');
+        await loadSettings();
+        await loadClipboardHistory();
+    }
+    // On failure the backend emits storage-error again, which reopens this dialog
+}
+
+/**
+ * Ask for the database password and unlock the encrypted database
+ */
+async function handleDatabaseLocked(message) {
+    const password = window.prompt(message === 'Wrong database password' ? '密码错误，请重新输入数据库密码' : '请输入数据库密码');
+    if (password === null) return;
+
+    const result = await invoke('unlock_database', { password });
+    if (result.success) {
+        showToast('数据库已解锁');
+        await loadSettings();
+        await loadClipboardHistory();
+    }
+    // On failure the backend emits database-locked again, which asks again
+}
+
 /**
  * Show welcome panel
  */
@@ -687,7 +792,7 @@ function handleShortcutKey(e) {
 function initEventListeners() {
     // Auto-hide on blur (frontend implementation with debounce)
     window.addEventListener('blur', () => {
-        if (state.isWindowVisible) {
+        if (state.isWindowVisible && !state.isLingering) {
             setTimeout(() => {
                 // If document no longer has focus (ensure focus hasn't moved to internal element)
                 if (!document.hasFocus()) {
@@ -955,6 +1060,7 @@ async function initTauriListeners() {
     });
 
     await listen('window-shown', () => {
+        state.isLingering = false;
         playShowAnimation();
         elements.searchInput.focus();
     });
@@ -967,6 +1073,24 @@ async function initTauriListeners() {
         });
     });
 
+    await listen('window-auto-hidden', () => {
+        state.isLingering = false;
+        state.isWindowVisible = false;
+        elements.app.classList.remove('window-show', 'window-hide');
+    });
+
+    await listen('disk-low', (event) => {
+        showToast(`磁盘空间不足（剩余 ${event.payload.free_mb} MB），已暂停记录`, 4000);
+    });
+
+    await listen('storage-error', (event) => {
+        handleStorageError(event.payload);
+    });
+
+    await listen('database-locked', (event) => {
+        handleDatabaseLocked(event.payload);
+    });
+
     await listen('open-settings', () => {
         showSettingsPanel();
     });
@@ -981,8 +1105,10 @@ async function init() {
     initEventListeners();
     await initTauriListeners();
 
-    // Check first run and show welcome page
-    await checkFirstRun();
+    // Check storage, then first run (welcome page)
+    if (!(await checkStorageError())) {
+        await checkFirstRun();
+    }
 
     console.log('EveryPaste initialized');
 }